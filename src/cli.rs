@@ -0,0 +1,360 @@
+use std::path::PathBuf;
+
+use pdf_tools::{BookmarkMode, CompressOptions, CompressOutcome, CompressPreset, CompressReport, ImageFormat, ImageOptions, MergeOptions, Operation, Progress, RotateOptions, SplitMode, SplitOptions, TextFormat, TextMode, TextOptions};
+
+const USAGE: &str = "Usage: pdf-tools <command> [options]
+
+Commands:
+  merge -o <output.pdf> <input.pdf>... [--bookmarks nested|flat] [--pages '1;3-12;;2-1']
+  split <input.pdf> -o <output-dir> [--mode pages|every:N|ranges:1-4,9-12|odd|even|bookmarks:DEPTH|size:10MB|blank[:render,drop]] [--name '{stem}_{start}-{end}.pdf']
+  rotate <input.pdf> -o <output.pdf> --degrees <90|-90|180|270> [--pages 1-3,5]
+  extract-text <input.pdf> -o <output.txt|output-dir> [--mode plain|layout] [--format text|json|csv] [--pages 1-3,5] [--per-page]
+  compress <input.pdf> -o <output.pdf> [--preset screen|ebook|print|prepress] [--quality 1-100] [--max-dpi N|none] [--min-size 10KB]
+  optimize <input.pdf> -o <output.pdf>
+  to-images <input.pdf> -o <output-dir> [--format png|jpeg]
+  help
+
+Run without arguments to start the graphical interface.
+Errors are reported on stderr as a single JSON object.";
+
+/// Exit code for a failed operation.
+const EXIT_FAILURE: i32 = 1;
+/// Exit code for invalid command line usage.
+const EXIT_USAGE: i32 = 2;
+
+#[derive(Debug)]
+struct CliError {
+    kind: &'static str,
+    message: String,
+    code: i32,
+}
+
+impl CliError {
+    fn usage(message: impl Into<String>) -> Self {
+        CliError { kind: "usage", message: message.into(), code: EXIT_USAGE }
+    }
+}
+
+impl From<pdf_tools::Error> for CliError {
+    fn from(e: pdf_tools::Error) -> Self {
+        let code = match e {
+            pdf_tools::Error::InvalidArgument(_) => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        };
+        CliError { kind: e.kind(), message: e.to_string(), code }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Args {
+    positional: Vec<String>,
+    output: Option<String>,
+    pages: Option<String>,
+    degrees: Option<String>,
+    format: Option<String>,
+    bookmarks: Option<String>,
+    mode: Option<String>,
+    name: Option<String>,
+    preset: Option<String>,
+    quality: Option<String>,
+    max_dpi: Option<String>,
+    min_size: Option<String>,
+    per_page: bool,
+}
+
+impl Args {
+    fn parse(raw: &[String]) -> Result<Self, CliError> {
+        let mut args = Args::default();
+        let mut it = raw.iter();
+        while let Some(arg) = it.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((f, v)) if f.starts_with("--") => (f, Some(v.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let slot = match flag {
+                "-o" | "--output" => &mut args.output,
+                "--pages" => &mut args.pages,
+                "--degrees" => &mut args.degrees,
+                "--format" => &mut args.format,
+                "--bookmarks" => &mut args.bookmarks,
+                "--mode" => &mut args.mode,
+                "--name" => &mut args.name,
+                "--preset" => &mut args.preset,
+                "--quality" => &mut args.quality,
+                "--max-dpi" => &mut args.max_dpi,
+                "--min-size" => &mut args.min_size,
+                "--per-page" if inline.is_none() => {
+                    args.per_page = true;
+                    continue;
+                }
+                f if f.starts_with('-') && f.len() > 1 => {
+                    return Err(CliError::usage(format!("Unknown option: {}", f)));
+                }
+                _ => {
+                    args.positional.push(arg.clone());
+                    continue;
+                }
+            };
+            let value = match inline {
+                Some(v) => v,
+                None => it.next().cloned().ok_or_else(|| CliError::usage(format!("Missing value for {}", flag)))?,
+            };
+            *slot = Some(value);
+        }
+        Ok(args)
+    }
+
+    fn output(&self) -> Result<PathBuf, CliError> {
+        self.output.as_ref().map(PathBuf::from).ok_or_else(|| CliError::usage("Missing -o/--output"))
+    }
+
+    fn single_input(&self) -> Result<PathBuf, CliError> {
+        match self.positional.as_slice() {
+            [one] => Ok(PathBuf::from(one)),
+            [] => Err(CliError::usage("Missing input file")),
+            _ => Err(CliError::usage("Expected exactly one input file")),
+        }
+    }
+}
+
+/// Run a command line invocation and return the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    match dispatch(args) {
+        Ok(Some(report)) => {
+            println!("{}", report);
+            0
+        }
+        Ok(None) => 0,
+        Err(e) => {
+            eprintln!("{}", serde_json::json!({ "status": "error", "error": { "kind": e.kind, "message": e.message } }));
+            if e.code == EXIT_USAGE {
+                eprintln!("{}", USAGE);
+            }
+            e.code
+        }
+    }
+}
+
+/// Run a command and return the JSON report to print on success.
+fn dispatch(raw: &[String]) -> Result<Option<serde_json::Value>, CliError> {
+    let (command, rest) = raw.split_first().ok_or_else(|| CliError::usage("Missing command"))?;
+    if matches!(command.as_str(), "help" | "-h" | "--help") {
+        println!("{}", USAGE);
+        return Ok(None);
+    }
+    let args = Args::parse(rest)?;
+    let operation = match command.as_str() {
+        "merge" => {
+            let bookmarks = match args.bookmarks.as_deref() {
+                Some(b) => b.parse::<BookmarkMode>()?,
+                None => BookmarkMode::default(),
+            };
+            // one selection per input, separated by ';'; an empty one takes every page
+            let pages = match args.pages.as_deref() {
+                Some(spec) => spec
+                    .split(';')
+                    .map(|s| if s.trim().is_empty() { Ok(None) } else { pdf_tools::parse_page_sequence(s).map(Some) })
+                    .collect::<pdf_tools::Result<Vec<_>>>()?,
+                None => Vec::new(),
+            };
+            Operation::Merge(MergeOptions { bookmarks, pages })
+        }
+        "split" => {
+            let mode = match args.mode.as_deref() {
+                Some(m) => m.parse::<SplitMode>()?,
+                None => SplitMode::default(),
+            };
+            Operation::Split(SplitOptions { mode, name_template: args.name.clone() })
+        }
+        "rotate" => {
+            let degrees = args
+                .degrees
+                .as_deref()
+                .ok_or_else(|| CliError::usage("Missing --degrees"))?
+                .parse::<i32>()
+                .map_err(|_| CliError::usage("--degrees must be an integer"))?;
+            let pages = match args.pages.as_deref() {
+                Some(spec) => Some(pdf_tools::parse_pages(spec)?),
+                None => None,
+            };
+            Operation::Rotate(RotateOptions { degrees, pages })
+        }
+        "extract-text" => {
+            let mode = match args.mode.as_deref() {
+                Some(m) => m.parse::<TextMode>()?,
+                None => TextMode::default(),
+            };
+            let format = match args.format.as_deref() {
+                Some(f) => f.parse::<TextFormat>()?,
+                None => TextFormat::default(),
+            };
+            let pages = match args.pages.as_deref() {
+                Some(spec) => Some(pdf_tools::parse_pages(spec)?),
+                None => None,
+            };
+            Operation::ExtractText(TextOptions { mode, format, pages, per_page: args.per_page })
+        }
+        "compress" => {
+            // the preset gives the defaults for the settings not given
+            let mut options = match args.preset.as_deref() {
+                Some(p) => p.parse::<CompressPreset>()?.options(),
+                None => CompressOptions::default(),
+            };
+            if let Some(quality) = args.quality.as_deref() {
+                options.quality = quality.parse().map_err(|_| CliError::usage("--quality must be an integer from 1 to 100"))?;
+            }
+            match args.max_dpi.as_deref() {
+                Some("none") => options.max_dpi = None,
+                Some(dpi) => options.max_dpi = Some(dpi.parse().map_err(|_| CliError::usage("--max-dpi must be a positive integer or none"))?),
+                None => {}
+            }
+            if let Some(size) = args.min_size.as_deref() {
+                options.min_image_size = pdf_tools::parse_size(size)?;
+            }
+            Operation::Compress(options)
+        }
+        "optimize" => Operation::Optimize,
+        "to-images" => {
+            let format = match args.format.as_deref() {
+                Some(f) => f.parse::<ImageFormat>()?,
+                None => ImageFormat::default(),
+            };
+            Operation::ConvertToImages(ImageOptions { format })
+        }
+        other => return Err(CliError::usage(format!("Unknown command: {}", other))),
+    };
+
+    let inputs = if operation.kind().accepts_multiple_inputs() {
+        if args.positional.is_empty() {
+            return Err(CliError::usage("Missing input files"));
+        }
+        args.positional.iter().map(PathBuf::from).collect()
+    } else {
+        vec![args.single_input()?]
+    };
+    let out = args.output()?;
+    let mut report = serde_json::json!({ "status": "ok", "output": out.to_string_lossy() });
+    match &operation {
+        // split lists its parts, e.g. to check their sizes against an upload limit
+        Operation::Split(o) => {
+            operation.validate()?;
+            let parts = pdf_tools::split(&inputs[0], &out, o, &Progress::new())?;
+            report["parts"] = parts
+                .iter()
+                .map(|p| serde_json::json!({ "path": p.path.to_string_lossy(), "pages": p.pages, "size": p.size }))
+                .collect();
+        }
+        // compress and optimize tell whether they saved anything, as they may fall back
+        // to a copy
+        Operation::Compress(o) => {
+            operation.validate()?;
+            add_savings(&mut report, &pdf_tools::compress(&inputs[0], &out, o, &Progress::new())?);
+        }
+        Operation::Optimize => add_savings(&mut report, &pdf_tools::optimize(&inputs[0], &out, &Progress::new())?),
+        _ => operation.run(&inputs, &out, &Progress::new())?,
+    }
+    Ok(Some(report))
+}
+
+fn add_savings(report: &mut serde_json::Value, result: &CompressReport) {
+    report["result"] = match result.outcome {
+        CompressOutcome::Images => "images",
+        CompressOutcome::Structure => "structure",
+        CompressOutcome::NoSavings => "no-savings",
+    }
+    .into();
+    report["input_size"] = result.input_size.into();
+    report["output_size"] = result.output_size.into();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn parse(args: &[&str]) -> Result<Args, CliError> {
+        Args::parse(&strings(args))
+    }
+
+    #[test]
+    fn parses_flags_inline_values_and_positionals() {
+        let args = parse(&["in.pdf", "-o", "out.pdf", "--pages=1-3", "--per-page", "--mode", "layout"]).unwrap();
+        assert_eq!(args.positional, ["in.pdf"]);
+        assert_eq!(args.output.as_deref(), Some("out.pdf"));
+        assert_eq!(args.pages.as_deref(), Some("1-3"));
+        assert_eq!(args.mode.as_deref(), Some("layout"));
+        assert!(args.per_page);
+        // a lone dash is a positional, e.g. for standard input
+        assert_eq!(parse(&["-"]).unwrap().positional, ["-"]);
+    }
+
+    #[test]
+    fn rejects_unknown_options_and_missing_values() {
+        let e = parse(&["in.pdf", "--bogus"]).unwrap_err();
+        assert_eq!((e.kind, e.code, e.message.as_str()), ("usage", EXIT_USAGE, "Unknown option: --bogus"));
+        let e = parse(&["in.pdf", "-o"]).unwrap_err();
+        assert_eq!((e.kind, e.code, e.message.as_str()), ("usage", EXIT_USAGE, "Missing value for -o"));
+        // a flag switch takes no value
+        assert!(parse(&["--per-page=yes"]).is_err());
+    }
+
+    #[test]
+    fn requires_output_and_one_input() {
+        let args = parse(&["in.pdf"]).unwrap();
+        assert_eq!(args.output().unwrap_err().message, "Missing -o/--output");
+        assert_eq!(parse(&[]).unwrap().single_input().unwrap_err().message, "Missing input file");
+        assert_eq!(parse(&["a.pdf", "b.pdf"]).unwrap().single_input().unwrap_err().code, EXIT_USAGE);
+    }
+
+    #[test]
+    fn maps_error_kinds_to_exit_codes() {
+        let e = CliError::from(pdf_tools::Error::InvalidArgument("No pages selected".into()));
+        assert_eq!((e.kind, e.code), ("invalid-argument", EXIT_USAGE));
+        let e = CliError::from(pdf_tools::Error::Malformed("no page tree".into()));
+        assert_eq!((e.kind, e.code), ("malformed", EXIT_FAILURE));
+        let e = CliError::from(pdf_tools::Error::Load { path: "missing.pdf".into(), source: std::io::ErrorKind::NotFound.into() });
+        assert_eq!((e.kind, e.code), ("load", EXIT_FAILURE));
+        assert_eq!(CliError::from(pdf_tools::Error::Cancelled).code, EXIT_FAILURE);
+    }
+
+    #[test]
+    fn dispatch_reports_usage_errors_before_touching_files() {
+        let e = dispatch(&strings(&["frobnicate", "in.pdf"])).unwrap_err();
+        assert_eq!((e.kind, e.code), ("usage", EXIT_USAGE));
+        let e = dispatch(&strings(&["rotate", "in.pdf", "-o", "out.pdf"])).unwrap_err();
+        assert_eq!(e.message, "Missing --degrees");
+        let e = dispatch(&strings(&["compress", "in.pdf", "-o", "out.pdf", "--quality", "0"])).unwrap_err();
+        assert_eq!((e.kind, e.code), ("invalid-argument", EXIT_USAGE));
+        let e = dispatch(&strings(&["merge", "-o", "out.pdf"])).unwrap_err();
+        assert_eq!(e.message, "Missing input files");
+        assert!(matches!(dispatch(&strings(&["help"])), Ok(None)));
+    }
+
+    #[test]
+    fn unknown_flag_values_are_usage_errors() {
+        for args in [
+            &["split", "in.pdf", "-o", "out", "--mode", "bogus"][..],
+            &["split", "in.pdf", "-o", "out", "--mode", "blank:twice"],
+            &["split", "in.pdf", "-o", "out", "--mode", "every:x"],
+            &["merge", "a.pdf", "-o", "out.pdf", "--bookmarks", "tree"],
+            &["extract-text", "in.pdf", "-o", "out.txt", "--mode", "fancy"],
+            &["extract-text", "in.pdf", "-o", "out.txt", "--format", "xml"],
+            &["to-images", "in.pdf", "-o", "out", "--format", "gif"],
+            &["compress", "in.pdf", "-o", "out.pdf", "--preset", "tiny"],
+        ] {
+            let e = dispatch(&strings(args)).unwrap_err();
+            assert_eq!((e.kind, e.code), ("invalid-argument", EXIT_USAGE), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn missing_input_is_a_failure_not_usage() {
+        let missing = std::env::temp_dir().join(format!("pdf-tools-cli-{}-missing.pdf", std::process::id()));
+        let out = missing.with_extension("out.pdf");
+        let e = dispatch(&strings(&["rotate", missing.to_str().unwrap(), "-o", out.to_str().unwrap(), "--degrees", "90"])).unwrap_err();
+        assert_eq!((e.kind, e.code), ("load", EXIT_FAILURE));
+    }
+}
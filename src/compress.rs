@@ -0,0 +1,260 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+
+use lopdf::{Document, Object, ObjectId, Stream};
+
+use crate::document::{load_document, write_atomically};
+use crate::error::{Error, Result};
+use crate::optimize::{deflate, optimize_document, to_bytes};
+use crate::progress::Progress;
+
+mod decode;
+mod jpeg;
+mod placement;
+
+/// Ready-made settings for [`compress`], named after Ghostscript's `-dPDFSETTINGS`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompressPreset {
+    /// Smallest files for reading on screen: 72 dpi, JPEG quality 50.
+    Screen,
+    /// 150 dpi, JPEG quality 75.
+    #[default]
+    Ebook,
+    /// 300 dpi, JPEG quality 85.
+    Print,
+    /// 300 dpi, JPEG quality 95, and only large images touched.
+    Prepress,
+}
+
+impl CompressPreset {
+    pub fn options(self) -> CompressOptions {
+        let (quality, max_dpi, min_image_size) = match self {
+            CompressPreset::Screen => (50, 72, 1 << 10),
+            CompressPreset::Ebook => (75, 150, 4 << 10),
+            CompressPreset::Print => (85, 300, 16 << 10),
+            CompressPreset::Prepress => (95, 300, 64 << 10),
+        };
+        CompressOptions { quality, max_dpi: Some(max_dpi), min_image_size }
+    }
+}
+
+impl std::str::FromStr for CompressPreset {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "screen" => Ok(CompressPreset::Screen),
+            "ebook" => Ok(CompressPreset::Ebook),
+            "print" | "printer" => Ok(CompressPreset::Print),
+            "prepress" => Ok(CompressPreset::Prepress),
+            other => Err(Error::InvalidArgument(format!("Unknown compression preset: {}", other))),
+        }
+    }
+}
+
+/// Settings for [`compress`]; the default is [`CompressPreset::Ebook`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressOptions {
+    /// JPEG quality (1-100) used when re-encoding images.
+    pub quality: u8,
+    /// Images drawn at more than this many pixels per inch of the page are downsampled
+    /// to it; `None` keeps every image's resolution.
+    pub max_dpi: Option<u32>,
+    /// Images stored in fewer bytes than this are left alone.
+    pub min_image_size: u64,
+}
+
+impl Default for CompressOptions {
+    fn default() -> Self {
+        CompressPreset::default().options()
+    }
+}
+
+/// What [`compress`] or [`crate::optimize`] did to make the file smaller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressOutcome {
+    /// Images were recompressed.
+    Images,
+    /// Only the structure was optimized, as with [`crate::optimize`]: no image got
+    /// meaningfully smaller, or the result did not.
+    Structure,
+    /// Nothing made the file smaller, so the output is a copy of the input.
+    NoSavings,
+}
+
+/// The result of [`compress`] and [`crate::optimize`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressReport {
+    pub outcome: CompressOutcome,
+    /// File sizes in bytes.
+    pub input_size: u64,
+    pub output_size: u64,
+}
+
+/// Downsample only images above this multiple of [`CompressOptions::max_dpi`], as
+/// resampling a little costs quality for next to no savings.
+const DOWNSAMPLE_THRESHOLD: f64 = 1.5;
+/// Images with at most this many colors are flat artwork, such as charts and logos,
+/// that JPEG would smear; they are kept lossless.
+const LINE_ART_COLORS: usize = 16;
+/// A JPEG re-encoding replaces an image only when it is at most this fraction of the
+/// stored size; a smaller gain is not worth another round of JPEG artifacts.
+const MAX_JPEG_RATIO: f64 = 0.9;
+
+/// Re-encode the embedded images of `input` as JPEG and write the result to `output`.
+///
+/// Images stored as JPEG are decoded as they are; raw samples and those compressed with
+/// Flate, LZW or RunLength (with or without a predictor) are read according to the
+/// image dictionary. Gray, RGB and CMYK images stay so, and indexed ones take their
+/// palette's color space. 1-bit images, line art and images with a color key mask are
+/// only recompressed losslessly; image masks and images in other encodings are kept.
+/// Soft masks are resized along with their image.
+///
+/// The document is then optimized and written as by [`crate::optimize`]. The file
+/// never grows: an image is only replaced when the new encoding is clearly smaller,
+/// and when the whole document still is not, the output falls back to
+/// [`CompressOutcome::Structure`] or [`CompressOutcome::NoSavings`].
+///
+/// The resolution of an image is how many of its pixels cover an inch of the page
+/// where it is drawn largest; images that no page draws keep their size.
+pub fn compress(input: &Path, output: &Path, options: &CompressOptions, progress: &Progress) -> Result<CompressReport> {
+    let mut doc = load_document(input)?;
+    let input_size = std::fs::metadata(input).map_err(|source| Error::Load { path: input.to_path_buf(), source })?.len();
+
+    let quality = options.quality.clamp(1, 100);
+    let drawn = placement::drawn_sizes(&doc);
+    // soft masks are resized along with their image rather than on their own
+    let soft_masks: HashSet<ObjectId> =
+        doc.objects.values().filter_map(|o| o.as_stream().ok()?.dict.get(b"SMask").ok()?.as_reference().ok()).collect();
+    let mut resized_masks = HashSet::new();
+    let mut recompressed = 0;
+
+    let object_ids: Vec<_> = doc.objects.keys().cloned().collect();
+    progress.add_total(object_ids.len() as u64);
+    for id in object_ids {
+        progress.check()?;
+        progress.step();
+        if soft_masks.contains(&id) {
+            continue;
+        }
+        let Some(Object::Stream(stream)) = doc.objects.get(&id) else { continue };
+        let dict = &stream.dict;
+        // `/Type` is optional on XObjects; the subtype is what tells an image
+        let is_image = dict.get(b"Subtype").and_then(Object::as_name).map(|n| n == b"Image").unwrap_or(false);
+        if !is_image || (stream.content.len() as u64) < options.min_image_size {
+            continue;
+        }
+        let Some(image) = decode::image_pixels(&doc, stream) else { continue };
+
+        // JPEG smears 1-bit scans and line art, and its approximate colors break color
+        // key masking, which matches colors exactly
+        let color_key = matches!(dict.get(b"Mask"), Ok(Object::Array(_)));
+        if image.bits == 1 || color_key || image.pixels.has_few_colors(LINE_ART_COLORS) {
+            if let Some(data) = lossless(&doc, stream) {
+                if let Some(Object::Stream(stream)) = doc.objects.get_mut(&id) {
+                    stream.set_content(data);
+                    stream.dict.set("Filter", Object::Name(b"FlateDecode".to_vec()));
+                    stream.dict.remove(b"DecodeParms");
+                    stream.allows_compression = false;
+                    progress.object_rewritten();
+                    recompressed += 1;
+                }
+            }
+            continue;
+        }
+
+        let (w, h) = image.pixels.dimensions();
+        let scale = match (options.max_dpi, drawn.get(&id)) {
+            (Some(max_dpi), Some(&(width, height))) if width > 0.0 && height > 0.0 => {
+                let dpi = (w as f64 / (width / 72.0)).min(h as f64 / (height / 72.0));
+                if dpi > max_dpi as f64 * DOWNSAMPLE_THRESHOLD {
+                    max_dpi as f64 / dpi
+                } else {
+                    1.0
+                }
+            }
+            _ => 1.0,
+        };
+        let new_w = ((w as f64) * scale).round().max(1.0) as u32;
+        let new_h = ((h as f64) * scale).round().max(1.0) as u32;
+        let resized = (new_w, new_h) != (w, h);
+        let pixels = if resized { image.pixels.resize(new_w, new_h) } else { image.pixels };
+        let Some(out) = jpeg::encode(&pixels, quality) else { continue };
+        if out.len() as f64 > stream.content.len() as f64 * MAX_JPEG_RATIO {
+            continue;
+        }
+        let soft_mask = dict.get(b"SMask").and_then(Object::as_reference).ok();
+
+        let Some(Object::Stream(stream)) = doc.objects.get_mut(&id) else { continue };
+        stream.set_content(out);
+        stream.dict.set("Filter", Object::Name(b"DCTDecode".to_vec()));
+        // the samples are written out as decoded, so these no longer apply
+        for key in [b"DecodeParms".as_slice(), b"Decode"] {
+            stream.dict.remove(key);
+        }
+        stream.dict.set("Width", Object::Integer(new_w as i64));
+        stream.dict.set("Height", Object::Integer(new_h as i64));
+        stream.dict.set("ColorSpace", image.color_space);
+        stream.dict.set("BitsPerComponent", Object::Integer(8));
+        stream.allows_compression = false;
+        progress.object_rewritten();
+        recompressed += 1;
+
+        if let Some(mask) = soft_mask.filter(|_| resized) {
+            if resized_masks.insert(mask) {
+                resize_soft_mask(&mut doc, mask, scale);
+            }
+        }
+    }
+
+    optimize_document(&mut doc, progress)?;
+    let mut outcome = if recompressed > 0 { CompressOutcome::Images } else { CompressOutcome::Structure };
+    let mut data = to_bytes(&doc, output)?;
+    if data.len() as u64 >= input_size && outcome == CompressOutcome::Images {
+        // the images did not pay for the rewrite; try again without touching them
+        progress.check()?;
+        let mut doc = load_document(input)?;
+        optimize_document(&mut doc, progress)?;
+        data = to_bytes(&doc, output)?;
+        outcome = CompressOutcome::Structure;
+    }
+    if data.len() as u64 >= input_size {
+        data = std::fs::read(input).map_err(|source| Error::Load { path: input.to_path_buf(), source })?;
+        outcome = CompressOutcome::NoSavings;
+    }
+    write_atomically(output, progress, |w| w.write_all(&data))?;
+    Ok(CompressReport { outcome, input_size, output_size: data.len() as u64 })
+}
+
+/// The unfiltered data of an image compressed with Flate, if that is smaller than the
+/// stream is now. The samples, and with them the color space and `/Decode`, stay as
+/// they are.
+fn lossless(doc: &Document, stream: &Stream) -> Option<Vec<u8>> {
+    let decode::Decoded::Raw(data) = decode::decode_stream(doc, stream)? else { return None };
+    let deflated = deflate(&data).ok()?;
+    (deflated.len() < stream.content.len()).then_some(deflated)
+}
+
+/// Resize the soft mask `id` by `scale`, as its image was, keeping it lossless.
+fn resize_soft_mask(doc: &mut Document, id: ObjectId, scale: f64) {
+    let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) else { return };
+    let Some(mask) = decode::image_pixels(doc, stream) else { return };
+    // a mask may have a resolution of its own, so it is scaled rather than matched
+    let (w, h) = mask.pixels.dimensions();
+    let (new_w, new_h) = (((w as f64) * scale).round().max(1.0) as u32, ((h as f64) * scale).round().max(1.0) as u32);
+    let decode::Pixels::Gray(resized) = mask.pixels.resize(new_w, new_h) else { return };
+    let Ok(data) = deflate(resized.as_raw()) else { return };
+    if let Ok(Object::Stream(stream)) = doc.get_object_mut(id) {
+        stream.set_content(data);
+        stream.dict.set("Filter", Object::Name(b"FlateDecode".to_vec()));
+        for key in [b"DecodeParms".as_slice(), b"Decode"] {
+            stream.dict.remove(key);
+        }
+        stream.dict.set("Width", Object::Integer(new_w as i64));
+        stream.dict.set("Height", Object::Integer(new_h as i64));
+        stream.dict.set("ColorSpace", Object::Name(b"DeviceGray".to_vec()));
+        stream.dict.set("BitsPerComponent", Object::Integer(8));
+        stream.allows_compression = false;
+    }
+}
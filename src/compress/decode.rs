@@ -0,0 +1,540 @@
+use std::collections::HashSet;
+use std::io::Read;
+
+use image::imageops::{self, FilterType};
+use image::{GrayImage, RgbImage, RgbaImage};
+use lopdf::{Dictionary, Document, Object, Stream};
+
+/// Images with more samples than this are left alone rather than decoded into memory.
+const MAX_SAMPLES: usize = 400_000_000;
+
+/// The data of a stream after undoing its filters.
+pub(super) enum Decoded {
+    /// JPEG data, the stream's last filter being `DCTDecode`.
+    Jpeg(Vec<u8>),
+    /// Unfiltered bytes.
+    Raw(Vec<u8>),
+}
+
+/// Color space of an image XObject.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum ColorSpace {
+    Gray,
+    Rgb,
+    Cmyk,
+    /// Samples are indexes into `palette`, which holds colors of `base` one after another.
+    Indexed { base: Box<ColorSpace>, palette: Vec<u8> },
+}
+
+impl ColorSpace {
+    fn components(&self) -> usize {
+        match self {
+            ColorSpace::Gray | ColorSpace::Indexed { .. } => 1,
+            ColorSpace::Rgb => 3,
+            ColorSpace::Cmyk => 4,
+        }
+    }
+}
+
+fn get<'a>(doc: &'a Document, dict: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+    doc.dereference(dict.get(key).ok()?).ok().map(|(_, o)| o)
+}
+
+fn integer(doc: &Document, dict: &Dictionary, key: &[u8]) -> Option<i64> {
+    get(doc, dict, key)?.as_i64().ok()
+}
+
+/// Undo the `FlateDecode`, `LZWDecode` and `RunLengthDecode` filters of `stream`, with
+/// their predictors, stopping at a final `DCTDecode`. `None` for other filters and for
+/// data that does not decode.
+pub(super) fn decode_stream(doc: &Document, stream: &Stream) -> Option<Decoded> {
+    let dict = &stream.dict;
+    let filters: Vec<&[u8]> = match get(doc, dict, b"Filter") {
+        None => Vec::new(),
+        Some(Object::Name(name)) => vec![name.as_slice()],
+        Some(Object::Array(names)) => names.iter().map(|n| doc.dereference(n).ok()?.1.as_name().ok()).collect::<Option<_>>()?,
+        Some(_) => return None,
+    };
+    let params: Vec<Option<&Dictionary>> = match get(doc, dict, b"DecodeParms") {
+        Some(Object::Dictionary(params)) => vec![Some(params)],
+        Some(Object::Array(params)) => params.iter().map(|p| doc.dereference(p).ok().and_then(|(_, p)| p.as_dict().ok())).collect(),
+        _ => Vec::new(),
+    };
+
+    let mut data = stream.content.clone();
+    for (i, filter) in filters.iter().enumerate() {
+        let params = params.get(i).copied().flatten();
+        data = match *filter {
+            b"FlateDecode" | b"Fl" => predict(inflate(&data)?, params)?,
+            b"LZWDecode" | b"LZW" => predict(lzw(&data, params)?, params)?,
+            b"RunLengthDecode" | b"RL" => run_length(&data),
+            b"DCTDecode" | b"DCT" if i + 1 == filters.len() => return Some(Decoded::Jpeg(data)),
+            _ => return None,
+        };
+    }
+    Some(Decoded::Raw(data))
+}
+
+fn inflate(data: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    // a truncated stream still gives the rows before the damage
+    match flate2::read::ZlibDecoder::new(data).read_to_end(&mut out) {
+        Ok(_) => Some(out),
+        Err(_) if !out.is_empty() => Some(out),
+        Err(_) => None,
+    }
+}
+
+fn lzw(data: &[u8], params: Option<&Dictionary>) -> Option<Vec<u8>> {
+    use weezl::{decode::Decoder, BitOrder};
+    let early_change = params.and_then(|p| p.get(b"EarlyChange").ok()).and_then(|e| e.as_i64().ok()).unwrap_or(1) != 0;
+    let mut decoder = if early_change { Decoder::with_tiff_size_switch(BitOrder::Msb, 8) } else { Decoder::new(BitOrder::Msb, 8) };
+    let mut out = Vec::new();
+    let result = decoder.into_stream(&mut out).decode_all(data);
+    (result.status.is_ok() || !out.is_empty()).then_some(out)
+}
+
+fn run_length(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while let Some(&length) = data.get(i) {
+        match length {
+            128 => break,
+            0..=127 => {
+                let end = (i + 2 + length as usize).min(data.len());
+                out.extend_from_slice(&data[i + 1..end]);
+                i = end;
+            }
+            _ => {
+                if let Some(&byte) = data.get(i + 1) {
+                    out.extend(std::iter::repeat_n(byte, 257 - length as usize));
+                }
+                i += 2;
+            }
+        }
+    }
+    out
+}
+
+/// Undo a TIFF (2) or PNG (10 and up) predictor.
+fn predict(data: Vec<u8>, params: Option<&Dictionary>) -> Option<Vec<u8>> {
+    let Some(params) = params else { return Some(data) };
+    let param = |key: &[u8], default: i64| params.get(key).ok().and_then(|v| v.as_i64().ok()).unwrap_or(default).max(0) as usize;
+    let predictor = param(b"Predictor", 1);
+    let (colors, bits, columns) = (param(b"Colors", 1), param(b"BitsPerComponent", 8), param(b"Columns", 1));
+    let row = (colors * bits * columns).div_ceil(8);
+    let pixel = (colors * bits).div_ceil(8).max(1);
+    match predictor {
+        0 | 1 => Some(data),
+        2 if bits == 8 && row > 0 => {
+            let mut data = data;
+            for line in data.chunks_mut(row) {
+                for i in colors..line.len() {
+                    line[i] = line[i].wrapping_add(line[i - colors]);
+                }
+            }
+            Some(data)
+        }
+        10.. if row > 0 => {
+            let mut out = Vec::with_capacity(data.len());
+            let mut previous = vec![0u8; row];
+            for line in data.chunks(row + 1) {
+                let (&kind, encoded) = line.split_first()?;
+                let mut current = encoded.to_vec();
+                current.resize(row, 0);
+                for i in 0..row {
+                    let left = if i >= pixel { current[i - pixel] } else { 0 };
+                    let up = previous[i];
+                    let up_left = if i >= pixel { previous[i - pixel] } else { 0 };
+                    current[i] = current[i].wrapping_add(match kind {
+                        0 => 0,
+                        1 => left,
+                        2 => up,
+                        3 => ((left as u16 + up as u16) / 2) as u8,
+                        4 => paeth(left, up, up_left),
+                        _ => return None,
+                    });
+                }
+                out.extend_from_slice(&current);
+                previous = current;
+            }
+            Some(out)
+        }
+        _ => None,
+    }
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// The color space `object` names, when it is one an image can be converted from.
+pub(super) fn color_space(doc: &Document, object: &Object) -> Option<ColorSpace> {
+    let object = doc.dereference(object).ok()?.1;
+    let name = match object {
+        Object::Name(name) => name.as_slice(),
+        Object::Array(array) => array.first()?.as_name().ok()?,
+        _ => return None,
+    };
+    let array = object.as_array().ok();
+    match name {
+        b"DeviceGray" | b"G" | b"CalGray" => Some(ColorSpace::Gray),
+        b"DeviceRGB" | b"RGB" | b"CalRGB" => Some(ColorSpace::Rgb),
+        b"DeviceCMYK" | b"CMYK" => Some(ColorSpace::Cmyk),
+        b"ICCBased" => {
+            let profile = doc.dereference(array?.get(1)?).ok()?.1.as_stream().ok()?;
+            match integer(doc, &profile.dict, b"N") {
+                Some(1) => Some(ColorSpace::Gray),
+                Some(3) => Some(ColorSpace::Rgb),
+                Some(4) => Some(ColorSpace::Cmyk),
+                _ => color_space(doc, profile.dict.get(b"Alternate").ok()?),
+            }
+        }
+        b"Indexed" | b"I" => {
+            let array = array?;
+            let base = color_space(doc, array.get(1)?)?;
+            if matches!(base, ColorSpace::Indexed { .. }) {
+                return None;
+            }
+            let colors = doc.dereference(array.get(2)?).ok()?.1.as_i64().ok()?.clamp(0, 255) as usize + 1;
+            let mut palette = match doc.dereference(array.get(3)?).ok()?.1 {
+                Object::String(bytes, _) => bytes.clone(),
+                Object::Stream(stream) => match decode_stream(doc, stream)? {
+                    Decoded::Raw(bytes) => bytes,
+                    Decoded::Jpeg(_) => return None,
+                },
+                _ => return None,
+            };
+            // indexes past `hival` are out of range; read them as black rather than fail
+            palette.truncate(colors * base.components());
+            palette.resize(256 * base.components(), 0);
+            Some(ColorSpace::Indexed { base: Box::new(base), palette })
+        }
+        _ => None,
+    }
+}
+
+/// Decoded samples of an image, 8 bits per component.
+pub(super) enum Pixels {
+    Gray(GrayImage),
+    Rgb(RgbImage),
+    /// Cyan, magenta, yellow and black in the four channels.
+    Cmyk(RgbaImage),
+}
+
+impl Pixels {
+    pub(super) fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Pixels::Gray(image) => image.dimensions(),
+            Pixels::Rgb(image) => image.dimensions(),
+            Pixels::Cmyk(image) => image.dimensions(),
+        }
+    }
+
+    pub(super) fn resize(&self, width: u32, height: u32) -> Pixels {
+        let filter = FilterType::Lanczos3;
+        match self {
+            Pixels::Gray(image) => Pixels::Gray(imageops::resize(image, width, height, filter)),
+            Pixels::Rgb(image) => Pixels::Rgb(imageops::resize(image, width, height, filter)),
+            Pixels::Cmyk(image) => Pixels::Cmyk(imageops::resize(image, width, height, filter)),
+        }
+    }
+
+    /// Whether the image has at most `limit` distinct colors, as flat artwork does.
+    pub(super) fn has_few_colors(&self, limit: usize) -> bool {
+        let mut seen = HashSet::new();
+        let mut few = |color: [u8; 4]| {
+            seen.insert(color);
+            seen.len() <= limit
+        };
+        match self {
+            Pixels::Gray(image) => image.pixels().all(|p| few([p[0], 0, 0, 0])),
+            Pixels::Rgb(image) => image.pixels().all(|p| few([p[0], p[1], p[2], 0])),
+            Pixels::Cmyk(image) => image.pixels().all(|p| few(p.0)),
+        }
+    }
+}
+
+/// An image XObject decoded for recompression.
+pub(super) struct Image {
+    pub(super) pixels: Pixels,
+    /// `/ColorSpace` for the decoded samples: the image's own, or the base of an indexed one.
+    pub(super) color_space: Object,
+    /// `/BitsPerComponent` as stored.
+    pub(super) bits: u8,
+}
+
+/// Map 8-bit samples through a `/Decode` array, e.g. `[1 0]` inverts a gray image.
+fn apply_decode(samples: &mut [u8], components: usize, decode: &[f64]) {
+    if decode.len() < 2 * components {
+        return;
+    }
+    for (i, sample) in samples.iter_mut().enumerate() {
+        let (low, high) = (decode[2 * (i % components)], decode[2 * (i % components) + 1]);
+        *sample = ((low + *sample as f64 / 255.0 * (high - low)).clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+}
+
+/// Decode JPEG data into its gray, RGB or CMYK samples as stored.
+fn decode_jpeg(data: &[u8]) -> Option<(u32, u32, usize, Vec<u8>)> {
+    let mut decoder = jpeg_decoder::Decoder::new(data);
+    let mut samples = decoder.decode().ok()?;
+    let info = decoder.info()?;
+    let components = match info.pixel_format {
+        jpeg_decoder::PixelFormat::L8 => 1,
+        jpeg_decoder::PixelFormat::RGB24 => 3,
+        jpeg_decoder::PixelFormat::CMYK32 => {
+            // the decoder inverts CMYK the way Adobe applications store it; PDF does not
+            for sample in &mut samples {
+                *sample = 255 - *sample;
+            }
+            4
+        }
+        jpeg_decoder::PixelFormat::L16 => return None,
+    };
+    Some((info.width as u32, info.height as u32, components, samples))
+}
+
+/// The pixels of an image XObject: JPEG data decoded, or raw samples read as its
+/// `/Width`, `/Height`, `/BitsPerComponent` and `/ColorSpace` describe them, in both
+/// cases with `/Decode` applied and indexed colors looked up. `None` for stencil masks,
+/// and for filters and color spaces that are not handled.
+pub(super) fn image_pixels(doc: &Document, stream: &Stream) -> Option<Image> {
+    let dict = &stream.dict;
+    if get(doc, dict, b"ImageMask").and_then(|m| m.as_bool().ok()) == Some(true) {
+        return None;
+    }
+    let space_object = dict.get(b"ColorSpace").ok()?;
+    let space = color_space(doc, space_object)?;
+    let bits = integer(doc, dict, b"BitsPerComponent").unwrap_or(8) as usize;
+    let decode: Vec<f64> = get(doc, dict, b"Decode")
+        .and_then(|d| d.as_array().ok())
+        .map(|d| d.iter().filter_map(|v| v.as_f64().ok().or_else(|| v.as_i64().ok().map(|i| i as f64))).collect())
+        .unwrap_or_default();
+
+    let (width, height, mut samples) = match decode_stream(doc, stream)? {
+        Decoded::Jpeg(data) => {
+            let (width, height, components, samples) = decode_jpeg(&data)?;
+            if components != space.components() || matches!(space, ColorSpace::Indexed { .. }) {
+                return None;
+            }
+            (width as usize, height as usize, samples)
+        }
+        Decoded::Raw(mut data) => {
+            let width = usize::try_from(integer(doc, dict, b"Width")?).ok()?;
+            let height = usize::try_from(integer(doc, dict, b"Height")?).ok()?;
+            let components = space.components();
+            if width == 0 || height == 0 || !matches!(bits, 1 | 2 | 4 | 8 | 16) || width.checked_mul(height)?.checked_mul(components)? > MAX_SAMPLES {
+                return None;
+            }
+            let row = (width * components * bits).div_ceil(8);
+            // a short stream, as left by a truncated scan, reads as black rows at the bottom
+            data.resize(row * height, 0);
+            let max = (1u32 << bits.min(8)) - 1;
+            let indexed = matches!(space, ColorSpace::Indexed { .. });
+            let mut samples: Vec<u8> = Vec::with_capacity(width * height * components);
+            for line in data.chunks(row) {
+                for i in 0..width * components {
+                    let value = match bits {
+                        8 => line[i],
+                        16 => line[2 * i],
+                        _ => {
+                            let bit = i * bits;
+                            (line[bit / 8] >> (8 - bits - bit % 8)) & ((1 << bits) - 1)
+                        }
+                    };
+                    // palette indexes stay as they are; other samples are scaled to 8 bits
+                    samples.push(if indexed || bits >= 8 { value } else { (value as u32 * 255 / max) as u8 });
+                }
+            }
+            (width, height, samples)
+        }
+    };
+
+    let (space, color_space) = match space {
+        ColorSpace::Indexed { base, palette } => {
+            let n = base.components();
+            samples = samples.iter().flat_map(|&i| palette[i as usize * n..(i as usize + 1) * n].to_vec()).collect();
+            let base_object = doc.dereference(space_object).ok()?.1.as_array().ok()?.get(1)?.clone();
+            (*base, base_object)
+        }
+        space => {
+            apply_decode(&mut samples, space.components(), &decode);
+            (space, space_object.clone())
+        }
+    };
+    let (width, height) = (width as u32, height as u32);
+    let pixels = match space {
+        ColorSpace::Gray => Pixels::Gray(GrayImage::from_raw(width, height, samples)?),
+        ColorSpace::Rgb => Pixels::Rgb(RgbImage::from_raw(width, height, samples)?),
+        ColorSpace::Cmyk => Pixels::Cmyk(RgbaImage::from_raw(width, height, samples)?),
+        ColorSpace::Indexed { .. } => return None,
+    };
+    Some(Image { pixels, color_space, bits: bits as u8 })
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::{dictionary, Object, Stream, StringFormat};
+
+    use super::*;
+
+    /// Bytes that vary from one to the next, so predictors have work to do.
+    fn samples(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 37 % 251) as u8 ^ (i / 7) as u8).collect()
+    }
+
+    fn image(dict: Dictionary, content: Vec<u8>) -> Option<Image> {
+        image_pixels(&Document::new(), &Stream::new(dict, content))
+    }
+
+    fn gray(image: Option<Image>) -> Vec<u8> {
+        match image.map(|i| i.pixels) {
+            Some(Pixels::Gray(image)) => image.into_raw(),
+            _ => panic!("not a gray image"),
+        }
+    }
+
+    /// `row` with PNG filter `kind` applied, given the row above and bytes per pixel.
+    fn png_filter(kind: u8, row: &[u8], previous: &[u8], pixel: usize) -> Vec<u8> {
+        let mut out = vec![kind];
+        for i in 0..row.len() {
+            let left = if i >= pixel { row[i - pixel] } else { 0 };
+            let up_left = if i >= pixel { previous[i - pixel] } else { 0 };
+            out.push(row[i].wrapping_sub(match kind {
+                0 => 0,
+                1 => left,
+                2 => previous[i],
+                3 => ((left as u16 + previous[i] as u16) / 2) as u8,
+                _ => paeth(left, previous[i], up_left),
+            }));
+        }
+        out
+    }
+
+    #[test]
+    fn png_predictors_undo_the_filter_of_each_row() {
+        // two RGB pixels a row, one row per filter type
+        let raw = samples(6 * 5);
+        let mut encoded = Vec::new();
+        let mut previous = vec![0; 6];
+        for (kind, row) in raw.chunks(6).enumerate() {
+            encoded.extend(png_filter(kind as u8, row, &previous, 3));
+            previous = row.to_vec();
+        }
+        // every PNG predictor number means "the type is given per row"
+        for predictor in 10..=15 {
+            let params = dictionary! { "Predictor" => predictor, "Colors" => 3, "Columns" => 2 };
+            assert_eq!(predict(encoded.clone(), Some(&params)), Some(raw.clone()), "predictor {}", predictor);
+        }
+        let params = dictionary! { "Predictor" => 12, "Columns" => 6 };
+        assert_eq!(predict(vec![5, 1, 2, 3, 4, 5, 6], Some(&params)), None);
+    }
+
+    #[test]
+    fn tiff_predictor_adds_each_sample_to_the_one_to_its_left() {
+        let params = dictionary! { "Predictor" => 2, "Colors" => 2, "Columns" => 3 };
+        let encoded = vec![10, 20, 1, 2, 250, 10, 0, 0, 5, 5, 5, 5];
+        let expected = vec![10, 20, 11, 22, 5, 32, 0, 0, 5, 5, 10, 10];
+        assert_eq!(predict(encoded, Some(&params)), Some(expected));
+        // only whole-byte samples are handled
+        let params = dictionary! { "Predictor" => 2, "BitsPerComponent" => 4, "Columns" => 4 };
+        assert_eq!(predict(vec![0x12, 0x34], Some(&params)), None);
+    }
+
+    #[test]
+    fn lzw_follows_early_change() {
+        use weezl::{encode::Encoder, BitOrder};
+        // long enough for the code size to grow past 9 bits
+        let raw: Vec<u8> = samples(4000).iter().map(|b| b % 16).collect();
+        let early = Encoder::with_tiff_size_switch(BitOrder::Msb, 8).encode(&raw).unwrap();
+        let late = Encoder::new(BitOrder::Msb, 8).encode(&raw).unwrap();
+        assert_eq!(lzw(&early, None).as_ref(), Some(&raw));
+        assert_eq!(lzw(&late, Some(&dictionary! { "EarlyChange" => 0 })).as_ref(), Some(&raw));
+        assert_ne!(lzw(&late, None).as_ref(), Some(&raw));
+    }
+
+    #[test]
+    fn run_length_copies_literals_and_repeats_runs() {
+        assert_eq!(run_length(&[2, b'a', b'b', b'c', 254, b'x', 128, b'z']), b"abcxxx");
+        // a truncated literal keeps what is there
+        assert_eq!(run_length(&[4, b'a', b'b']), b"ab");
+    }
+
+    #[test]
+    fn filters_chain_with_their_parameters() {
+        let raw = samples(8);
+        let mut filtered = png_filter(1, &raw[..4], &[0; 4], 1);
+        filtered.extend(png_filter(2, &raw[4..], &raw[..4], 1));
+        let deflated = crate::optimize::deflate(&filtered).unwrap();
+        let stream = Stream::new(
+            dictionary! {
+                "Filter" => vec!["FlateDecode".into(), "DCTDecode".into()],
+                "DecodeParms" => vec![dictionary! { "Predictor" => 15, "Columns" => 4 }.into(), Object::Null],
+            },
+            deflated,
+        );
+        assert!(matches!(decode_stream(&Document::new(), &stream), Some(Decoded::Jpeg(data)) if data == raw));
+
+        let stream = Stream::new(dictionary! { "Filter" => "JBIG2Decode" }, raw);
+        assert!(decode_stream(&Document::new(), &stream).is_none());
+    }
+
+    #[test]
+    fn sub_byte_samples_scale_to_eight_bits() {
+        let dict = |bits: i64| dictionary! { "Width" => 4, "Height" => 2, "ColorSpace" => "DeviceGray", "BitsPerComponent" => bits };
+        // rows start on a byte boundary
+        assert_eq!(gray(image(dict(1), vec![0b1010_0000, 0b0110_0000])), [255, 0, 255, 0, 0, 255, 255, 0]);
+        assert_eq!(gray(image(dict(2), vec![0b0001_1011, 0b1110_0100])), [0, 85, 170, 255, 255, 170, 85, 0]);
+        assert_eq!(gray(image(dict(4), vec![0x0F, 0x5A, 0xF0, 0x00])), [0, 255, 85, 170, 255, 0, 0, 0]);
+        assert_eq!(gray(image(dict(16), vec![0x12, 0x34, 0xFF, 0xFF, 0, 0, 0x80, 0, 1, 2, 3, 4, 5, 6, 7, 8])), [0x12, 0xFF, 0, 0x80, 1, 3, 5, 7]);
+        assert!(image(dict(3), vec![0; 4]).is_none());
+        // a short stream ends in black rows
+        assert_eq!(gray(image(dict(8), vec![9; 4])), [9, 9, 9, 9, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn decode_arrays_remap_samples() {
+        let dict = dictionary! { "Width" => 4, "Height" => 1, "ColorSpace" => "DeviceGray", "BitsPerComponent" => 1, "Decode" => vec![1.into(), 0.into()] };
+        assert_eq!(gray(image(dict, vec![0b1010_0000])), [0, 255, 0, 255]);
+
+        let dict = dictionary! { "Width" => 1, "Height" => 1, "ColorSpace" => "DeviceRGB", "Decode" => vec![0.into(), 1.into(), 1.into(), 0.into(), Object::Real(0.0), Object::Real(0.5)] };
+        let Some(Pixels::Rgb(rgb)) = image(dict, vec![200, 200, 200]).map(|i| i.pixels) else { panic!("not an RGB image") };
+        assert_eq!(rgb.into_raw(), [200, 55, 100]);
+    }
+
+    #[test]
+    fn indexed_images_look_up_their_palette() {
+        let palette = Object::String(vec![255, 0, 0, 0, 0, 255], StringFormat::Hexadecimal);
+        let space = vec!["Indexed".into(), "DeviceRGB".into(), 1.into(), palette];
+        let dict = dictionary! { "Width" => 3, "Height" => 1, "ColorSpace" => space, "BitsPerComponent" => 2 };
+        let image = image(dict, vec![0b0001_1000]).unwrap();
+        assert_eq!(image.color_space.as_name().ok(), Some(&b"DeviceRGB"[..]));
+        assert_eq!(image.bits, 2);
+        let Pixels::Rgb(rgb) = image.pixels else { panic!("not an RGB image") };
+        // index 2 is past hival and reads as black
+        assert_eq!(rgb.into_raw(), [255, 0, 0, 0, 0, 255, 0, 0, 0]);
+    }
+
+    #[test]
+    fn icc_based_spaces_go_by_their_component_count() {
+        let mut doc = Document::new();
+        let cmyk = doc.add_object(Stream::new(dictionary! { "N" => 4 }, Vec::new()));
+        let unknown = doc.add_object(Stream::new(dictionary! { "Alternate" => "DeviceGray" }, Vec::new()));
+        let space = |profile| Object::Array(vec!["ICCBased".into(), Object::Reference(profile)]);
+        assert_eq!(color_space(&doc, &space(cmyk)), Some(ColorSpace::Cmyk));
+        assert_eq!(color_space(&doc, &space(unknown)), Some(ColorSpace::Gray));
+
+        let dict = dictionary! { "Width" => 1, "Height" => 1, "ColorSpace" => space(cmyk) };
+        let image = image_pixels(&doc, &Stream::new(dict, vec![1, 2, 3, 4])).unwrap();
+        assert!(matches!(image.pixels, Pixels::Cmyk(cmyk) if cmyk.as_raw() == &[1, 2, 3, 4]));
+    }
+}
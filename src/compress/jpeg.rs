@@ -0,0 +1,113 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::{GrayImage, Luma, RgbaImage};
+
+use super::decode::Pixels;
+
+/// Marker codes of the JPEG segments put together by [`cmyk`].
+const SOI: u8 = 0xD8;
+const APP14: u8 = 0xEE;
+const SOF0: u8 = 0xC0;
+const DHT: u8 = 0xC4;
+const DQT: u8 = 0xDB;
+const SOS: u8 = 0xDA;
+const EOI: u8 = 0xD9;
+
+/// Encode `pixels` as a baseline JPEG with the same components: gray, RGB or CMYK.
+pub(super) fn encode(pixels: &Pixels, quality: u8) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut encoder = JpegEncoder::new_with_quality(&mut out, quality);
+    match pixels {
+        Pixels::Gray(image) => encoder.encode_image(image).ok()?,
+        Pixels::Rgb(image) => encoder.encode_image(image).ok()?,
+        Pixels::Cmyk(image) => return cmyk(image, quality),
+    }
+    Some(out)
+}
+
+/// A four component JPEG. The encoder only writes gray and RGB, but a baseline frame
+/// may carry its components in separate scans, so each channel is encoded as a gray
+/// image and the scans are put together under one frame with their shared tables.
+fn cmyk(image: &RgbaImage, quality: u8) -> Option<Vec<u8>> {
+    let (width, height) = image.dimensions();
+    let (w, h) = (u16::try_from(width).ok()?, u16::try_from(height).ok()?);
+    let mut tables = Vec::new();
+    let mut scans = Vec::new();
+    for c in 0..4 {
+        let channel = GrayImage::from_fn(width, height, |x, y| Luma([image.get_pixel(x, y)[c]]));
+        let gray = encode(&Pixels::Gray(channel), quality)?;
+        let (segments, scan) = split(&gray)?;
+        if c == 0 {
+            // every channel is quantized and coded with the same tables
+            tables = segments.into_iter().filter(|s| s[1] == DQT || s[1] == DHT).flatten().copied().collect();
+        }
+        scans.push(scan.to_vec());
+    }
+
+    let mut out = vec![0xFF, SOI];
+    // Adobe segment: version 100, no flags, transform 0, i.e. the components are CMYK
+    // as they are rather than YCCK
+    out.extend([0xFF, APP14, 0, 14]);
+    out.extend(b"Adobe");
+    out.extend([0, 100, 0, 0, 0, 0, 0]);
+    out.extend(tables);
+    // frame header: 8-bit samples, four components numbered 1 to 4, no subsampling,
+    // quantization table 0
+    out.extend([0xFF, SOF0, 0, 20, 8]);
+    out.extend(h.to_be_bytes());
+    out.extend(w.to_be_bytes());
+    out.push(4);
+    for id in 1..=4 {
+        out.extend([id, 0x11, 0]);
+    }
+    for (id, scan) in (1..=4).zip(scans) {
+        // scan header: one component with Huffman tables 0, the full spectrum
+        out.extend([0xFF, SOS, 0, 8, 1, id, 0x00, 0, 63, 0]);
+        out.extend(scan);
+    }
+    out.extend([0xFF, EOI]);
+    Some(out)
+}
+
+/// The marker segments of a single-scan JPEG before its scan, and its entropy-coded data.
+fn split(jpeg: &[u8]) -> Option<(Vec<&[u8]>, &[u8])> {
+    let mut segments = Vec::new();
+    let mut i = 2;
+    loop {
+        if *jpeg.get(i)? != 0xFF {
+            return None;
+        }
+        let marker = *jpeg.get(i + 1)?;
+        let length = u16::from_be_bytes([*jpeg.get(i + 2)?, *jpeg.get(i + 3)?]) as usize;
+        let segment = jpeg.get(i..i + 2 + length)?;
+        i += 2 + length;
+        if marker == SOS {
+            return Some((segments, jpeg.get(i..jpeg.len().checked_sub(2)?)?));
+        }
+        segments.push(segment);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cmyk_round_trips_through_a_decoder() {
+        let image = RgbaImage::from_fn(16, 8, |x, y| image::Rgba([(x * 16) as u8, (y * 32) as u8, 200, ((x + y) * 8) as u8]));
+        let jpeg = encode(&Pixels::Cmyk(image.clone()), 95).unwrap();
+
+        // the Adobe segment comes first and says the data is not YCCK
+        assert_eq!(&jpeg[2..6], [0xFF, APP14, 0, 14]);
+        assert_eq!(&jpeg[6..11], b"Adobe");
+        assert_eq!(jpeg[17], 0);
+
+        let mut decoder = jpeg_decoder::Decoder::new(jpeg.as_slice());
+        let samples = decoder.decode().unwrap();
+        let info = decoder.info().unwrap();
+        assert_eq!((info.width, info.height, info.pixel_format), (16, 8, jpeg_decoder::PixelFormat::CMYK32));
+        // the decoder inverts CMYK as Adobe applications store it; PDF does not
+        for (decoded, original) in samples.iter().zip(image.as_raw()) {
+            assert!((255 - *decoded as i16 - *original as i16).abs() <= 8, "{} against {}", 255 - decoded, original);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use lopdf::content::Operation;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+use crate::graphics::{self, deref, Matrix, Visitor};
+
+/// The largest width and height, in points, at which each image XObject is drawn by
+/// the pages of `doc` and the forms they paint. Images drawn only from elsewhere, such
+/// as annotation appearances, are missing.
+pub(super) fn drawn_sizes(doc: &Document) -> HashMap<ObjectId, (f64, f64)> {
+    let mut placement = Placement { doc, sizes: HashMap::new() };
+    for page in doc.get_pages().into_values() {
+        // an unreadable stream hides its images; they keep their resolution
+        let _ = graphics::walk_page(doc, page, Matrix::IDENTITY, (), &mut placement);
+    }
+    placement.sizes
+}
+
+struct Placement<'a> {
+    doc: &'a Document,
+    sizes: HashMap<ObjectId, (f64, f64)>,
+}
+
+impl<'a> Visitor<'a> for Placement<'a> {
+    type State = ();
+
+    fn operation(&mut self, op: &Operation, _: &mut (), ctm: &Matrix, resources: Option<&'a Dictionary>) {
+        if op.operator != "Do" {
+            return;
+        }
+        let Some(name) = op.operands.first().and_then(|n| n.as_name().ok()) else { return };
+        let xobjects = resources.and_then(|r| deref(self.doc, r.get(b"XObject").ok())).and_then(|x| x.as_dict().ok());
+        let Some(id) = xobjects.and_then(|x| x.get(name).ok()).and_then(|r| r.as_reference().ok()) else { return };
+        let Ok(stream) = self.doc.get_object(id).and_then(Object::as_stream) else { return };
+        if stream.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Image".as_slice()) {
+            // the image fills the unit square, so the matrix scales its sides
+            let size = (ctm.a.hypot(ctm.b), ctm.c.hypot(ctm.d));
+            let largest = self.sizes.entry(id).or_insert((0.0, 0.0));
+            *largest = (largest.0.max(size.0), largest.1.max(size.1));
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use lopdf::{Document, Object, ObjectId};
+
+use crate::error::{Error, Result};
+use crate::progress::Progress;
+
+/// Load a PDF from disk, rejecting encrypted documents.
+pub(crate) fn load_document(path: &Path) -> Result<Document> {
+    let doc = Document::load(path).map_err(|e| Error::load(path, e))?;
+    if doc.trailer.has(b"Encrypt") {
+        return Err(Error::Encrypted { path: path.to_path_buf() });
+    }
+    Ok(doc)
+}
+
+/// Page attributes that a page inherits from its ancestors in the page tree.
+const INHERITABLE_PAGE_KEYS: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// Copy the attributes `page` inherits from its `Pages` ancestors onto the page itself,
+/// so it keeps them when moved into a different page tree.
+pub(crate) fn inherit_page_attributes(doc: &mut Document, page: ObjectId) {
+    let mut inherited: Vec<(&[u8], Object)> = Vec::new();
+    let Ok(dict) = doc.get_dictionary(page) else { return };
+    let mut missing: Vec<&[u8]> = INHERITABLE_PAGE_KEYS.iter().copied().filter(|k| !dict.has(k)).collect();
+    let mut parent = dict.get(b"Parent").and_then(Object::as_reference).ok();
+    let mut seen = HashSet::new();
+    while let Some(id) = parent {
+        if missing.is_empty() || !seen.insert(id) {
+            break;
+        }
+        let Ok(node) = doc.get_dictionary(id) else { break };
+        missing.retain(|key| match node.get(key) {
+            Ok(value) => {
+                inherited.push((key, value.clone()));
+                false
+            }
+            Err(_) => true,
+        });
+        parent = node.get(b"Parent").and_then(Object::as_reference).ok();
+    }
+    if let Ok(Object::Dictionary(dict)) = doc.get_object_mut(page) {
+        for (key, value) in inherited {
+            dict.set(key.to_vec(), value);
+        }
+    }
+}
+
+/// Value of `key` on `page` or, for the inheritable attributes, on its nearest `Pages`
+/// ancestor that has it; references are resolved.
+pub(crate) fn page_attribute<'a>(doc: &'a Document, page: ObjectId, key: &[u8]) -> Option<&'a Object> {
+    let mut node = doc.get_dictionary(page).ok()?;
+    let mut seen = HashSet::from([page]);
+    loop {
+        if let Ok(value) = node.get(key) {
+            return doc.dereference(value).ok().map(|(_, v)| v);
+        }
+        if !INHERITABLE_PAGE_KEYS.contains(&key) {
+            return None;
+        }
+        let parent = node.get(b"Parent").and_then(Object::as_reference).ok()?;
+        if !seen.insert(parent) {
+            return None;
+        }
+        node = doc.get_dictionary(parent).ok()?;
+    }
+}
+
+/// Remove every object that cannot be reached from the trailer; returns how many were removed.
+pub(crate) fn prune_unreachable(doc: &mut Document) -> usize {
+    let mut reachable: HashSet<ObjectId> = HashSet::new();
+    let mut pending: Vec<ObjectId> = Vec::new();
+    for (_, value) in doc.trailer.iter() {
+        push_refs(value, &mut pending);
+    }
+    while let Some(id) = pending.pop() {
+        if reachable.insert(id) {
+            if let Some(object) = doc.objects.get(&id) {
+                push_refs(object, &mut pending);
+            }
+        }
+    }
+    let before = doc.objects.len();
+    doc.objects.retain(|id, _| reachable.contains(id));
+    before - doc.objects.len()
+}
+
+/// Append the objects referenced directly from `object` to `out`.
+pub(crate) fn push_refs(object: &Object, out: &mut Vec<ObjectId>) {
+    match object {
+        Object::Reference(id) => out.push(*id),
+        Object::Array(items) => items.iter().for_each(|o| push_refs(o, out)),
+        Object::Dictionary(dict) => dict.iter().for_each(|(_, o)| push_refs(o, out)),
+        Object::Stream(stream) => stream.dict.iter().for_each(|(_, o)| push_refs(o, out)),
+        _ => {}
+    }
+}
+
+/// Save a PDF to disk; see [`write_atomically`].
+pub(crate) fn save_document(doc: &mut Document, path: &Path, progress: &Progress) -> Result<()> {
+    write_atomically(path, progress, |w| doc.save_to(w))
+}
+
+/// Write `path` through a temporary `.part` file that is renamed into place on success.
+///
+/// Written bytes are counted into `progress`, and a cancellation request aborts the
+/// write; either way no partial file is left behind.
+pub(crate) fn write_atomically<F>(path: &Path, progress: &Progress, write: F) -> Result<()>
+where
+    F: FnOnce(&mut ProgressWriter<BufWriter<File>>) -> std::io::Result<()>,
+{
+    progress.check()?;
+    let mut part = path.as_os_str().to_owned();
+    part.push(".part");
+    let part = PathBuf::from(part);
+
+    let result = File::create(&part).and_then(|file| {
+        let mut w = ProgressWriter { inner: BufWriter::new(file), progress };
+        write(&mut w)?;
+        w.flush()
+    });
+    let result = result.and_then(|_| std::fs::rename(&part, path));
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&part);
+        return Err(if progress.is_cancelled() { Error::Cancelled } else { Error::io(path, e) });
+    }
+    Ok(())
+}
+
+/// Writer that counts bytes into a [`Progress`] and fails once cancellation is requested.
+pub(crate) struct ProgressWriter<'a, W> {
+    inner: W,
+    progress: &'a Progress,
+}
+
+impl<W: Write> Write for ProgressWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.progress.is_cancelled() {
+            // not `Interrupted`: write_all would retry it forever
+            return Err(std::io::Error::other("cancelled"));
+        }
+        let n = self.inner.write(buf)?;
+        self.progress.add_bytes(n as u64);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Folder that a folder-output operation writes into: `{stem}-{suffix}` under
+/// `output_dir`, or `output_dir` itself when it already has that name.
+pub(crate) fn output_folder(input: &Path, output_dir: &Path, suffix: &str) -> PathBuf {
+    match input.file_stem().and_then(|s| s.to_str()) {
+        Some(stem) => {
+            let name = format!("{}-{}", stem, suffix);
+            if output_dir.file_name().and_then(|s| s.to_str()) == Some(name.as_str()) {
+                output_dir.to_path_buf()
+            } else {
+                output_dir.join(name)
+            }
+        }
+        None => output_dir.to_path_buf(),
+    }
+}
+
+/// Files written by an operation that produces several outputs.
+///
+/// Unless [`CreatedFiles::keep`] is called, dropping it removes every recorded
+/// file and the folder (if this operation created it), so a failed or cancelled
+/// run leaves nothing behind.
+#[derive(Default)]
+pub(crate) struct CreatedFiles {
+    files: Vec<PathBuf>,
+    dir: Option<PathBuf>,
+    keep: bool,
+}
+
+impl CreatedFiles {
+    /// Create `dir` (and parents) if needed, remembering whether it was new.
+    pub(crate) fn create_dir(&mut self, dir: &Path) -> Result<()> {
+        if !dir.exists() {
+            std::fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+            self.dir = Some(dir.to_path_buf());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn push(&mut self, path: PathBuf) {
+        self.files.push(path);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub(crate) fn keep(mut self) -> Vec<PathBuf> {
+        self.keep = true;
+        std::mem::take(&mut self.files)
+    }
+}
+
+impl Drop for CreatedFiles {
+    fn drop(&mut self) {
+        if self.keep {
+            return;
+        }
+        for f in &self.files {
+            let _ = std::fs::remove_file(f);
+        }
+        if let Some(dir) = &self.dir {
+            // only succeeds when empty, so unrelated files are never removed
+            let _ = std::fs::remove_dir(dir);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Result type returned by the operations in this crate.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors returned by the operations in this crate.
+#[derive(Debug)]
+pub enum Error {
+    /// An input file could not be read (missing, unreadable, ...).
+    Load { path: PathBuf, source: std::io::Error },
+    /// An input file is not a valid PDF (bad header, malformed xref, ...).
+    Parse { path: PathBuf, source: lopdf::Error },
+    /// An input file is password protected; decryption is not supported.
+    Encrypted { path: PathBuf },
+    /// A document loaded but lacks structure the operation needs (no page tree, ...).
+    Malformed(String),
+    /// The document or the request uses a feature this crate does not handle.
+    Unsupported(String),
+    /// The caller passed invalid options (page range, rotation, ...).
+    InvalidArgument(String),
+    /// Writing an output file or folder failed.
+    Io { path: PathBuf, source: std::io::Error },
+    /// An external program such as `pdftoppm` could not be started or failed.
+    ExternalTool { tool: &'static str, message: String, source: Option<std::io::Error> },
+    /// A bug in this crate stopped the operation, e.g. a panic caught by the caller.
+    Internal(String),
+    /// The operation was stopped through [`crate::Progress::cancel`].
+    Cancelled,
+}
+
+impl Error {
+    /// Short machine-readable name of the variant, e.g. `"encrypted"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Load { .. } => "load",
+            Error::Parse { .. } => "parse",
+            Error::Encrypted { .. } => "encrypted",
+            Error::Malformed(_) => "malformed",
+            Error::Unsupported(_) => "unsupported",
+            Error::InvalidArgument(_) => "invalid-argument",
+            Error::Io { .. } => "io",
+            Error::ExternalTool { .. } => "external-tool",
+            Error::Internal(_) => "internal",
+            Error::Cancelled => "cancelled",
+        }
+    }
+
+    pub(crate) fn load(path: &Path, err: lopdf::Error) -> Self {
+        match err {
+            lopdf::Error::IO(source) => Error::Load { path: path.to_path_buf(), source },
+            source => Error::Parse { path: path.to_path_buf(), source },
+        }
+    }
+
+    pub(crate) fn io(path: &Path, source: std::io::Error) -> Self {
+        Error::Io { path: path.to_path_buf(), source }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Load { path, source } => write!(f, "Failed to load {}: {}", path.display(), source),
+            Error::Parse { path, source } => write!(f, "{} is not a valid PDF: {}", path.display(), source),
+            Error::Encrypted { path } => write!(f, "{} is encrypted; password-protected PDFs are not supported", path.display()),
+            Error::Malformed(msg) => write!(f, "Malformed PDF: {}", msg),
+            Error::Unsupported(msg) => write!(f, "Unsupported: {}", msg),
+            Error::InvalidArgument(msg) => write!(f, "{}", msg),
+            Error::Io { path, source } => write!(f, "Failed to write {}: {}", path.display(), source),
+            Error::ExternalTool { tool, message, .. } => write!(f, "{} failed: {}", tool, message),
+            Error::Internal(msg) => write!(f, "Internal error: {}", msg),
+            Error::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Load { source, .. } | Error::Io { source, .. } => Some(source),
+            Error::Parse { source, .. } => Some(source),
+            Error::ExternalTool { source: Some(source), .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+
+use crate::document::page_attribute;
+
+/// Form XObjects nested deeper than this are not entered.
+const MAX_FORM_DEPTH: usize = 12;
+
+/// Affine transformation `[a b c d e f]` as used by PDF, applied to row vectors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Matrix {
+    pub(crate) a: f64,
+    pub(crate) b: f64,
+    pub(crate) c: f64,
+    pub(crate) d: f64,
+    pub(crate) e: f64,
+    pub(crate) f: f64,
+}
+
+impl Matrix {
+    pub(crate) const IDENTITY: Matrix = Matrix { a: 1.0, b: 0.0, c: 0.0, d: 1.0, e: 0.0, f: 0.0 };
+
+    pub(crate) fn translate(e: f64, f: f64) -> Matrix {
+        Matrix { e, f, ..Matrix::IDENTITY }
+    }
+
+    pub(crate) fn from_operands(operands: &[Object]) -> Option<Matrix> {
+        let n: Vec<f64> = operands.iter().map(number).collect::<Option<_>>()?;
+        match n[..] {
+            [a, b, c, d, e, f] => Some(Matrix { a, b, c, d, e, f }),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (x * self.a + y * self.c + self.e, x * self.b + y * self.d + self.f)
+    }
+
+    /// `self` followed by `then`.
+    pub(crate) fn then(&self, then: &Matrix) -> Matrix {
+        Matrix {
+            a: self.a * then.a + self.b * then.c,
+            b: self.a * then.b + self.b * then.d,
+            c: self.c * then.a + self.d * then.c,
+            d: self.c * then.b + self.d * then.d,
+            e: self.e * then.a + self.f * then.c + then.e,
+            f: self.e * then.b + self.f * then.d + then.f,
+        }
+    }
+}
+
+pub(crate) fn deref<'a>(doc: &'a Document, object: Option<&'a Object>) -> Option<&'a Object> {
+    doc.dereference(object?).ok().map(|(_, o)| o)
+}
+
+pub(crate) fn number(object: &Object) -> Option<f64> {
+    match object {
+        Object::Integer(i) => Some(*i as f64),
+        Object::Real(r) => Some(*r),
+        _ => None,
+    }
+}
+
+/// Decoded data of a stream, or its raw data if a filter is not supported.
+pub(crate) fn content(stream: &Stream) -> Vec<u8> {
+    stream.decompressed_content().unwrap_or_else(|_| stream.content.clone())
+}
+
+/// The operators of a content stream that [`walk_page`] does not handle itself.
+pub(crate) trait Visitor<'a> {
+    /// Graphics state parameters besides the CTM: saved by `q`, restored by `Q`, and
+    /// the starting state of a form XObject.
+    type State: Clone;
+
+    /// An operator other than `q`, `Q`, `cm` and the `Do` of a form XObject it enters,
+    /// with the current transformation matrix and the resources it is drawn with.
+    fn operation(&mut self, operation: &Operation, state: &mut Self::State, ctm: &Matrix, resources: Option<&'a Dictionary>);
+}
+
+/// Follow the content streams of `page`, starting from `ctm` and `state`, through the
+/// form XObjects they paint, and hand every other operator to `visitor`. Only the
+/// page's own content failing to parse is an error.
+pub(crate) fn walk_page<'a, V: Visitor<'a>>(doc: &'a Document, page: ObjectId, ctm: Matrix, state: V::State, visitor: &mut V) -> lopdf::Result<()> {
+    let mut data = Vec::new();
+    for id in doc.get_page_contents(page) {
+        if let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) {
+            data.extend(content(stream));
+            // streams are concatenated, so a token must not run into the next one
+            data.push(b'\n');
+        }
+    }
+    let resources = page_attribute(doc, page, b"Resources").and_then(|r| r.as_dict().ok());
+    walk(doc, &data, resources, ctm, state, visitor, 0)
+}
+
+fn walk<'a, V: Visitor<'a>>(
+    doc: &'a Document,
+    data: &[u8],
+    resources: Option<&'a Dictionary>,
+    mut ctm: Matrix,
+    mut state: V::State,
+    visitor: &mut V,
+    depth: usize,
+) -> lopdf::Result<()> {
+    let operations = Content::decode(data)?.operations;
+    let mut saved: Vec<(Matrix, V::State)> = Vec::new();
+    for op in &operations {
+        match op.operator.as_str() {
+            "q" => saved.push((ctm, state.clone())),
+            "Q" => {
+                if let Some((m, s)) = saved.pop() {
+                    ctm = m;
+                    state = s;
+                }
+            }
+            "cm" => {
+                if let Some(m) = Matrix::from_operands(&op.operands) {
+                    ctm = m.then(&ctm);
+                }
+            }
+            "Do" => match form(doc, resources, &op.operands).filter(|_| depth < MAX_FORM_DEPTH) {
+                Some(form) => {
+                    let matrix = deref(doc, form.dict.get(b"Matrix").ok())
+                        .and_then(|m| m.as_array().ok())
+                        .and_then(|m| Matrix::from_operands(m))
+                        .unwrap_or(Matrix::IDENTITY);
+                    let form_resources = deref(doc, form.dict.get(b"Resources").ok()).and_then(|r| r.as_dict().ok()).or(resources);
+                    // a form that does not parse loses its own content, not the page's
+                    let _ = walk(doc, &content(form), form_resources, matrix.then(&ctm), state.clone(), visitor, depth + 1);
+                }
+                None => visitor.operation(op, &mut state, &ctm, resources),
+            },
+            _ => visitor.operation(op, &mut state, &ctm, resources),
+        }
+    }
+    Ok(())
+}
+
+/// The form XObject a `Do` with `operands` paints, if it paints one.
+fn form<'a>(doc: &'a Document, resources: Option<&'a Dictionary>, operands: &[Object]) -> Option<&'a Stream> {
+    let name = operands.first()?.as_name().ok()?;
+    let xobjects = deref(doc, resources?.get(b"XObject").ok())?.as_dict().ok()?;
+    let stream = deref(doc, xobjects.get(name).ok())?.as_stream().ok()?;
+    (stream.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Form".as_slice())).then_some(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::{dictionary, Stream};
+
+    use super::*;
+
+    /// Records the translation of the CTM and the state at each `Tj`, whose operand
+    /// names the call.
+    #[derive(Default)]
+    struct Recorder(Vec<(String, (f64, f64), u32)>);
+
+    impl<'a> Visitor<'a> for Recorder {
+        type State = u32;
+
+        fn operation(&mut self, op: &Operation, state: &mut u32, ctm: &Matrix, _: Option<&'a Dictionary>) {
+            match op.operator.as_str() {
+                "Tc" => *state = op.operands[0].as_i64().unwrap() as u32,
+                "Tj" => self.0.push((String::from_utf8_lossy(op.operands[0].as_str().unwrap()).into_owned(), (ctm.e, ctm.f), *state)),
+                _ => {}
+            }
+        }
+    }
+
+    /// A page drawing `content`, whose resources name the form `/X` drawing `form`,
+    /// translated by its `/Matrix` to (100, 0); the form can paint itself.
+    fn walk_content(content: &str, form: &str) -> Vec<(String, (f64, f64), u32)> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let form_id = doc.new_object_id();
+        let resources = dictionary! { "XObject" => dictionary! { "X" => form_id } };
+        let matrix = vec![1.into(), 0.into(), 0.into(), 1.into(), 100.into(), 0.into()];
+        let form = Stream::new(dictionary! { "Subtype" => "Form", "Matrix" => matrix, "Resources" => resources.clone() }, form.as_bytes().to_vec());
+        doc.objects.insert(form_id, Object::Stream(form));
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.as_bytes().to_vec()));
+        let page = doc.add_object(dictionary! { "Type" => "Page", "Parent" => pages_id, "Contents" => content_id, "Resources" => resources });
+        doc.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page.into()], "Count" => 1 }));
+
+        let mut recorder = Recorder::default();
+        walk_page(&doc, page, Matrix::IDENTITY, 0, &mut recorder).unwrap();
+        recorder.0
+    }
+
+    #[test]
+    fn q_and_big_q_restore_the_ctm_and_the_state() {
+        let calls = walk_content("1 0 0 1 10 20 cm q 2 Tc 1 0 0 1 5 5 cm (inner) Tj Q (outer) Tj", "");
+        assert_eq!(calls, [("inner".to_string(), (15.0, 25.0), 2), ("outer".to_string(), (10.0, 20.0), 0)]);
+    }
+
+    #[test]
+    fn forms_start_from_the_state_at_do_and_apply_their_matrix() {
+        let calls = walk_content("3 Tc 1 0 0 1 0 50 cm /X Do (after) Tj", "1 Tc (form) Tj");
+        assert_eq!(calls, [("form".to_string(), (100.0, 50.0), 1), ("after".to_string(), (0.0, 50.0), 3)]);
+    }
+
+    #[test]
+    fn forms_painting_themselves_stop_at_the_depth_limit() {
+        let calls = walk_content("/X Do", "(form) Tj /X Do");
+        assert_eq!(calls.len(), MAX_FORM_DEPTH);
+        assert_eq!(calls.last().unwrap().1, (100.0 * MAX_FORM_DEPTH as f64, 0.0));
+    }
+}
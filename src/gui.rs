@@ -0,0 +1,1322 @@
+use gtk::{gdk, prelude::*};
+use gtk::glib::Type as GlibType;
+use gtk::{Application, ApplicationWindow, Box as GtkBox, Button, CheckButton, Entry, Frame, Label, Orientation, ProgressBar, ToggleButton};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use pdf_tools::{BookmarkMode, CompressOptions, CompressPreset, ImageOptions, MergeOptions, Operation, OperationKind, Progress, RotateOptions, SplitMode, SplitOptions, TextFormat, TextMode, TextOptions};
+
+/// A UI callback that is registered after the closures that invoke it are built.
+type SharedCallback = Rc<RefCell<Option<std::boxed::Box<dyn Fn() + 'static>>>>;
+
+pub fn run() {
+    let app = Application::builder()
+        .application_id("com.github.gemini.pdf-tools")
+        .build();
+    app.connect_activate(build_ui);
+    app.run();
+}
+
+fn build_ui(app: &Application) {
+    let files: Rc<RefCell<Vec<PathBuf>>> = Rc::new(RefCell::new(Vec::new()));
+
+    let css = r#"
+    .root { background-color: #f5f7fa; }
+    .card { background-color: #ffffff; border-radius: 8px; padding: 10px; box-shadow: 0 2px 6px rgba(0,0,0,0.06); }
+    .func-button { font-weight: 600; padding: 10px 14px; border-radius: 6px; }
+    .preview-image { border-radius: 6px; border: 1px solid rgba(0,0,0,0.08); }
+    "#;
+    let provider = gtk::CssProvider::new();
+    provider.load_from_data(css);
+    if let Some(display) = gdk::Display::default() {
+        gtk::style_context_add_provider_for_display(&display, &provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+    }
+
+    let vbox = GtkBox::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(12)
+        .margin_top(12)
+        .margin_bottom(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+    vbox.style_context().add_class("root");
+
+    // Drop area (Frame with label inside) to improve DnD handling
+    let drop_frame = Frame::builder().label("Drop files here").build();
+    drop_frame.set_size_request(400, 120);
+    let drop_label = Label::builder().label("Drag and drop a PDF file here").build();
+    drop_frame.set_child(Some(&drop_label));
+    drop_frame.style_context().add_class("card");
+    vbox.append(&drop_frame);
+
+    let file_list_label = Label::builder().label("").build();
+    vbox.append(&file_list_label);
+    file_list_label.set_visible(false);
+
+    // File pool box (shows checkboxes for each added file) inside a scroller
+    let file_pool_box = GtkBox::builder().orientation(Orientation::Vertical).spacing(4).build();
+    let file_pool_scroller = gtk::ScrolledWindow::builder().vexpand(true).min_content_height(120).build();
+    file_pool_scroller.set_child(Some(&file_pool_box));
+    vbox.append(&file_pool_scroller);
+
+    let pool_controls = GtkBox::builder().orientation(Orientation::Horizontal).spacing(6).build();
+    let select_all_btn = Button::with_label("Select All");
+    let remove_selected_btn = Button::with_label("Remove Selected");
+    pool_controls.append(&select_all_btn);
+    pool_controls.append(&remove_selected_btn);
+    vbox.append(&pool_controls);
+
+    let add_files_btn = Button::with_label("Add files");
+    vbox.append(&add_files_btn);
+
+    let transform_frame = Frame::builder().label("Transform").build();
+    let transform_box = GtkBox::builder().orientation(Orientation::Horizontal).spacing(6).margin_top(6).margin_bottom(6).build();
+    transform_frame.set_child(Some(&transform_box));
+    transform_frame.style_context().add_class("card");
+
+    let export_frame = Frame::builder().label("Export / Convert").build();
+    let export_box = GtkBox::builder().orientation(Orientation::Horizontal).spacing(6).margin_top(6).margin_bottom(6).build();
+    export_frame.set_child(Some(&export_box));
+    export_frame.style_context().add_class("card");
+
+    // Toggle buttons for functions (use emoji for simple icons)
+    let btn_rotate = ToggleButton::with_label("🔁 Rotate PDF");
+    let btn_split = ToggleButton::with_label("📐 Split PDF");
+    let btn_merge = ToggleButton::with_label("🧩 Merge PDFs");
+    btn_rotate.style_context().add_class("func-button");
+    btn_split.style_context().add_class("func-button");
+    btn_merge.style_context().add_class("func-button");
+    btn_rotate.set_size_request(140, 48);
+    btn_split.set_size_request(140, 48);
+    btn_merge.set_size_request(140, 48);
+    transform_box.append(&btn_rotate);
+    transform_box.append(&btn_split);
+    transform_box.append(&btn_merge);
+
+    let btn_extract = ToggleButton::with_label("📄 Extract Text");
+    let btn_images = ToggleButton::with_label("🖼️ Convert to Images");
+    let btn_compress = ToggleButton::with_label("🗜️ Compress PDF");
+    let btn_optimize = ToggleButton::with_label("🧹 Optimize PDF");
+    btn_extract.style_context().add_class("func-button");
+    btn_images.style_context().add_class("func-button");
+    btn_compress.style_context().add_class("func-button");
+    btn_optimize.style_context().add_class("func-button");
+    btn_extract.set_size_request(140, 48);
+    btn_images.set_size_request(140, 48);
+    btn_compress.set_size_request(140, 48);
+    btn_optimize.set_size_request(140, 48);
+    export_box.append(&btn_extract);
+    export_box.append(&btn_images);
+    export_box.append(&btn_compress);
+    export_box.append(&btn_optimize);
+
+    vbox.append(&transform_frame);
+    vbox.append(&export_frame);
+
+    // Output options
+    let output_box = GtkBox::builder().orientation(Orientation::Horizontal).spacing(8).build();
+    let same_location = CheckButton::with_label("Use input folder for output");
+    same_location.set_active(true);
+    let choose_folder_btn = Button::with_label("Choose folder");
+    choose_folder_btn.set_sensitive(false);
+    output_box.append(&same_location);
+    output_box.append(&choose_folder_btn);
+    vbox.append(&output_box);
+
+    let filename_entry = Entry::new();
+    filename_entry.set_placeholder_text(Some("Output filename (e.g. output.pdf, {stem}_opt.pdf)"));
+    filename_entry.set_tooltip_text(Some("{stem} is replaced by each input's name and {index} by its position"));
+    vbox.append(&filename_entry);
+
+    let rotate_box = GtkBox::builder().orientation(Orientation::Horizontal).spacing(6).build();
+    let rot_90_cw = ToggleButton::with_label("90° CW");
+    let rot_90_ccw = ToggleButton::with_label("90° CCW");
+    let rot_180 = ToggleButton::with_label("180°");
+    rot_90_cw.set_active(true);
+    rotate_box.append(&rot_90_cw);
+    rotate_box.append(&rot_90_ccw);
+    rotate_box.append(&rot_180);
+    rotate_box.set_visible(false);
+    vbox.append(&rotate_box);
+    let pages_entry = Entry::new();
+    pages_entry.set_placeholder_text(Some("Pages (e.g. 1-3,5) — empty = all"));
+    rotate_box.append(&pages_entry);
+
+    let split_box = GtkBox::builder().orientation(Orientation::Horizontal).spacing(6).build();
+    let split_mode = gtk::DropDown::from_strings(&["Every page", "Every N pages", "Page ranges", "Odd pages", "Even pages", "Bookmarks", "Max part size", "Blank pages"]);
+    let split_arg = Entry::new();
+    split_arg.set_placeholder_text(Some("N, ranges, depth or size (e.g. 3, 1-4,9-12, 2 or 10MB)"));
+    split_arg.set_sensitive(false);
+    let split_names = Entry::new();
+    split_names.set_placeholder_text(Some(&split_names_placeholder(0)));
+    split_names.set_tooltip_text(Some("{stem}, {start}, {end}, {index} and {title} are replaced for each part"));
+    split_box.append(&split_mode);
+    split_box.append(&split_arg);
+    split_box.append(&split_names);
+    let split_render = CheckButton::with_label("Detect blank scans");
+    split_render.set_tooltip_text(Some("Also treat pages that render almost uniform as blank (needs pdftoppm)"));
+    let split_drop = CheckButton::with_label("Drop separator pages");
+    for check in [&split_render, &split_drop] {
+        check.set_sensitive(false);
+        split_box.append(check);
+    }
+    split_box.set_visible(false);
+    vbox.append(&split_box);
+    let split_arg_cl = split_arg.clone();
+    let split_render_cl = split_render.clone();
+    let split_drop_cl = split_drop.clone();
+    let split_names_cl = split_names.clone();
+    split_mode.connect_selected_notify(move |d| {
+        split_names_cl.set_placeholder_text(Some(&split_names_placeholder(d.selected())));
+        split_arg_cl.set_sensitive(matches!(d.selected(), 1 | 2 | 5 | 6));
+        split_render_cl.set_sensitive(d.selected() == 7);
+        split_drop_cl.set_sensitive(d.selected() == 7);
+    });
+
+    let merge_box = GtkBox::builder().orientation(Orientation::Horizontal).spacing(6).build();
+    let keep_bookmarks = CheckButton::with_label("Keep bookmarks of each file");
+    keep_bookmarks.set_active(true);
+    merge_box.append(&keep_bookmarks);
+    merge_box.set_visible(false);
+    vbox.append(&merge_box);
+
+    let text_box = GtkBox::builder().orientation(Orientation::Horizontal).spacing(6).build();
+    let text_mode = gtk::DropDown::from_strings(&["Reading order", "Keep layout"]);
+    text_mode.set_tooltip_text(Some("Keep layout places the text on a grid that follows the page"));
+    let text_format = gtk::DropDown::from_strings(&["Plain text", "JSON with positions", "Tables as CSV"]);
+    text_format.set_tooltip_text(Some("JSON lists each run of text with its bounding box, font, size and color; CSV writes each table found into a {stem}-tables folder"));
+    let text_pages = Entry::new();
+    text_pages.set_placeholder_text(Some("Pages (e.g. 1-3,5) — empty = all"));
+    let text_per_page = CheckButton::with_label("One file per page");
+    text_per_page.set_tooltip_text(Some("Write page-N files into a {stem}-text folder instead of one file with form feeds between pages"));
+    text_box.append(&text_mode);
+    text_box.append(&text_format);
+    text_box.append(&text_pages);
+    text_box.append(&text_per_page);
+    let text_mode_cl = text_mode.clone();
+    let text_per_page_cl = text_per_page.clone();
+    let filename_entry_for_format = filename_entry.clone();
+    text_format.connect_selected_notify(move |d| {
+        text_mode_cl.set_sensitive(d.selected() == 0);
+        // tables always go to a folder
+        text_per_page_cl.set_sensitive(d.selected() != 2);
+        // follow the format in the suggested name, but leave a name the user typed alone
+        let (from, to) = match d.selected() {
+            1 => ("{stem}.txt", "{stem}.json"),
+            0 => ("{stem}.json", "{stem}.txt"),
+            _ => return,
+        };
+        if filename_entry_for_format.text() == from {
+            filename_entry_for_format.set_text(to);
+        }
+    });
+    text_box.set_visible(false);
+    vbox.append(&text_box);
+
+    let compress_box = GtkBox::builder().orientation(Orientation::Horizontal).spacing(6).build();
+    let compress_presets = [CompressPreset::Screen, CompressPreset::Ebook, CompressPreset::Print, CompressPreset::Prepress];
+    let compress_preset = gtk::DropDown::from_strings(&["Screen (72 dpi)", "eBook (150 dpi)", "Print (300 dpi)", "Prepress (300 dpi)", "Custom"]);
+    compress_preset.set_selected(1);
+    let compress_quality = Entry::new();
+    compress_quality.set_placeholder_text(Some("JPEG quality (1-100)"));
+    let compress_dpi = Entry::new();
+    compress_dpi.set_placeholder_text(Some("Max DPI — empty = keep"));
+    compress_dpi.set_tooltip_text(Some("Images drawn at a higher resolution than this on the page are downsampled"));
+    let compress_min_size = Entry::new();
+    compress_min_size.set_placeholder_text(Some("Skip images under (e.g. 10KB)"));
+    compress_box.append(&compress_preset);
+    for entry in [&compress_quality, &compress_dpi, &compress_min_size] {
+        compress_box.append(entry);
+    }
+    // the fields show a preset's settings and become editable for Custom
+    let show_compress_preset = {
+        let fields = [compress_quality.clone(), compress_dpi.clone(), compress_min_size.clone()];
+        move |selected: u32| {
+            let custom = compress_presets.get(selected as usize).is_none();
+            if let Some(preset) = compress_presets.get(selected as usize) {
+                let options = preset.options();
+                fields[0].set_text(&options.quality.to_string());
+                fields[1].set_text(&options.max_dpi.map(|d| d.to_string()).unwrap_or_default());
+                fields[2].set_text(&format!("{}KiB", options.min_image_size >> 10));
+            }
+            for field in &fields {
+                field.set_sensitive(custom);
+            }
+        }
+    };
+    show_compress_preset(compress_preset.selected());
+    compress_preset.connect_selected_notify(move |d| show_compress_preset(d.selected()));
+    compress_box.set_visible(false);
+    vbox.append(&compress_box);
+
+    let preview_image = gtk::Image::new();
+    preview_image.set_pixel_size(160);
+    preview_image.style_context().add_class("preview-image");
+    vbox.append(&preview_image);
+
+    let preview_generator: SharedCallback = Rc::new(RefCell::new(None));
+    let rebuild_workplace_hook: SharedCallback = Rc::new(RefCell::new(None));
+
+    {
+        let files_for_preview = files.clone();
+        let preview_image_cl = preview_image.clone();
+        let rot_90_cw_pre = rot_90_cw.clone();
+        let rot_90_ccw_pre = rot_90_ccw.clone();
+        let rot_180_pre = rot_180.clone();
+        let gen = move || {
+            let current_files = files_for_preview.borrow().clone();
+            if current_files.is_empty() {
+                preview_image_cl.clear();
+                return;
+            }
+            let input = current_files[0].clone();
+            let degrees = if rot_90_cw_pre.is_active() {
+                90
+            } else if rot_90_ccw_pre.is_active() {
+                -90
+            } else if rot_180_pre.is_active() {
+                180
+            } else {
+                0
+            };
+
+            let ts = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_millis();
+            let tmp_prefix = std::env::temp_dir().join(format!("pdf_preview_{}", ts));
+            let tmp_png = tmp_prefix.with_extension("png");
+
+            let (sender, receiver) = glib::MainContext::channel::<Result<std::path::PathBuf, String>>(glib::PRIORITY_DEFAULT);
+
+            std::thread::spawn(move || {
+                // render first page to PNG from the original PDF using pdftoppm
+                if std::process::Command::new("pdftoppm").arg("-v").status().is_ok() {
+                    let mut cmd = std::process::Command::new("pdftoppm");
+                    cmd.args(["-png", "-f", "1", "-singlefile", input.to_string_lossy().as_ref(), tmp_prefix.to_string_lossy().as_ref()]);
+                    match cmd.status() {
+                        Ok(s) if s.success() => {
+                            if degrees != 0 {
+                                if std::process::Command::new("convert").arg("-version").status().is_ok() {
+                                    let deg_arg = degrees.to_string();
+                                    let mut rcmd = std::process::Command::new("convert");
+                                    rcmd.args([tmp_png.to_string_lossy().as_ref(), "-rotate", &deg_arg, tmp_png.to_string_lossy().as_ref()]);
+                                    match rcmd.status() {
+                                        Ok(rs) if rs.success() => { let _ = sender.send(Ok(tmp_png)); }
+                                        Ok(rs) => { let _ = sender.send(Err(format!("convert exited: {}", rs))); }
+                                        Err(e) => { let _ = sender.send(Err(format!("failed to spawn convert: {}", e))); }
+                                    }
+                                } else {
+                                    let _ = sender.send(Ok(tmp_png));
+                                }
+                            } else {
+                                let _ = sender.send(Ok(tmp_png));
+                            }
+                        }
+                        Ok(s) => { let _ = sender.send(Err(format!("pdftoppm exited: {}", s))); }
+                        Err(e) => { let _ = sender.send(Err(format!("failed to spawn pdftoppm: {}", e))); }
+                    }
+                } else {
+                    let _ = sender.send(Err("pdftoppm not found".into()));
+                }
+            });
+
+            let preview_image_for_attach = preview_image_cl.clone();
+            receiver.attach(None, move |res| {
+                match res {
+                    Ok(path) => { preview_image_for_attach.set_from_file(Some(&path)); }
+                    Err(_) => { preview_image_for_attach.clear(); }
+                }
+                glib::Continue(false)
+            });
+        };
+        preview_generator.borrow_mut().replace(std::boxed::Box::new(gen));
+    }
+
+    let workplace = Frame::builder().label("Workplace").build();
+    let wp_box = GtkBox::builder().orientation(Orientation::Vertical).spacing(6).margin_top(6).margin_bottom(6).build();
+    workplace.set_child(Some(&wp_box));
+
+    let wp_input = Label::new(None);
+    let wp_action = Label::new(None);
+    let wp_status = Label::new(None);
+    let open_output_btn = Button::with_label("Open output folder");
+    open_output_btn.set_sensitive(false);
+
+    wp_box.append(&wp_input);
+    wp_input.set_visible(false);
+    wp_box.append(&wp_action);
+    wp_box.append(&wp_status);
+
+    let scroller = gtk::ScrolledWindow::builder().vexpand(true).min_content_height(120).build();
+    let file_listbox = gtk::ListBox::new();
+    scroller.set_child(Some(&file_listbox));
+    wp_box.append(&scroller);
+
+    let files_progress: Rc<RefCell<Vec<ProgressBar>>> = Rc::new(RefCell::new(Vec::new()));
+    let files_output_labels: Rc<RefCell<Vec<Label>>> = Rc::new(RefCell::new(Vec::new()));
+    // per-row page selection used by merge, in the same syntax as the rotate pages entry
+    let files_pages: Rc<RefCell<Vec<Entry>>> = Rc::new(RefCell::new(Vec::new()));
+
+    wp_box.append(&open_output_btn);
+
+    vbox.append(&workplace);
+
+    let rebuild_workplace = {
+        let file_listbox = file_listbox.clone();
+        let files = files.clone();
+        let files_progress = files_progress.clone();
+        let files_output_labels = files_output_labels.clone();
+        let files_pages = files_pages.clone();
+        let filename_entry = filename_entry.clone();
+        let btn_merge = btn_merge.clone();
+        Box::new(move || {
+            while let Some(row) = file_listbox.row_at_index(0) {
+                file_listbox.remove(&row);
+            }
+            files_progress.borrow_mut().clear();
+            files_output_labels.borrow_mut().clear();
+            // rows are rebuilt from scratch; keep the page selections already typed in
+            let old_pages: Vec<String> = files_pages.borrow_mut().drain(..).map(|e| e.text().to_string()).collect();
+
+            let template = filename_entry.text().as_str().to_string();
+            for (i, p) in files.borrow().iter().enumerate() {
+                let row = GtkBox::builder().orientation(Orientation::Horizontal).spacing(8).margin_top(4).margin_bottom(4).build();
+                let in_lbl = Label::new(None);
+                let in_name = p.file_name().and_then(|s| s.to_str()).unwrap_or("").to_string();
+                let max_in = 28;
+                if in_name.chars().count() > max_in {
+                    let short: String = in_name.chars().take(max_in - 1).collect();
+                    in_lbl.set_label(&format!("{}…", short));
+                    in_lbl.set_tooltip_text(Some(&in_name));
+                } else {
+                    in_lbl.set_label(&in_name);
+                    in_lbl.set_tooltip_text(None);
+                }
+                in_lbl.set_xalign(0.0);
+                in_lbl.set_hexpand(true);
+
+                let bar = ProgressBar::new();
+                bar.set_fraction(0.0);
+                bar.set_hexpand(true);
+                bar.set_valign(gtk::Align::Center);
+
+                let pages = Entry::new();
+                pages.set_placeholder_text(Some("All pages"));
+                pages.set_tooltip_text(Some("Pages to merge from this file, e.g. 3-12 or 12-3 — empty = all"));
+                pages.set_width_chars(10);
+                pages.set_text(old_pages.get(i).map(String::as_str).unwrap_or(""));
+                pages.set_visible(btn_merge.is_active());
+
+                let out_lbl = Label::new(None);
+                set_row_output(&out_lbl, &pdf_tools::output_name(&template, p, i + 1), None);
+                out_lbl.set_xalign(1.0);
+                out_lbl.set_hexpand(true);
+
+                row.append(&in_lbl);
+                row.append(&pages);
+                row.append(&bar);
+                row.append(&out_lbl);
+
+                file_listbox.append(&row);
+                files_progress.borrow_mut().push(bar);
+                files_output_labels.borrow_mut().push(out_lbl);
+                files_pages.borrow_mut().push(pages);
+            }
+        })
+    };
+
+    // initial build (empty)
+    rebuild_workplace();
+    // store rebuild into shared holder so earlier closures can call it
+    rebuild_workplace_hook.borrow_mut().replace(rebuild_workplace);
+
+    let run_box = GtkBox::builder().orientation(Orientation::Horizontal).spacing(6).build();
+    let run_button = Button::with_label("Run");
+    run_button.set_hexpand(true);
+    let cancel_button = Button::with_label("Cancel");
+    cancel_button.set_sensitive(false);
+    run_box.append(&run_button);
+    run_box.append(&cancel_button);
+    vbox.append(&run_box);
+
+    // Header bar: app icon, title, spacer, quick actions
+    let header_box = GtkBox::builder().orientation(Orientation::Horizontal).spacing(8).build();
+    let app_icon = gtk::Image::builder().icon_name("application-pdf-symbolic").build();
+    let title_lbl = Label::builder().label("PDF Toolset").build();
+    let spacer = GtkBox::builder().orientation(Orientation::Horizontal).build();
+    spacer.set_hexpand(true);
+    let header_add = Button::with_label("＋");
+    let header_run = Button::with_label("▶");
+    header_box.append(&app_icon);
+    header_box.append(&title_lbl);
+    header_box.append(&spacer);
+    header_box.append(&header_add);
+    header_box.append(&header_run);
+
+    let add_files_btn_cl = add_files_btn.clone();
+    header_add.connect_clicked(move |_| {
+        add_files_btn_cl.emit_clicked();
+    });
+    let run_button_cl = run_button.clone();
+    header_run.connect_clicked(move |_| {
+        run_button_cl.emit_clicked();
+    });
+
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title("PDF Toolset")
+        .default_width(480)
+        .default_height(300)
+        .child(&vbox)
+        .build();
+
+    vbox.prepend(&header_box);
+
+    let files_clone = files.clone();
+    let file_list_label_clone = file_list_label.clone();
+    let wp_input_clone = wp_input.clone();
+    let file_pool_box_clone = file_pool_box.clone();
+    let filename_entry_for_drop = filename_entry.clone();
+    let file_checks: Rc<RefCell<Vec<gtk::CheckButton>>> = Rc::new(RefCell::new(Vec::new()));
+    let file_checks_for_drop = file_checks.clone();
+    let drop_target = gtk::DropTarget::new(GlibType::STRING, gdk::DragAction::COPY);
+    let preview_gen_for_drop = preview_generator.clone();
+    let rebuild_workplace_for_drop = rebuild_workplace_hook.clone();
+    drop_target.connect_drop(move |_, value, _, _| {
+        if let Ok(uri) = value.get::<String>() {
+            for raw in uri.split('\n') {
+                if raw.trim().is_empty() {
+                    continue;
+                }
+                let p = raw.trim().trim_start_matches("file://");
+                let path = std::path::PathBuf::from(p);
+                files_clone.borrow_mut().push(path);
+            }
+                let names: Vec<String> = files_clone
+                .borrow()
+                .iter()
+                .map(|p| p.file_name().and_then(|s| s.to_str()).unwrap_or("").to_string())
+                .collect();
+            file_list_label_clone.set_label(&names.join(", "));
+            wp_input_clone.set_label(&format!("Input: {}", names.join(", ")));
+            for cb in file_checks_for_drop.borrow().iter() {
+                    file_pool_box_clone.remove(cb);
+                }
+                file_checks_for_drop.borrow_mut().clear();
+                for p in files_clone.borrow().iter() {
+                    let cb = gtk::CheckButton::with_label(p.file_name().and_then(|s| s.to_str()).unwrap_or(""));
+                    file_pool_box_clone.append(&cb);
+                    file_checks_for_drop.borrow_mut().push(cb);
+                }
+                if filename_entry_for_drop.text().is_empty() && !files_clone.borrow().is_empty() {
+                    filename_entry_for_drop.set_text("{stem}_opt.pdf");
+                }
+                if let Some(gen) = &*preview_gen_for_drop.borrow() {
+                    (gen)();
+                }
+                if let Some(rb) = &*rebuild_workplace_for_drop.borrow() {
+                    (rb)();
+                }
+        }
+        true
+    });
+    drop_frame.add_controller(drop_target);
+
+    // Add files button -> use zenity to pick multiple files
+    let files_clone_for_add = files.clone();
+    let file_list_label_for_add = file_list_label.clone();
+    let wp_input_for_add = wp_input.clone();
+    let file_pool_for_add = file_pool_box.clone();
+    let file_checks_for_add = file_checks.clone();
+    let filename_entry_for_add = filename_entry.clone();
+    let preview_gen_for_add = preview_generator.clone();
+    let rebuild_workplace_for_add = rebuild_workplace_hook.clone();
+    add_files_btn.connect_clicked(move |_| {
+        if let Ok(out) = std::process::Command::new("zenity").arg("--file-selection").arg("--multiple").arg("--separator=\n").output() {
+            if out.status.success() {
+                let text = String::from_utf8_lossy(&out.stdout).to_string();
+                for line in text.split('\n') {
+                    let line = line.trim();
+                    if line.is_empty() { continue; }
+                    files_clone_for_add.borrow_mut().push(PathBuf::from(line));
+                }
+                let names: Vec<String> = files_clone_for_add
+                    .borrow()
+                    .iter()
+                    .map(|p| p.file_name().and_then(|s| s.to_str()).unwrap_or("").to_string())
+                    .collect();
+                file_list_label_for_add.set_label(&names.join(", "));
+                wp_input_for_add.set_label(&format!("Input: {}", names.join(", ")));
+                // update pool UI: remove existing checkbuttons then recreate
+                for cb in file_checks_for_add.borrow().iter() {
+                    file_pool_for_add.remove(cb);
+                }
+                file_checks_for_add.borrow_mut().clear();
+                for p in files_clone_for_add.borrow().iter() {
+                    let cb = gtk::CheckButton::with_label(p.file_name().and_then(|s| s.to_str()).unwrap_or(""));
+                    file_pool_for_add.append(&cb);
+                    file_checks_for_add.borrow_mut().push(cb);
+                }
+                if filename_entry_for_add.text().is_empty() && !files_clone_for_add.borrow().is_empty() {
+                    filename_entry_for_add.set_text("{stem}_opt.pdf");
+                }
+                if let Some(gen) = &*preview_gen_for_add.borrow() {
+                    (gen)();
+                }
+                if let Some(rb) = &*rebuild_workplace_for_add.borrow() {
+                    (rb)();
+                }
+            }
+        }
+    });
+
+    // Select All / Remove Selected behavior for the pool
+    let file_checks_for_select_all = file_checks.clone();
+    select_all_btn.connect_clicked(move |_| {
+        for cb in file_checks_for_select_all.borrow().iter() {
+            cb.set_active(true);
+        }
+    });
+
+    let files_for_remove = files.clone();
+    let file_checks_for_remove = file_checks.clone();
+    let file_pool_for_remove = file_pool_box.clone();
+    let file_list_label_for_remove = file_list_label.clone();
+    let wp_input_for_remove = wp_input.clone();
+    let preview_gen_for_remove = preview_generator.clone();
+    let filename_entry_for_remove = filename_entry.clone();
+    let rebuild_workplace_for_remove = rebuild_workplace_hook.clone();
+    let files_pages_for_remove = files_pages.clone();
+    remove_selected_btn.connect_clicked(move |_| {
+        let mut to_remove: Vec<usize> = Vec::new();
+        for (i, cb) in file_checks_for_remove.borrow().iter().enumerate() {
+            if cb.is_active() {
+                to_remove.push(i);
+            }
+        }
+        if to_remove.is_empty() {
+            return;
+        }
+        for &i in to_remove.iter().rev() {
+            if let Some(cb) = file_checks_for_remove.borrow_mut().get(i) {
+                file_pool_for_remove.remove(cb);
+            }
+            file_checks_for_remove.borrow_mut().remove(i);
+            files_for_remove.borrow_mut().remove(i);
+            if i < files_pages_for_remove.borrow().len() {
+                files_pages_for_remove.borrow_mut().remove(i);
+            }
+        }
+        let names: Vec<String> = files_for_remove
+            .borrow()
+            .iter()
+            .map(|p| p.file_name().and_then(|s| s.to_str()).unwrap_or("").to_string())
+            .collect();
+        file_list_label_for_remove.set_label(&names.join(", "));
+        wp_input_for_remove.set_label(&format!("Input: {}", names.join(", ")));
+        if files_for_remove.borrow().is_empty() {
+            filename_entry_for_remove.set_text("");
+        }
+        if let Some(gen) = &*preview_gen_for_remove.borrow() {
+            (gen)();
+        }
+        if let Some(rb) = &*rebuild_workplace_for_remove.borrow() {
+            (rb)();
+        }
+    });
+
+    let selected_action: Rc<RefCell<Option<OperationKind>>> = Rc::new(RefCell::new(None));
+    let selected_action_clone = selected_action.clone();
+
+    let output_dir: Rc<RefCell<Option<PathBuf>>> = Rc::new(RefCell::new(None));
+    let output_dir_clone_for_choose = output_dir.clone();
+
+    let toggles: Vec<(ToggleButton, OperationKind)> = vec![
+        (btn_rotate.clone(), OperationKind::Rotate),
+        (btn_split.clone(), OperationKind::Split),
+        (btn_merge.clone(), OperationKind::Merge),
+        (btn_extract.clone(), OperationKind::ExtractText),
+        (btn_images.clone(), OperationKind::ConvertToImages),
+        (btn_compress.clone(), OperationKind::Compress),
+        (btn_optimize.clone(), OperationKind::Optimize),
+    ];
+    let toggles_rc = Rc::new(toggles);
+    let filename_entry_for_toggles = filename_entry.clone();
+    let files_for_toggles = files.clone();
+
+    for (tb, kind) in toggles_rc.iter() {
+        let kind = *kind;
+        let toggles_inner = toggles_rc.clone();
+        let sel_clone = selected_action_clone.clone();
+        let wp_action_clone = wp_action.clone();
+        let filename_entry_local = filename_entry_for_toggles.clone();
+        let files_local = files_for_toggles.clone();
+        tb.connect_toggled(move |t| {
+            if t.is_active() {
+                for (other, _) in toggles_inner.iter() {
+                    if other != t {
+                        other.set_active(false);
+                    }
+                }
+                sel_clone.borrow_mut().replace(kind);
+                wp_action_clone.set_label(&format!("Queued: {}", kind.label()));
+                if !files_local.borrow().is_empty() {
+                    filename_entry_local.set_text(kind.default_output_template());
+                }
+            } else {
+                let any = toggles_inner.iter().any(|(b, _)| b.is_active());
+                if !any {
+                    sel_clone.borrow_mut().take();
+                    wp_action_clone.set_label("");
+                }
+            }
+        });
+    }
+
+    let rotate_modes: Vec<ToggleButton> = vec![rot_90_cw.clone(), rot_90_ccw.clone(), rot_180.clone()];
+    let rotate_modes_rc = Rc::new(rotate_modes);
+    for rm in rotate_modes_rc.iter() {
+        let others = rotate_modes_rc.clone();
+        rm.connect_toggled(move |b| {
+            if b.is_active() {
+                for o in others.iter() {
+                    if o != b {
+                        o.set_active(false);
+                    }
+                }
+            }
+        });
+    }
+
+    let preview_gen_cl = preview_generator.clone();
+    let files_for_preview_toggle = files.clone();
+    let btn_rotate_clone_for_preview = btn_rotate.clone();
+    for r in vec![rot_90_cw.clone(), rot_90_ccw.clone(), rot_180.clone()].into_iter() {
+        let gen_clone = preview_gen_cl.clone();
+        let files_local = files_for_preview_toggle.clone();
+        let rotate_active = btn_rotate_clone_for_preview.clone();
+        r.connect_toggled(move |b| {
+            if b.is_active() && rotate_active.is_active() && !files_local.borrow().is_empty() {
+                if let Some(gen) = &*gen_clone.borrow() {
+                    gen();
+                }
+            }
+        });
+    }
+
+    // Update compress_box and rotate_box visibility when relevant function toggles change
+    let compress_box_cl = compress_box.clone();
+    btn_compress.connect_toggled(move |b| {
+        compress_box_cl.set_visible(b.is_active());
+    });
+
+    let rotate_box_cl = rotate_box.clone();
+    let btn_rotate_cl = btn_rotate.clone();
+    btn_rotate_cl.connect_toggled(move |b| {
+        rotate_box_cl.set_visible(b.is_active());
+    });
+    let split_box_cl = split_box.clone();
+    btn_split.connect_toggled(move |b| {
+        split_box_cl.set_visible(b.is_active());
+    });
+    let text_box_cl = text_box.clone();
+    btn_extract.connect_toggled(move |b| {
+        text_box_cl.set_visible(b.is_active());
+    });
+    let merge_box_cl = merge_box.clone();
+    let files_pages_for_merge = files_pages.clone();
+    btn_merge.connect_toggled(move |b| {
+        merge_box_cl.set_visible(b.is_active());
+        for e in files_pages_for_merge.borrow().iter() {
+            e.set_visible(b.is_active());
+        }
+    });
+
+    choose_folder_btn.connect_clicked(move |_| {
+        if let Ok(out) = std::process::Command::new("zenity").arg("--file-selection").arg("--directory").output() {
+            if out.status.success() {
+                let path = String::from_utf8_lossy(&out.stdout).trim().to_string();
+                if !path.is_empty() {
+                    output_dir_clone_for_choose.borrow_mut().replace(PathBuf::from(path));
+                }
+            }
+        }
+    });
+
+    let choose_folder_btn_clone = choose_folder_btn.clone();
+    same_location.connect_toggled(move |c| {
+        choose_folder_btn_clone.set_sensitive(!c.is_active());
+    });
+
+    let files_for_run = files.clone();
+    let window_for_run = window.clone();
+    let selected_action_for_run = selected_action.clone();
+    let output_dir_for_run = output_dir.clone();
+    let filename_entry_for_run = filename_entry.clone();
+    let wp_status_clone = wp_status.clone();
+    let wp_input_for_run = wp_input.clone();
+    let open_output_btn_clone = open_output_btn.clone();
+    let files_progress_for_run = files_progress.clone();
+    let files_output_labels_for_run = files_output_labels.clone();
+    let same_location_for_run = same_location.clone();
+    let file_checks_for_run = file_checks.clone();
+
+    let run_btn_for_connect = run_button.clone();
+    let run_btn_for_action = run_button.clone();
+    // progress handle of the running operation, used by the Cancel button
+    let current_progress: Rc<RefCell<Vec<Progress>>> = Rc::new(RefCell::new(Vec::new()));
+    let current_progress_for_run = current_progress.clone();
+    let cancel_btn_for_run = cancel_button.clone();
+    let pages_entry_for_run = pages_entry.clone();
+    let rot_90_ccw_cl = rot_90_ccw.clone();
+    let rot_180_cl = rot_180.clone();
+    let keep_bookmarks_for_run = keep_bookmarks.clone();
+    let split_mode_for_run = split_mode.clone();
+    let split_arg_for_run = split_arg.clone();
+    let split_names_for_run = split_names.clone();
+    let split_render_for_run = split_render.clone();
+    let split_drop_for_run = split_drop.clone();
+    let text_mode_for_run = text_mode.clone();
+    let text_format_for_run = text_format.clone();
+    let text_pages_for_run = text_pages.clone();
+    let text_per_page_for_run = text_per_page.clone();
+    let compress_preset_for_run = compress_preset.clone();
+    let compress_quality_for_run = compress_quality.clone();
+    let compress_dpi_for_run = compress_dpi.clone();
+    let compress_min_size_for_run = compress_min_size.clone();
+    let files_pages_for_run = files_pages.clone();
+
+    run_btn_for_connect.connect_clicked(move |_| {
+        let current_files = files_for_run.borrow().clone();
+        if current_files.is_empty() {
+            let dialog = gtk::MessageDialog::builder()
+                .transient_for(&window_for_run)
+                .modal(true)
+                .text("No files dropped. Please drag PDF files into the drop area.")
+                .build();
+            dialog.add_button("OK", gtk::ResponseType::Ok);
+            dialog.connect_response(|d, _| {
+                d.close();
+            });
+            dialog.show();
+            return;
+        }
+
+        // single-file operations run on every checked file of the pool (all files when none is checked)
+        let checked: Vec<usize> = file_checks_for_run
+            .borrow()
+            .iter()
+            .enumerate()
+            .filter(|(_, cb)| cb.is_active())
+            .map(|(i, _)| i)
+            .collect();
+        let selected: Vec<usize> = if checked.is_empty() { (0..current_files.len()).collect() } else { checked };
+
+        // update workplace input label
+        let names: Vec<String> = selected.iter().map(|&i| current_files[i].file_name().and_then(|s| s.to_str()).unwrap_or("").to_string()).collect();
+        wp_input_for_run.set_label(&format!("Input: {}", names.join(", ")));
+
+        // determine output dir; None means next to each input
+        let out_dir = if same_location_for_run.is_active() {
+            None
+        } else {
+            match output_dir_for_run.borrow().clone() {
+                Some(dir) => Some(dir),
+                None => {
+                    wp_status_clone.set_label("Output folder: (not set)");
+                    return;
+                }
+            }
+        };
+        let out_dir_display = out_dir.as_ref().map(|p| p.to_string_lossy().to_string()).unwrap_or_else(|| "(input folder)".to_string());
+        wp_status_clone.set_label(&format!("Output folder: {}", out_dir_display));
+
+        // output filename template
+        let template = filename_entry_for_run.text().as_str().to_string();
+        if template.is_empty() {
+            wp_status_clone.set_label(&format!("Output folder: {} — missing filename", out_dir_display));
+            return;
+        }
+
+        // selected action
+        let kind = match *selected_action_for_run.borrow() {
+            Some(kind) => kind,
+            None => {
+                let dialog = gtk::MessageDialog::builder()
+                    .transient_for(&window_for_run)
+                    .modal(true)
+                    .text("No action selected. Please choose a function block.")
+                    .build();
+                dialog.add_button("OK", gtk::ResponseType::Ok);
+                dialog.connect_response(|d, _| {
+                    d.close();
+                });
+                dialog.show();
+                return;
+            }
+        };
+        // build the typed operation from the UI state (GTK objects stay on the main thread)
+        let operation = match kind {
+            OperationKind::Merge => {
+                let bookmarks = if keep_bookmarks_for_run.is_active() { BookmarkMode::Nested } else { BookmarkMode::Flat };
+                let mut pages = Vec::new();
+                for (n, &i) in selected.iter().enumerate() {
+                    let spec = files_pages_for_run.borrow().get(i).map(|e| e.text().trim().to_string()).unwrap_or_default();
+                    if spec.is_empty() {
+                        pages.push(None);
+                        continue;
+                    }
+                    match pdf_tools::parse_page_sequence(&spec) {
+                        Ok(p) => pages.push(Some(p)),
+                        Err(e) => {
+                            wp_status_clone.set_label(&format!("Error: {}: {}", names[n], e));
+                            return;
+                        }
+                    }
+                }
+                Operation::Merge(MergeOptions { bookmarks, pages })
+            }
+            OperationKind::Split => {
+                let arg = split_arg_for_run.text().trim().to_string();
+                let mode = match split_mode_for_run.selected() {
+                    1 => arg
+                        .parse::<u32>()
+                        .map(SplitMode::Chunks)
+                        .map_err(|_| pdf_tools::Error::InvalidArgument(format!("Invalid chunk size: {}", arg))),
+                    2 => pdf_tools::parse_page_ranges(&arg).map(SplitMode::Ranges),
+                    3 => Ok(SplitMode::OddPages),
+                    4 => Ok(SplitMode::EvenPages),
+                    5 if arg.is_empty() => Ok(SplitMode::Bookmarks(1)),
+                    5 => arg
+                        .parse::<u32>()
+                        .map(SplitMode::Bookmarks)
+                        .map_err(|_| pdf_tools::Error::InvalidArgument(format!("Invalid bookmark depth: {}", arg))),
+                    6 => format!("size:{}", arg).parse::<SplitMode>(),
+                    7 => Ok(SplitMode::BlankPages { render: split_render_for_run.is_active(), drop: split_drop_for_run.is_active() }),
+                    _ => Ok(SplitMode::EveryPage),
+                };
+                let mode = match mode {
+                    Ok(mode) => mode,
+                    Err(e) => {
+                        wp_status_clone.set_label(&format!("Error: {}", e));
+                        return;
+                    }
+                };
+                let names = split_names_for_run.text().trim().to_string();
+                let name_template = if names.is_empty() { None } else { Some(names) };
+                Operation::Split(SplitOptions { mode, name_template })
+            }
+            OperationKind::ExtractText => {
+                let mode = if text_mode_for_run.selected() == 1 { TextMode::Layout } else { TextMode::Plain };
+                let format = match text_format_for_run.selected() {
+                    1 => TextFormat::Json,
+                    2 => TextFormat::Csv,
+                    _ => TextFormat::Text,
+                };
+                let pages_text = text_pages_for_run.text().as_str().trim().to_string();
+                let pages = if pages_text.is_empty() {
+                    None
+                } else {
+                    match pdf_tools::parse_pages(&pages_text) {
+                        Ok(pages) => Some(pages),
+                        Err(e) => {
+                            wp_status_clone.set_label(&format!("Error: {}", e));
+                            return;
+                        }
+                    }
+                };
+                Operation::ExtractText(TextOptions { mode, format, pages, per_page: text_per_page_for_run.is_active() })
+            }
+            OperationKind::ConvertToImages => Operation::ConvertToImages(ImageOptions::default()),
+            OperationKind::Optimize => Operation::Optimize,
+            OperationKind::Compress => {
+                let options = match compress_presets.get(compress_preset_for_run.selected() as usize) {
+                    Some(preset) => Ok(preset.options()),
+                    None => {
+                        let quality = compress_quality_for_run.text().trim().to_string();
+                        let dpi = compress_dpi_for_run.text().trim().to_string();
+                        let min_size = compress_min_size_for_run.text().trim().to_string();
+                        quality
+                            .parse::<u8>()
+                            .map_err(|_| pdf_tools::Error::InvalidArgument(format!("Invalid JPEG quality: {}", quality)))
+                            .and_then(|quality| {
+                                let max_dpi = if dpi.is_empty() {
+                                    None
+                                } else {
+                                    Some(dpi.parse::<u32>().map_err(|_| pdf_tools::Error::InvalidArgument(format!("Invalid DPI: {}", dpi)))?)
+                                };
+                                let min_image_size = if min_size.is_empty() { 0 } else { pdf_tools::parse_size(&min_size)? };
+                                Ok(CompressOptions { quality, max_dpi, min_image_size })
+                            })
+                    }
+                };
+                match options {
+                    Ok(options) => Operation::Compress(options),
+                    Err(e) => {
+                        wp_status_clone.set_label(&format!("Error: {}", e));
+                        return;
+                    }
+                }
+            }
+            OperationKind::Rotate => {
+                let degrees = if rot_90_ccw_cl.is_active() {
+                    -90
+                } else if rot_180_cl.is_active() {
+                    180
+                } else {
+                    90
+                };
+                let pages_text = pages_entry_for_run.text().as_str().trim().to_string();
+                let pages = if pages_text.is_empty() {
+                    None
+                } else {
+                    match pdf_tools::parse_pages(&pages_text) {
+                        Ok(pages) => Some(pages),
+                        Err(e) => {
+                            wp_status_clone.set_label(&format!("Error: {}", e));
+                            return;
+                        }
+                    }
+                };
+                Operation::Rotate(RotateOptions { degrees, pages })
+            }
+        };
+        if let Err(e) = operation.validate() {
+            wp_status_clone.set_label(&format!("Error: {}", e));
+            return;
+        }
+        if !kind.accepts_multiple_inputs() && !operation.output_is_folder() && selected.len() > 1 && !pdf_tools::is_per_file_template(&template) {
+            wp_status_clone.set_label("Error: the output filename must contain {stem} or {index} when processing several files");
+            return;
+        }
+
+        // merge combines the selection into one job; other operations get one job per file
+        let dir_for = |p: &Path| out_dir.clone().unwrap_or_else(|| p.parent().map(Path::to_path_buf).unwrap_or_default());
+        let jobs: Vec<BatchJob> = if kind.accepts_multiple_inputs() {
+            let first = &current_files[selected[0]];
+            vec![BatchJob {
+                rows: selected.clone(),
+                inputs: selected.iter().map(|&i| current_files[i].clone()).collect(),
+                target: dir_for(first).join(pdf_tools::output_name(&template, first, 1)),
+            }]
+        } else {
+            selected
+                .iter()
+                .enumerate()
+                .map(|(n, &i)| {
+                    let input = &current_files[i];
+                    // folder outputs are named by the backend after the input stem
+                    let target = if operation.output_is_folder() {
+                        dir_for(input)
+                    } else {
+                        dir_for(input).join(pdf_tools::output_name(&template, input, n + 1))
+                    };
+                    BatchJob { rows: vec![i], inputs: vec![input.clone()], target }
+                })
+                .collect()
+        };
+
+        // set brief status for user
+        wp_status_clone.set_label(&format!("Running: {} ({} job{})", kind.label(), jobs.len(), if jobs.len() == 1 { "" } else { "s" }));
+        run_btn_for_action.set_sensitive(false);
+        open_output_btn_clone.set_sensitive(false);
+
+        // before running, reset every row and show the expected output of the selected ones
+        for b in files_progress_for_run.borrow().iter() {
+            b.set_fraction(0.0);
+        }
+        {
+            let outs = files_output_labels_for_run.borrow();
+            for lbl in outs.iter() {
+                set_row_output(lbl, "", None);
+            }
+            for job in &jobs {
+                for &row in &job.rows {
+                    let expected = if operation.output_is_folder() { job.target.join(operation.default_output_name(&current_files[row])) } else { job.target.clone() };
+                    if let Some(lbl) = outs.get(row) {
+                        set_row_output(lbl, &expected.to_string_lossy(), None);
+                    }
+                }
+            }
+        }
+
+        let progresses: Vec<Progress> = jobs.iter().map(|_| Progress::new()).collect();
+        *current_progress_for_run.borrow_mut() = progresses.clone();
+        cancel_btn_for_run.set_sensitive(true);
+
+        // poll the backend's progress counters and mirror them on each job's rows;
+        // jobs run one after another, so the first unfinished one is the active one
+        let finished = Rc::new(std::cell::Cell::new(0usize));
+        let progress_id: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+        let files_progress_for_poll = files_progress_for_run.clone();
+        let progresses_for_poll = progresses.clone();
+        let job_rows: Rc<Vec<Vec<usize>>> = Rc::new(jobs.iter().map(|j| j.rows.clone()).collect());
+        let job_rows_for_poll = job_rows.clone();
+        let finished_for_poll = finished.clone();
+        let id = glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
+            let active = finished_for_poll.get();
+            if let (Some(progress), Some(rows)) = (progresses_for_poll.get(active), job_rows_for_poll.get(active)) {
+                let fraction = progress.snapshot().fraction();
+                let bars = files_progress_for_poll.borrow();
+                for b in rows.iter().filter_map(|&r| bars.get(r)) {
+                    match fraction {
+                        Some(f) => b.set_fraction(f),
+                        None => b.pulse(),
+                    }
+                }
+            }
+            glib::Continue(true)
+        });
+        *progress_id.borrow_mut() = Some(id);
+
+        // spawn background thread to run the jobs; use a glib channel to update UI safely
+        let (sender, receiver) = glib::MainContext::channel::<(usize, pdf_tools::Result<()>)>(glib::PRIORITY_DEFAULT);
+
+        // attach receiver on main context to update each job's rows as it finishes
+        let files_progress_for_recv = files_progress_for_run.clone();
+        let files_output_labels_for_recv = files_output_labels_for_run.clone();
+        let wp_status_after = wp_status_clone.clone();
+        let open_btn_after = open_output_btn_clone.clone();
+        let run_btn_after = run_btn_for_action.clone();
+        let cancel_btn_after = cancel_btn_for_run.clone();
+        let current_progress_after = current_progress_for_run.clone();
+        let progress_id_for_recv = progress_id.clone();
+        let window_after = window_for_run.clone();
+        let job_count = jobs.len();
+        let (mut succeeded, mut failed, mut cancelled) = (0usize, 0usize, 0usize);
+        let mut only_error: Option<pdf_tools::Error> = None;
+        receiver.attach(None, move |(job, res)| {
+            let bars = files_progress_for_recv.borrow();
+            let outs = files_output_labels_for_recv.borrow();
+            for &row in &job_rows[job] {
+                let (bar, lbl) = match (bars.get(row), outs.get(row)) {
+                    (Some(bar), Some(lbl)) => (bar, lbl),
+                    _ => continue,
+                };
+                match &res {
+                    Ok(_) => {
+                        bar.set_fraction(1.0);
+                        lbl.add_css_class("success");
+                    }
+                    Err(pdf_tools::Error::Cancelled) => {
+                        bar.set_fraction(0.0);
+                        set_row_output(lbl, "Cancelled", None);
+                    }
+                    Err(e) => {
+                        bar.set_fraction(0.0);
+                        set_row_output(lbl, &format!("✗ {}", error_headline(e)), Some(&e.to_string()));
+                        lbl.add_css_class("error");
+                    }
+                }
+            }
+            match res {
+                Ok(_) => succeeded += 1,
+                Err(pdf_tools::Error::Cancelled) => cancelled += 1,
+                Err(e) => {
+                    failed += 1;
+                    if job_count == 1 {
+                        only_error = Some(e);
+                    }
+                }
+            }
+            finished.set(job + 1);
+            if job + 1 < job_count {
+                return glib::Continue(true);
+            }
+
+            // all jobs done: stop polling and summarize
+            if let Some(src) = progress_id_for_recv.borrow_mut().take() {
+                src.remove();
+            }
+            if let Some(e) = only_error.take() {
+                wp_status_after.set_label(&format!("Error: {}", error_headline(&e)));
+                show_error_dialog(&window_after, &e);
+            } else if job_count == 1 && cancelled == 1 {
+                wp_status_after.set_label("Cancelled — no output was written");
+            } else if failed == 0 && cancelled == 0 {
+                wp_status_after.set_label("Completed");
+            } else {
+                let mut summary = format!("Completed {} of {}", succeeded, job_count);
+                if failed > 0 {
+                    summary.push_str(&format!(", {} failed", failed));
+                }
+                if cancelled > 0 {
+                    summary.push_str(&format!(", {} cancelled", cancelled));
+                }
+                wp_status_after.set_label(&summary);
+            }
+            open_btn_after.set_sensitive(succeeded > 0);
+            current_progress_after.borrow_mut().clear();
+            cancel_btn_after.set_sensitive(false);
+            run_btn_after.set_sensitive(true);
+            glib::Continue(false)
+        });
+
+        std::thread::spawn(move || {
+            for (i, (job, progress)) in jobs.into_iter().zip(progresses).enumerate() {
+                // a cancelled batch skips the remaining jobs
+                let res = if progress.is_cancelled() {
+                    Err(pdf_tools::Error::Cancelled)
+                } else {
+                    // a bug tripped by an unusual file must not take down the thread, and
+                    // with it the remaining jobs and the final report
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| operation.run(&job.inputs, &job.target, &progress)))
+                        .unwrap_or_else(|panic| Err(pdf_tools::Error::Internal(format!("Processing stopped unexpectedly: {}", panic_message(&*panic)))))
+                };
+                let _ = sender.send((i, res));
+            }
+        });
+    });
+
+    let current_progress_for_cancel = current_progress.clone();
+    let wp_status_for_cancel = wp_status.clone();
+    cancel_button.connect_clicked(move |b| {
+        let progresses = current_progress_for_cancel.borrow();
+        if !progresses.is_empty() {
+            for progress in progresses.iter() {
+                progress.cancel();
+            }
+            wp_status_for_cancel.set_label("Cancelling…");
+            b.set_sensitive(false);
+        }
+    });
+
+    // Open output folder button — resolve folder the same way Run does
+    let output_dir_for_open2 = output_dir.clone();
+    let files_for_open = files.clone();
+    let same_location_for_open = same_location.clone();
+    let window_for_open = window.clone();
+    open_output_btn.connect_clicked(move |_| {
+        // Determine folder: if "use input folder" is active, open parent of first input,
+        // otherwise open the chosen output_dir (if set).
+        let folder_opt: Option<PathBuf> = if same_location_for_open.is_active() {
+            files_for_open
+                .borrow()
+                .first()
+                .and_then(|p| p.parent().map(|pp| pp.to_path_buf()))
+        } else {
+            output_dir_for_open2.borrow().clone()
+        };
+
+        if let Some(folder) = folder_opt {
+            if folder.exists() {
+                match std::process::Command::new("xdg-open").arg(&folder).spawn() {
+                    Ok(_) => {},
+                    Err(e) => {
+                        let dialog = gtk::MessageDialog::builder()
+                            .transient_for(&window_for_open)
+                            .modal(true)
+                            .text(format!("Failed to open folder: {}", e))
+                            .build();
+                        dialog.add_button("OK", gtk::ResponseType::Ok);
+                        dialog.connect_response(|d, _| { d.close(); });
+                        dialog.show();
+                    }
+                }
+            } else {
+                let dialog = gtk::MessageDialog::builder()
+                    .transient_for(&window_for_open)
+                    .modal(true)
+                    .text(format!("Output folder does not exist: {}", folder.to_string_lossy()))
+                    .build();
+                dialog.add_button("OK", gtk::ResponseType::Ok);
+                dialog.connect_response(|d, _| { d.close(); });
+                dialog.show();
+            }
+        } else {
+            let dialog = gtk::MessageDialog::builder()
+                .transient_for(&window_for_open)
+                .modal(true)
+                .text("Output folder not set")
+                .build();
+            dialog.add_button("OK", gtk::ResponseType::Ok);
+            dialog.connect_response(|d, _| { d.close(); });
+            dialog.show();
+        }
+    });
+
+    window.present();
+}
+
+/// Work item of a run: merge has one job for all selected rows, other operations one per row.
+struct BatchJob {
+    rows: Vec<usize>,
+    inputs: Vec<PathBuf>,
+    target: PathBuf,
+}
+
+/// Show `text` in a row's output label, eliding the front of long paths.
+fn set_row_output(lbl: &Label, text: &str, tooltip: Option<&str>) {
+    let max_out = 36;
+    lbl.remove_css_class("success");
+    lbl.remove_css_class("error");
+    if text.chars().count() > max_out {
+        let short: String = text.chars().skip(text.chars().count() - (max_out - 1)).collect();
+        lbl.set_label(&format!("…{}", short));
+        lbl.set_tooltip_text(Some(tooltip.unwrap_or(text)));
+    } else {
+        lbl.set_label(text);
+        lbl.set_tooltip_text(tooltip);
+    }
+}
+
+/// Placeholder of the part name entry, naming the default template of the split
+/// mode at `selected` in the mode dropdown.
+fn split_names_placeholder(selected: u32) -> String {
+    // the arguments do not change the template
+    let mode = match selected {
+        1 => SplitMode::Chunks(1),
+        2 => SplitMode::Ranges(Vec::new()),
+        3 => SplitMode::OddPages,
+        4 => SplitMode::EvenPages,
+        5 => SplitMode::Bookmarks(1),
+        6 => SplitMode::MaxSize(0),
+        7 => SplitMode::BlankPages { render: false, drop: false },
+        _ => SplitMode::EveryPage,
+    };
+    format!("Part names (default {})", mode.default_name_template())
+}
+
+/// The message a panic was raised with.
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("internal error")
+}
+
+/// One-line summary of an error for the workplace status label.
+fn error_headline(e: &pdf_tools::Error) -> &'static str {
+    match e {
+        pdf_tools::Error::Load { .. } => "could not open input file",
+        pdf_tools::Error::Parse { .. } => "input is not a valid PDF",
+        pdf_tools::Error::Encrypted { .. } => "input PDF is password protected",
+        pdf_tools::Error::Malformed(_) => "input PDF is damaged",
+        pdf_tools::Error::Unsupported(_) => "operation not supported for this input",
+        pdf_tools::Error::InvalidArgument(_) => "invalid options",
+        pdf_tools::Error::Io { .. } => "could not write output",
+        pdf_tools::Error::ExternalTool { .. } => "external tool failed",
+        pdf_tools::Error::Internal(_) => "internal error",
+        pdf_tools::Error::Cancelled => "cancelled",
+    }
+}
+
+fn show_error_dialog(window: &ApplicationWindow, e: &pdf_tools::Error) {
+    let mut details = e.to_string();
+    if let pdf_tools::Error::ExternalTool { tool, .. } = e {
+        details.push_str(&format!("\n\nMake sure `{}` is installed (package poppler-utils).", tool));
+    }
+    let dialog = gtk::MessageDialog::builder()
+        .transient_for(window)
+        .modal(true)
+        .message_type(gtk::MessageType::Error)
+        .text(error_headline(e))
+        .secondary_text(details)
+        .build();
+    dialog.add_button("OK", gtk::ResponseType::Ok);
+    dialog.connect_response(|d, _| {
+        d.close();
+    });
+    dialog.show();
+}
@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::document::{output_folder, CreatedFiles};
+use crate::error::{Error, Result};
+use crate::progress::Progress;
+
+/// Image file format produced by [`convert_to_images`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    Jpeg,
+}
+
+impl ImageFormat {
+    fn pdftoppm_flag(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "-png",
+            ImageFormat::Jpeg => "-jpeg",
+        }
+    }
+}
+
+impl std::str::FromStr for ImageFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "png" => Ok(ImageFormat::Png),
+            "jpeg" | "jpg" => Ok(ImageFormat::Jpeg),
+            other => Err(Error::InvalidArgument(format!("Unknown image format: {}", other))),
+        }
+    }
+}
+
+/// Settings for [`convert_to_images`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImageOptions {
+    pub format: ImageFormat,
+}
+
+/// Render every page of `input` to an image file using `pdftoppm`.
+///
+/// Images are written into a `{stem}-images` folder under `output_dir`
+/// (or into `output_dir` itself when it already has that name).
+pub fn convert_to_images(input: &Path, output_dir: &Path, options: &ImageOptions, progress: &Progress) -> Result<()> {
+    if !input.exists() {
+        let source = std::io::Error::new(std::io::ErrorKind::NotFound, "input PDF not found");
+        return Err(Error::Load { path: input.to_path_buf(), source });
+    }
+    let format_flag = options.format.pdftoppm_flag();
+
+    let final_out_dir = output_folder(input, output_dir, "images");
+    let mut created = CreatedFiles::default();
+    created.create_dir(&final_out_dir)?;
+    let existing = list_dir(&final_out_dir);
+
+    // best effort: pdftoppm reads some files lopdf cannot, so the total may stay unknown
+    if let Ok(doc) = lopdf::Document::load(input) {
+        progress.add_total(doc.get_pages().len() as u64);
+    }
+
+    let prefix = final_out_dir.join(input.file_stem().and_then(|s| s.to_str()).unwrap_or("page"));
+    let prefix_str = prefix.to_string_lossy().to_string();
+
+    let mut child = std::process::Command::new("pdftoppm")
+        .arg(format_flag)
+        .arg(input.to_string_lossy().as_ref())
+        .arg(&prefix_str)
+        .spawn()
+        .map_err(|e| Error::ExternalTool { tool: "pdftoppm", message: format!("could not start: {}", e), source: Some(e) })?;
+
+    // pdftoppm writes one file per page; poll the folder to report progress
+    let new_files = || list_dir(&final_out_dir).into_iter().filter(|p| !existing.contains(p)).collect::<Vec<_>>();
+    let mut reported = 0;
+    let status = loop {
+        let produced = new_files().len();
+        while reported < produced {
+            progress.page_done();
+            reported += 1;
+        }
+        if progress.is_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) => std::thread::sleep(Duration::from_millis(100)),
+            Err(e) => {
+                let _ = child.kill();
+                return Err(Error::ExternalTool { tool: "pdftoppm", message: e.to_string(), source: Some(e) });
+            }
+        }
+    };
+    // anything pdftoppm wrote is removed again unless the run succeeds
+    for p in new_files() {
+        created.push(p);
+    }
+    let status = status.ok_or(Error::Cancelled)?;
+
+    if !status.success() {
+        return Err(Error::ExternalTool { tool: "pdftoppm", message: format!("exited with {}", status), source: None });
+    }
+
+    created.keep();
+    Ok(())
+}
+
+/// Render every page of `input` in grayscale at `dpi` with `pdftoppm`, in page order.
+pub(crate) fn render_gray_pages(input: &Path, dpi: u32) -> Result<Vec<image::GrayImage>> {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let run = RUNS.fetch_add(1, Ordering::Relaxed);
+    let dir = std::env::temp_dir().join(format!("pdf-tools-render-{}-{}", std::process::id(), run));
+    std::fs::create_dir_all(&dir).map_err(|e| Error::io(&dir, e))?;
+    let rendered = render_into(input, dpi, &dir);
+    let _ = std::fs::remove_dir_all(&dir);
+    rendered
+}
+
+fn render_into(input: &Path, dpi: u32, dir: &Path) -> Result<Vec<image::GrayImage>> {
+    let status = std::process::Command::new("pdftoppm")
+        .args(["-png", "-gray", "-r", &dpi.to_string()])
+        .arg(input)
+        .arg(dir.join("page"))
+        .status()
+        .map_err(|e| Error::ExternalTool { tool: "pdftoppm", message: format!("could not start: {}", e), source: Some(e) })?;
+    if !status.success() {
+        return Err(Error::ExternalTool { tool: "pdftoppm", message: format!("exited with {}", status), source: None });
+    }
+    // files are named page-1.png or page-001.png depending on the page count
+    let mut files: Vec<(u32, PathBuf)> = list_dir(dir)
+        .into_iter()
+        .filter_map(|p| Some((p.file_stem()?.to_str()?.rsplit('-').next()?.parse().ok()?, p)))
+        .collect();
+    files.sort();
+    files
+        .iter()
+        .map(|(_, p)| {
+            image::open(p).map(|img| img.to_luma8()).map_err(|e| Error::ExternalTool {
+                tool: "pdftoppm",
+                message: format!("unreadable image {}: {}", p.display(), e),
+                source: None,
+            })
+        })
+        .collect()
+}
+
+fn list_dir(dir: &Path) -> HashSet<PathBuf> {
+    std::fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|e| e.ok().map(|e| e.path())).collect())
+        .unwrap_or_default()
+}
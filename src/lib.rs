@@ -0,0 +1,42 @@
+//! PDF operations behind the PDF Toolset application.
+//!
+//! Every operation reads its input from disk and writes its result to a path
+//! chosen by the caller, so the crate can be used without the GTK interface:
+//!
+//! ```no_run
+//! use std::path::{Path, PathBuf};
+//! use pdf_tools::Progress;
+//!
+//! let inputs = vec![PathBuf::from("a.pdf"), PathBuf::from("b.pdf")];
+//! pdf_tools::merge(&inputs, Path::new("merged.pdf"), &Default::default(), &Progress::new()).unwrap();
+//! pdf_tools::compress(Path::new("merged.pdf"), Path::new("small.pdf"), &Default::default(), &Progress::new()).unwrap();
+//! ```
+
+mod compress;
+mod document;
+mod error;
+mod graphics;
+mod images;
+mod merge;
+mod operation;
+mod optimize;
+mod outline;
+mod pages;
+mod progress;
+mod rotate;
+mod split;
+mod template;
+mod text;
+
+pub use compress::{compress, CompressOptions, CompressOutcome, CompressPreset, CompressReport};
+pub use error::{Error, Result};
+pub use images::{convert_to_images, ImageFormat, ImageOptions};
+pub use merge::{merge, BookmarkMode, MergeOptions};
+pub use operation::{Operation, OperationKind};
+pub use optimize::optimize;
+pub use pages::{parse_page_ranges, parse_page_sequence, parse_pages};
+pub use progress::{Progress, ProgressSnapshot};
+pub use rotate::{rotate, RotateOptions};
+pub use split::{parse_size, split, SplitMode, SplitOptions, SplitPart};
+pub use template::{is_per_file_template, output_name};
+pub use text::{extract_text, TextFormat, TextMode, TextOptions};
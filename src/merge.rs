@@ -0,0 +1,514 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use lopdf::{Bookmark, Dictionary, Document, Object, ObjectId};
+
+use crate::document::{inherit_page_attributes, load_document, prune_unreachable, save_document};
+use crate::error::{Error, Result};
+use crate::outline::{build_outline_root, read_outline, retain_outline, text_string, OutlineNode};
+use crate::progress::Progress;
+
+/// How the bookmarks of the merged document are built.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BookmarkMode {
+    /// One top-level bookmark per input, named after the file, holding that input's own outline.
+    #[default]
+    Nested,
+    /// One `Page_N` bookmark per input pointing at its first page; source outlines are dropped.
+    Flat,
+}
+
+impl std::str::FromStr for BookmarkMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "nested" => Ok(BookmarkMode::Nested),
+            "flat" => Ok(BookmarkMode::Flat),
+            other => Err(Error::InvalidArgument(format!("Unknown bookmark mode: {}", other))),
+        }
+    }
+}
+
+/// Settings for [`merge`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeOptions {
+    pub bookmarks: BookmarkMode,
+    /// Pages to take from each input, by position in `inputs`; a missing or `None`
+    /// entry takes every page. Pages are used in the given order, see
+    /// [`crate::parse_page_sequence`].
+    pub pages: Vec<Option<Vec<u32>>>,
+}
+
+/// Top-level bookmark of one input in [`BookmarkMode::Nested`].
+struct InputOutline {
+    title: String,
+    first_page: ObjectId,
+    items: Vec<OutlineNode>,
+}
+
+/// Concatenate the pages of `inputs` in order into a single PDF at `output`.
+///
+/// The same file may appear several times, each time with its own page
+/// selection from [`MergeOptions::pages`]. Bookmarks are built according to
+/// [`MergeOptions::bookmarks`].
+pub fn merge(inputs: &[PathBuf], output: &Path, options: &MergeOptions, progress: &Progress) -> Result<()> {
+    if inputs.is_empty() {
+        return Err(Error::InvalidArgument("No input files provided".into()));
+    }
+    if options.pages.len() > inputs.len() {
+        return Err(Error::InvalidArgument(format!(
+            "Page selections given for {} inputs, but there are only {}",
+            options.pages.len(),
+            inputs.len()
+        )));
+    }
+
+    // one step per input, one per merged page, one for saving
+    progress.add_total(inputs.len() as u64 + 1);
+    let mut uses: HashMap<&Path, usize> = HashMap::new();
+    let mut loaded: HashMap<&Path, Document> = HashMap::new();
+    for p in inputs {
+        progress.check()?;
+        *uses.entry(p.as_path()).or_default() += 1;
+        if !loaded.contains_key(p.as_path()) {
+            loaded.insert(p, load_document(p)?);
+        }
+        progress.step();
+    }
+
+    let mut max_id: u32 = 1;
+    let mut document = Document::with_version("1.5");
+    let mut kids: Vec<ObjectId> = Vec::new();
+    let mut catalog: Option<(ObjectId, Dictionary)> = None;
+    let mut input_outlines: Vec<InputOutline> = Vec::new();
+    let mut forms = FormMerger::default();
+
+    for (i, path) in inputs.iter().enumerate() {
+        // a file used several times is copied for each use, so its pages get distinct objects
+        let remaining = uses.entry(path.as_path()).or_default();
+        *remaining = remaining.saturating_sub(1);
+        let doc = if *remaining == 0 { loaded.remove(path.as_path()) } else { loaded.get(path.as_path()).cloned() };
+        let mut doc = doc.ok_or_else(|| Error::Malformed(format!("{} was not loaded", path.display())))?;
+        doc.renumber_objects_with(max_id);
+        max_id = doc.max_id + 1;
+
+        let pages = doc.get_pages();
+        let selection = options.pages.get(i).and_then(Option::as_ref);
+        let selected: Vec<ObjectId> = match selection {
+            Some(numbers) => numbers
+                .iter()
+                .map(|n| {
+                    pages.get(n).copied().ok_or_else(|| {
+                        Error::InvalidArgument(format!("{} has {} pages, page {} was requested", path.display(), pages.len(), n))
+                    })
+                })
+                .collect::<Result<_>>()?,
+            None => pages.into_values().collect(),
+        };
+        let Some(&first_page) = selected.first() else { continue };
+        progress.add_total(selected.len() as u64);
+
+        let root_id = doc.trailer.get(b"Root").and_then(Object::as_reference).ok();
+        let source_catalog = doc
+            .catalog()
+            .map_err(|_| Error::Malformed(format!("Catalog root not found in {}", path.display())))?
+            .clone();
+        forms.add(&mut doc, &source_catalog, &selected);
+        if catalog.is_none() {
+            catalog = root_id.map(|id| (id, source_catalog));
+        }
+
+        match options.bookmarks {
+            BookmarkMode::Nested => {
+                let wanted: HashSet<ObjectId> = selected.iter().copied().collect();
+                let items = read_outline(&mut doc);
+                let items = retain_outline(&doc, &items, &wanted);
+                let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("Document");
+                let title = match selection {
+                    Some(numbers) => format!("{} ({})", stem, describe_pages(numbers)),
+                    None => stem.to_string(),
+                };
+                input_outlines.push(InputOutline { title, first_page, items });
+            }
+            BookmarkMode::Flat => {
+                let bookmark = Bookmark::new(format!("Page_{}", i + 1), [0.0, 0.0, 1.0], 0, first_page);
+                document.add_bookmark(bookmark, None);
+            }
+        }
+
+        // pages leave their source tree, so they take along what they inherited from it
+        for id in &selected {
+            inherit_page_attributes(&mut doc, *id);
+        }
+        let mut used = HashSet::new();
+        for id in selected {
+            if used.insert(id) {
+                kids.push(id);
+            } else {
+                // a page listed twice needs its own page object; content and resources are shared
+                let mut copy = doc.get_dictionary(id).map_err(|_| Error::Malformed(format!("Missing page object in {}", path.display())))?.clone();
+                let new_id = (max_id, 0);
+                max_id += 1;
+                // an annotation is on a single page, so the copy gets copies of its own
+                if let Ok(annots) = copy.get(b"Annots").cloned() {
+                    let annots = copy_annotations(&mut doc, &annots, new_id, &mut max_id);
+                    if annots.is_empty() {
+                        copy.remove(b"Annots");
+                    } else {
+                        copy.set("Annots", annots);
+                    }
+                }
+                doc.objects.insert(new_id, Object::Dictionary(copy));
+                kids.push(new_id);
+            }
+        }
+        document.objects.extend(doc.objects);
+    }
+
+    let Some((catalog_id, mut catalog)) = catalog else {
+        return Err(Error::Malformed("Pages root not found in input documents".into()));
+    };
+    document.max_id = max_id - 1;
+    let pages_id = document.new_object_id();
+    for id in &kids {
+        progress.check()?;
+        if let Ok(Object::Dictionary(page)) = document.get_object_mut(*id) {
+            page.set("Parent", pages_id);
+        }
+        progress.page_done();
+    }
+
+    let merged_pages: HashSet<ObjectId> = kids.iter().copied().collect();
+    let mut pages = Dictionary::new();
+    pages.set("Type", Object::Name(b"Pages".to_vec()));
+    pages.set("Count", kids.len() as u32);
+    pages.set("Kids", kids.into_iter().map(Object::Reference).collect::<Vec<_>>());
+    document.objects.insert(pages_id, Object::Dictionary(pages));
+
+    catalog.set("Pages", pages_id);
+    catalog.remove(b"Outlines");
+    drop_input_only_entries(&document, &mut catalog, &merged_pages);
+    let outline = match options.bookmarks {
+        BookmarkMode::Flat => document.build_outline(),
+        BookmarkMode::Nested => nest_outlines(&mut document, input_outlines),
+    };
+    if let Some(n) = outline {
+        catalog.set("Outlines", n);
+    }
+    match forms.finish() {
+        Some(acro_form) => catalog.set("AcroForm", acro_form),
+        None => {
+            catalog.remove(b"AcroForm");
+        }
+    }
+    document.objects.insert(catalog_id, Object::Dictionary(catalog));
+    document.trailer.set("Root", catalog_id);
+
+    // unselected pages and the inputs' old page trees are no longer referenced
+    prune_unreachable(&mut document);
+    document.renumber_objects();
+    document.adjust_zero_pages();
+
+    document.compress();
+    progress.check()?;
+    save_document(&mut document, output, progress)?;
+    progress.step();
+    Ok(())
+}
+
+/// Copies of the annotations in `annots` for `page`, a copy of the page they are on,
+/// numbered from `max_id`. Widgets are left out, as a form field cannot gain a widget
+/// this way, and so are pop-ups, which belong to the annotation that opens them.
+fn copy_annotations(doc: &mut Document, annots: &Object, page: ObjectId, max_id: &mut u32) -> Vec<Object> {
+    let items = match annots {
+        Object::Reference(id) => doc.get_object(*id).and_then(Object::as_array).cloned().unwrap_or_default(),
+        Object::Array(items) => items.clone(),
+        _ => Vec::new(),
+    };
+    let mut copies = Vec::new();
+    for item in items {
+        let annot = match &item {
+            Object::Reference(id) => doc.get_dictionary(*id).ok().cloned(),
+            Object::Dictionary(annot) => Some(annot.clone()),
+            _ => None,
+        };
+        let Some(mut annot) = annot else { continue };
+        if matches!(annot.get(b"Subtype").and_then(Object::as_name), Ok(b"Widget" | b"Popup")) {
+            continue;
+        }
+        annot.set("P", page);
+        annot.remove(b"Popup");
+        let id = (*max_id, 0);
+        *max_id += 1;
+        doc.objects.insert(id, Object::Dictionary(annot));
+        copies.push(Object::Reference(id));
+    }
+    copies
+}
+
+/// Short description of a page selection for a bookmark title, e.g. `1, 3-12, 9-7`.
+fn describe_pages(pages: &[u32]) -> String {
+    let mut runs: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < pages.len() {
+        let start = pages[i];
+        let mut end = i;
+        let step = match pages.get(i + 1) {
+            Some(&next) if next == start + 1 => 1i64,
+            Some(&next) if next + 1 == start => -1,
+            _ => 0,
+        };
+        if step != 0 {
+            while end + 1 < pages.len() && pages[end + 1] as i64 == pages[end] as i64 + step {
+                end += 1;
+            }
+        }
+        runs.push(if end == i { start.to_string() } else { format!("{}-{}", start, pages[end]) });
+        i = end + 1;
+    }
+    runs.join(", ")
+}
+
+/// Combines the interactive forms (`/AcroForm`) of the merged inputs.
+///
+/// Field names are fully qualified by their top-level ancestor, so renaming a
+/// top-level field that clashes with one from an earlier input keeps every
+/// field below it distinct as well.
+#[derive(Default)]
+struct FormMerger {
+    found: bool,
+    fields: Vec<Object>,
+    names: HashSet<Vec<u8>>,
+    /// Every field object kept, for filtering `/CO`.
+    kept: HashSet<ObjectId>,
+    resources: Dictionary,
+    need_appearances: bool,
+    sig_flags: i64,
+    calculation_order: Vec<ObjectId>,
+    default_appearance: Option<Object>,
+    quadding: Option<Object>,
+}
+
+impl FormMerger {
+    /// Take the form fields of `doc` that have a widget on one of the `pages` being merged.
+    fn add(&mut self, doc: &mut Document, catalog: &Dictionary, pages: &[ObjectId]) {
+        let Some(form) = catalog
+            .get(b"AcroForm")
+            .ok()
+            .and_then(|f| doc.dereference(f).ok())
+            .and_then(|(_, f)| f.as_dict().ok())
+            .cloned()
+        else {
+            return;
+        };
+        self.found = true;
+
+        let mut annots: HashSet<ObjectId> = HashSet::new();
+        for page in pages {
+            let list = doc
+                .get_dictionary(*page)
+                .ok()
+                .and_then(|p| p.get(b"Annots").ok())
+                .and_then(|a| doc.dereference(a).ok())
+                .and_then(|(_, a)| a.as_array().ok());
+            annots.extend(list.into_iter().flatten().filter_map(|a| a.as_reference().ok()));
+        }
+
+        let fields = deref_array(doc, form.get(b"Fields").ok());
+        for field in fields.iter().filter_map(|f| f.as_reference().ok()) {
+            let mut members = Vec::new();
+            collect_field_tree(doc, field, &mut members, 0);
+            let widgets: Vec<ObjectId> = members.iter().copied().filter(|id| is_widget(doc, *id)).collect();
+            // fields whose widgets all sit on pages left out are dropped; widget-less ones stay
+            if !widgets.is_empty() && !widgets.iter().any(|w| annots.contains(w)) {
+                continue;
+            }
+            let Ok(Object::Dictionary(dict)) = doc.get_object_mut(field) else { continue };
+            // the merged form has a single default appearance and alignment, so keep this input's on its fields
+            for (key, value) in [(b"DA".as_slice(), form.get(b"DA")), (b"Q".as_slice(), form.get(b"Q"))] {
+                if let Ok(value) = value {
+                    if !dict.has(key) {
+                        dict.set(key.to_vec(), value.clone());
+                    }
+                }
+            }
+            if let Ok(Object::String(name, format)) = dict.get(b"T").cloned() {
+                let unique = self.unique_name(&name);
+                if unique != name {
+                    dict.set("T", Object::String(unique.clone(), format));
+                }
+                self.names.insert(unique);
+            }
+            self.kept.extend(members);
+            self.fields.push(Object::Reference(field));
+        }
+
+        if let Some(resources) = form.get(b"DR").ok().and_then(|r| doc.dereference(r).ok()).and_then(|(_, r)| r.as_dict().ok()) {
+            for (category, value) in resources.iter() {
+                let entries = doc.dereference(value).ok().and_then(|(_, e)| e.as_dict().ok());
+                match (entries, self.resources.get_mut(category)) {
+                    // on a clash the earlier input's resource wins
+                    (Some(entries), Ok(Object::Dictionary(merged))) => {
+                        for (name, value) in entries.iter() {
+                            if !merged.has(name) {
+                                merged.set(name.clone(), value.clone());
+                            }
+                        }
+                    }
+                    (Some(entries), Err(_)) => self.resources.set(category.clone(), entries.clone()),
+                    (None, Err(_)) => self.resources.set(category.clone(), value.clone()),
+                    (None, Ok(_)) | (Some(_), Ok(_)) => {}
+                }
+            }
+        }
+        self.need_appearances |= form.get(b"NeedAppearances").and_then(Object::as_bool).unwrap_or(false);
+        self.sig_flags |= form.get(b"SigFlags").and_then(Object::as_i64).unwrap_or(0);
+        self.calculation_order
+            .extend(deref_array(doc, form.get(b"CO").ok()).iter().filter_map(|f| f.as_reference().ok()));
+        if self.default_appearance.is_none() {
+            self.default_appearance = form.get(b"DA").ok().cloned();
+        }
+        if self.quadding.is_none() {
+            self.quadding = form.get(b"Q").ok().cloned();
+        }
+    }
+
+    /// `name`, or `name_2`, `name_3`, ... if it is already taken.
+    fn unique_name(&self, name: &[u8]) -> Vec<u8> {
+        if !self.names.contains(name) {
+            return name.to_vec();
+        }
+        let utf16 = name.starts_with(&[0xFE, 0xFF]);
+        let mut n = 2;
+        loop {
+            let suffix = format!("_{}", n);
+            let mut candidate = name.to_vec();
+            if utf16 {
+                candidate.extend(suffix.encode_utf16().flat_map(|u| u.to_be_bytes()));
+            } else {
+                candidate.extend(suffix.bytes());
+            }
+            if !self.names.contains(&candidate) {
+                return candidate;
+            }
+            n += 1;
+        }
+    }
+
+    /// The merged `/AcroForm` dictionary, or `None` if no input had a form.
+    fn finish(self) -> Option<Dictionary> {
+        if !self.found {
+            return None;
+        }
+        // built from scratch: an input's XFA form describes only its own fields and
+        // would take precedence over the merged AcroForm in viewers that support it
+        let mut form = Dictionary::new();
+        form.set("Fields", self.fields);
+        if !self.resources.is_empty() {
+            form.set("DR", self.resources);
+        }
+        if self.need_appearances {
+            form.set("NeedAppearances", true);
+        }
+        if self.sig_flags != 0 {
+            form.set("SigFlags", self.sig_flags);
+        }
+        let kept = self.kept;
+        let calculation_order: Vec<Object> =
+            self.calculation_order.into_iter().filter(|id| kept.contains(id)).map(Object::Reference).collect();
+        if !calculation_order.is_empty() {
+            form.set("CO", calculation_order);
+        }
+        if let Some(da) = self.default_appearance {
+            form.set("DA", da);
+        }
+        if let Some(q) = self.quadding {
+            form.set("Q", q);
+        }
+        Some(form)
+    }
+}
+
+/// Catalog entries of the first input that describe only that input: its structure
+/// tree, page labels, article threads and destinations all point at its own pages.
+const INPUT_ONLY_CATALOG_KEYS: [&[u8]; 5] = [b"StructTreeRoot", b"MarkInfo", b"PageLabels", b"Threads", b"Dests"];
+
+/// Remove from the merged `catalog`, taken from the first input, what would point at
+/// pages that were not merged or apply that input's settings to the whole document:
+/// the entries above, named destinations and document scripts, and an open action
+/// that does not go to one of `merged_pages`.
+fn drop_input_only_entries(document: &Document, catalog: &mut Dictionary, merged_pages: &HashSet<ObjectId>) {
+    for key in INPUT_ONLY_CATALOG_KEYS {
+        catalog.remove(key);
+    }
+    let names = catalog.get(b"Names").ok().and_then(|n| document.dereference(n).ok()).and_then(|(_, n)| n.as_dict().ok()).cloned();
+    if let Some(mut names) = names {
+        names.remove(b"Dests");
+        names.remove(b"JavaScript");
+        if names.is_empty() {
+            catalog.remove(b"Names");
+        } else {
+            catalog.set("Names", names);
+        }
+    }
+    let keeps_open_action = catalog
+        .get(b"OpenAction")
+        .ok()
+        .and_then(|a| document.dereference(a).ok())
+        .and_then(|(_, action)| match action {
+            Object::Array(destination) => Some(destination),
+            Object::Dictionary(action) if action.get(b"S").and_then(Object::as_name).ok() == Some(b"GoTo".as_slice()) => {
+                action.get(b"D").ok().and_then(|d| d.as_array().ok())
+            }
+            _ => None,
+        })
+        .and_then(|destination| destination.first()?.as_reference().ok())
+        .map(|page| merged_pages.contains(&page))
+        .unwrap_or(false);
+    if !keeps_open_action {
+        catalog.remove(b"OpenAction");
+    }
+}
+
+fn deref_array(doc: &Document, object: Option<&Object>) -> Vec<Object> {
+    object
+        .and_then(|o| doc.dereference(o).ok())
+        .and_then(|(_, o)| o.as_array().ok())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// `field` and all its descendants through `/Kids`.
+fn collect_field_tree(doc: &Document, field: ObjectId, out: &mut Vec<ObjectId>, depth: usize) {
+    if depth > 32 || out.contains(&field) {
+        return;
+    }
+    out.push(field);
+    let kids = deref_array(doc, doc.get_dictionary(field).ok().and_then(|d| d.get(b"Kids").ok()));
+    for kid in kids.iter().filter_map(|k| k.as_reference().ok()) {
+        collect_field_tree(doc, kid, out, depth + 1);
+    }
+}
+
+fn is_widget(doc: &Document, id: ObjectId) -> bool {
+    doc.get_dictionary(id)
+        .ok()
+        .and_then(|d| d.get(b"Subtype").ok())
+        .and_then(|s| s.as_name().ok())
+        == Some(b"Widget".as_slice())
+}
+
+/// Build the merged outline: one item per input, named after the file and pointing at its
+/// first page, with the input's own outline items beneath it.
+fn nest_outlines(document: &mut Document, inputs: Vec<InputOutline>) -> Option<ObjectId> {
+    let mut top = Vec::new();
+    for input in inputs {
+        let mut item = Dictionary::new();
+        item.set("Title", text_string(&input.title));
+        item.set("Dest", vec![input.first_page.into(), Object::Name(b"Fit".to_vec())]);
+        let id = document.add_object(item);
+        top.push(OutlineNode::new(id, input.items));
+    }
+    build_outline_root(document, &top)
+}
@@ -0,0 +1,178 @@
+use std::path::{Path, PathBuf};
+
+use crate::compress::CompressOptions;
+use crate::error::{Error, Result};
+use crate::images::ImageOptions;
+use crate::merge::MergeOptions;
+use crate::progress::Progress;
+use crate::rotate::RotateOptions;
+use crate::split::SplitOptions;
+use crate::text::{TextFormat, TextOptions};
+
+/// The kind of an [`Operation`], without its settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OperationKind {
+    Merge,
+    Split,
+    Rotate,
+    ExtractText,
+    ConvertToImages,
+    Compress,
+    Optimize,
+}
+
+impl OperationKind {
+    pub const ALL: [OperationKind; 7] = [
+        OperationKind::Merge,
+        OperationKind::Split,
+        OperationKind::Rotate,
+        OperationKind::ExtractText,
+        OperationKind::ConvertToImages,
+        OperationKind::Compress,
+        OperationKind::Optimize,
+    ];
+
+    /// Human readable name, e.g. for status messages.
+    pub fn label(self) -> &'static str {
+        match self {
+            OperationKind::Merge => "Merge PDFs",
+            OperationKind::Split => "Split PDF",
+            OperationKind::Rotate => "Rotate PDF",
+            OperationKind::ExtractText => "Extract Text",
+            OperationKind::ConvertToImages => "Convert to Images",
+            OperationKind::Compress => "Compress PDF",
+            OperationKind::Optimize => "Optimize PDF",
+        }
+    }
+
+    /// Whether the operation combines several inputs into one output.
+    pub fn accepts_multiple_inputs(self) -> bool {
+        matches!(self, OperationKind::Merge)
+    }
+
+    /// Whether the output is always a folder of files rather than a single file; see
+    /// also [`Operation::output_is_folder`].
+    pub fn output_is_folder(self) -> bool {
+        matches!(self, OperationKind::Split | OperationKind::ConvertToImages)
+    }
+
+    /// Suggested output name template, see [`crate::output_name`].
+    pub fn default_output_template(self) -> &'static str {
+        match self {
+            OperationKind::Split => "{stem}-pages",
+            OperationKind::ConvertToImages => "{stem}-images",
+            OperationKind::ExtractText => "{stem}.txt",
+            OperationKind::Merge | OperationKind::Rotate | OperationKind::Compress | OperationKind::Optimize => "{stem}_opt.pdf",
+        }
+    }
+
+    /// Suggested output file or folder name for `input`.
+    pub fn default_output_name(self, input: &Path) -> String {
+        crate::output_name(self.default_output_template(), input, 1)
+    }
+}
+
+/// A PDF operation together with its settings.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    Merge(MergeOptions),
+    Split(SplitOptions),
+    Rotate(RotateOptions),
+    ExtractText(TextOptions),
+    ConvertToImages(ImageOptions),
+    Compress(CompressOptions),
+    /// Lossless structural optimization, see [`crate::optimize`].
+    Optimize,
+}
+
+impl Operation {
+    pub fn kind(&self) -> OperationKind {
+        match self {
+            Operation::Merge(_) => OperationKind::Merge,
+            Operation::Split(_) => OperationKind::Split,
+            Operation::Rotate(_) => OperationKind::Rotate,
+            Operation::ExtractText(_) => OperationKind::ExtractText,
+            Operation::ConvertToImages(_) => OperationKind::ConvertToImages,
+            Operation::Compress(_) => OperationKind::Compress,
+            Operation::Optimize => OperationKind::Optimize,
+        }
+    }
+
+    /// Whether the output is a folder of files rather than a single file, which
+    /// depends on the settings for text extraction.
+    pub fn output_is_folder(&self) -> bool {
+        match self {
+            Operation::ExtractText(o) => o.per_page || o.format == TextFormat::Csv,
+            other => other.kind().output_is_folder(),
+        }
+    }
+
+    /// Suggested output file or folder name for `input`.
+    pub fn default_output_name(&self, input: &Path) -> String {
+        match self {
+            Operation::ExtractText(o) if o.format == TextFormat::Csv => crate::output_name("{stem}-tables", input, 1),
+            Operation::ExtractText(o) if o.per_page => crate::output_name("{stem}-text", input, 1),
+            other => other.kind().default_output_name(input),
+        }
+    }
+
+    /// Check the settings without touching any file.
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Operation::Rotate(o) => {
+                if o.degrees % 90 != 0 {
+                    return Err(Error::InvalidArgument("Rotation must be a multiple of 90 degrees".into()));
+                }
+                if matches!(&o.pages, Some(p) if p.is_empty()) {
+                    return Err(Error::InvalidArgument("No pages selected".into()));
+                }
+            }
+            Operation::Compress(o) => {
+                if !(1..=100).contains(&o.quality) {
+                    return Err(Error::InvalidArgument("JPEG quality must be between 1 and 100".into()));
+                }
+                if o.max_dpi == Some(0) {
+                    return Err(Error::InvalidArgument("Maximum DPI must be at least 1".into()));
+                }
+            }
+            Operation::Merge(o) => {
+                if let Some(i) = o.pages.iter().position(|p| matches!(p, Some(p) if p.is_empty())) {
+                    return Err(Error::InvalidArgument(format!("No pages selected for input {}", i + 1)));
+                }
+            }
+            Operation::Split(o) => o.mode.validate()?,
+            Operation::ExtractText(o) => {
+                if matches!(&o.pages, Some(p) if p.is_empty()) {
+                    return Err(Error::InvalidArgument("No pages selected".into()));
+                }
+            }
+            Operation::ConvertToImages(_) | Operation::Optimize => {}
+        }
+        Ok(())
+    }
+
+    /// Validate and run the operation.
+    ///
+    /// Merge takes any number of `inputs`; every other operation takes exactly one.
+    /// `output` is a folder when [`Operation::output_is_folder`] is true, otherwise a file.
+    pub fn run(&self, inputs: &[PathBuf], output: &Path, progress: &Progress) -> Result<()> {
+        self.validate()?;
+        match self {
+            Operation::Merge(o) => crate::merge(inputs, output, o, progress),
+            Operation::Split(o) => crate::split(self.single_input(inputs)?, output, o, progress).map(drop),
+            Operation::Rotate(o) => crate::rotate(self.single_input(inputs)?, output, o, progress),
+            Operation::ExtractText(o) => crate::extract_text(self.single_input(inputs)?, output, o, progress),
+            Operation::ConvertToImages(o) => crate::convert_to_images(self.single_input(inputs)?, output, o, progress),
+            Operation::Compress(o) => crate::compress(self.single_input(inputs)?, output, o, progress).map(drop),
+            Operation::Optimize => crate::optimize(self.single_input(inputs)?, output, progress).map(drop),
+        }
+    }
+
+    fn single_input<'a>(&self, inputs: &'a [PathBuf]) -> Result<&'a Path> {
+        match inputs {
+            [one] => Ok(one),
+            [] => Err(Error::InvalidArgument("No input file provided".into())),
+            _ => Err(Error::InvalidArgument(format!("{} takes exactly one input file", self.kind().label()))),
+        }
+    }
+}
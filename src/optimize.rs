@@ -0,0 +1,191 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hasher;
+use std::io::Write;
+use std::path::Path;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use lopdf::{Document, Object, ObjectId};
+
+use crate::compress::{CompressOutcome, CompressReport};
+use crate::document::{load_document, prune_unreachable, write_atomically};
+use crate::error::{Error, Result};
+use crate::progress::Progress;
+
+mod write;
+
+/// Objects never merged with an identical copy: every page has to stay a node of its
+/// own in the page tree, and an annotation belongs to a single page. As `/Type` is
+/// optional for annotations, see also [`is_annotation`].
+const DISTINCT_TYPES: [&[u8]; 3] = [b"Page", b"Pages", b"Annot"];
+
+/// Rewrite `input` losslessly to take less space and write the result to `output`.
+///
+/// Identical objects, such as a font embedded once per page, are merged, objects that
+/// nothing refers to are dropped, uncompressed streams are compressed with Flate at its
+/// highest level, and the file is written with object and cross-reference streams,
+/// which need a PDF 1.5 reader. As with [`crate::compress`], the output is a copy of
+/// the input when none of this makes it smaller.
+pub fn optimize(input: &Path, output: &Path, progress: &Progress) -> Result<CompressReport> {
+    let mut doc = load_document(input)?;
+    let input_size = std::fs::metadata(input).map_err(|source| Error::Load { path: input.to_path_buf(), source })?.len();
+
+    optimize_document(&mut doc, progress)?;
+    let mut data = to_bytes(&doc, output)?;
+    let mut outcome = CompressOutcome::Structure;
+    if data.len() as u64 >= input_size {
+        data = std::fs::read(input).map_err(|source| Error::Load { path: input.to_path_buf(), source })?;
+        outcome = CompressOutcome::NoSavings;
+    }
+    write_atomically(output, progress, |w| w.write_all(&data))?;
+    Ok(CompressReport { outcome, input_size, output_size: data.len() as u64 })
+}
+
+/// Compress the uncompressed streams of `doc`, drop its unreachable objects, merge its
+/// identical objects and number what is left from 1 up.
+pub(crate) fn optimize_document(doc: &mut Document, progress: &Progress) -> Result<()> {
+    // a direct length lets identical streams compare equal, and frees length objects
+    for object in doc.objects.values_mut() {
+        if let Object::Stream(stream) = object {
+            let length = stream.content.len() as i64;
+            stream.dict.set("Length", Object::Integer(length));
+        }
+    }
+    prune_unreachable(doc);
+
+    let ids: Vec<ObjectId> = doc.objects.keys().copied().collect();
+    progress.add_total(ids.len() as u64);
+    for id in ids {
+        progress.check()?;
+        progress.step();
+        let Some(Object::Stream(stream)) = doc.objects.get_mut(&id) else { continue };
+        // streams that would not get smaller stay uncompressed
+        if stream.dict.get(b"Filter").is_err() && stream.compress().is_ok() && stream.dict.has(b"Filter") {
+            progress.object_rewritten();
+        }
+    }
+
+    while let Some(duplicates) = duplicates(doc, progress)? {
+        doc.objects.retain(|id, _| !duplicates.contains_key(id));
+        for object in doc.objects.values_mut() {
+            remap(object, &duplicates, false);
+        }
+        for (_, value) in doc.trailer.iter_mut() {
+            remap(value, &duplicates, false);
+        }
+    }
+    renumber(doc);
+    Ok(())
+}
+
+/// Serialize `doc` with object and cross-reference streams; `output` names it in errors.
+pub(crate) fn to_bytes(doc: &Document, output: &Path) -> Result<Vec<u8>> {
+    write::to_bytes(doc).map_err(|e| Error::io(output, e))
+}
+
+/// `data` compressed with Flate at its highest level.
+pub(crate) fn deflate(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+/// The objects of `doc` that are identical to an object with a lower number, mapped
+/// to that object, or `None` when there are none. Merging objects can make the objects
+/// that refer to them identical in turn, so this is repeated until nothing is found.
+fn duplicates(doc: &Document, progress: &Progress) -> Result<Option<HashMap<ObjectId, ObjectId>>> {
+    let listed = listed_annotations(doc);
+    let mut by_hash: HashMap<u64, Vec<ObjectId>> = HashMap::new();
+    for (&id, object) in &doc.objects {
+        let distinct = object
+            .as_dict()
+            .and_then(|d| d.get(b"Type"))
+            .and_then(Object::as_name)
+            .map(|t| DISTINCT_TYPES.contains(&t))
+            .unwrap_or(false);
+        if !distinct && !is_annotation(object) && !listed.contains(&id) {
+            let mut hasher = DefaultHasher::new();
+            hasher.write(&serialize(object));
+            by_hash.entry(hasher.finish()).or_default().push(id);
+        }
+    }
+
+    let mut duplicates = HashMap::new();
+    for ids in by_hash.values().filter(|ids| ids.len() > 1) {
+        progress.check()?;
+        // objects are visited in order, so the first of a group has the lowest number;
+        // a shared hash is confirmed byte for byte
+        for (i, &id) in ids.iter().enumerate() {
+            if duplicates.contains_key(&id) {
+                continue;
+            }
+            let original = serialize(&doc.objects[&id]);
+            for &other in &ids[i + 1..] {
+                if !duplicates.contains_key(&other) && serialize(&doc.objects[&other]) == original {
+                    duplicates.insert(other, id);
+                    progress.object_rewritten();
+                }
+            }
+        }
+    }
+    Ok((!duplicates.is_empty()).then_some(duplicates))
+}
+
+/// Whether `object` looks like an annotation without saying so: a dictionary with the
+/// `/Subtype` and `/Rect` every annotation has.
+fn is_annotation(object: &Object) -> bool {
+    object.as_dict().is_ok_and(|d| d.has(b"Subtype") && d.has(b"Rect"))
+}
+
+/// The objects an `/Annots` array refers to, whatever they look like.
+fn listed_annotations(doc: &Document) -> HashSet<ObjectId> {
+    doc.objects
+        .values()
+        .filter_map(|object| object.as_dict().ok()?.get(b"Annots").ok())
+        .filter_map(|annots| match annots {
+            Object::Reference(id) => doc.get_object(*id).and_then(Object::as_array).ok(),
+            annots => annots.as_array().ok(),
+        })
+        .flatten()
+        .filter_map(|annot| annot.as_reference().ok())
+        .collect()
+}
+
+fn serialize(object: &Object) -> Vec<u8> {
+    let mut data = Vec::new();
+    // writing to memory does not fail
+    let _ = write::write_object(&mut data, object);
+    data
+}
+
+/// Number the objects of `doc` from 1 up without gaps and with generation 0, which
+/// keeps the cross-reference table small and lets every object go in an object stream.
+fn renumber(doc: &mut Document) {
+    let numbers: HashMap<ObjectId, ObjectId> = doc.objects.keys().zip(1..).map(|(&id, n)| (id, (n, 0))).collect();
+    let objects = std::mem::take(&mut doc.objects);
+    for (id, mut object) in objects {
+        remap(&mut object, &numbers, true);
+        doc.objects.insert(numbers[&id], object);
+    }
+    for (_, value) in doc.trailer.iter_mut() {
+        remap(value, &numbers, true);
+    }
+    doc.max_id = doc.objects.len() as u32;
+}
+
+/// Point the references in `object` through `map`. With `drop_missing`, references to
+/// objects outside `map` become null, which is what a reader takes them for anyway.
+fn remap(object: &mut Object, map: &HashMap<ObjectId, ObjectId>, drop_missing: bool) {
+    match object {
+        Object::Reference(id) => match map.get(id) {
+            Some(&new) => *id = new,
+            None if drop_missing => *object = Object::Null,
+            None => {}
+        },
+        Object::Array(items) => items.iter_mut().for_each(|o| remap(o, map, drop_missing)),
+        Object::Dictionary(dict) => dict.iter_mut().for_each(|(_, o)| remap(o, map, drop_missing)),
+        Object::Stream(stream) => stream.dict.iter_mut().for_each(|(_, o)| remap(o, map, drop_missing)),
+        _ => {}
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+
+use super::deflate;
+
+/// Objects gathered into one object stream. Readers decompress a whole stream to get
+/// at any of its objects, so this keeps random access cheap.
+const OBJECTS_PER_STREAM: usize = 100;
+
+/// Trailer entries carried over to the cross-reference stream.
+const TRAILER_KEYS: [&[u8]; 3] = [b"Root", b"Info", b"ID"];
+
+/// Serialize `doc` as PDF 1.5 with its non-stream objects in compressed object streams
+/// and a compressed cross-reference stream.
+pub(super) fn to_bytes(doc: &Document) -> io::Result<Vec<u8>> {
+    let version = if doc.version.as_str() < "1.5" { "1.5" } else { doc.version.as_str() };
+    let mut out = Vec::new();
+    writeln!(out, "%PDF-{}", version)?;
+    // the binary comment marks the file as binary for transfer programs
+    out.extend([b'%', 0xE2, 0xE3, 0xCF, 0xD3, b'\n']);
+
+    // cross-reference entries: type, then offset or object stream, then generation or index
+    let mut entries: BTreeMap<u32, (u8, u64, u16)> = BTreeMap::new();
+    let mut packed: Vec<(ObjectId, &Object)> = Vec::new();
+    for (&id, object) in &doc.objects {
+        // streams, and objects with a generation, cannot live in an object stream
+        if matches!(object, Object::Stream(_)) || id.1 != 0 {
+            entries.insert(id.0, (1, out.len() as u64, id.1));
+            write_indirect(&mut out, id, object)?;
+        } else {
+            packed.push((id, object));
+        }
+    }
+
+    let mut next_id = doc.objects.keys().map(|id| id.0).max().unwrap_or(0) + 1;
+    for chunk in packed.chunks(OBJECTS_PER_STREAM) {
+        let mut header = Vec::new();
+        let mut body = Vec::new();
+        for (index, (id, object)) in chunk.iter().enumerate() {
+            write!(header, "{} {} ", id.0, body.len())?;
+            write_object(&mut body, object)?;
+            body.push(b'\n');
+            entries.insert(id.0, (2, next_id as u64, index as u16));
+        }
+        let first = header.len();
+        header.extend(body);
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::Name(b"ObjStm".to_vec()));
+        dict.set("N", Object::Integer(chunk.len() as i64));
+        dict.set("First", Object::Integer(first as i64));
+        let stream = compressed(dict, header)?;
+        entries.insert(next_id, (1, out.len() as u64, 0));
+        write_indirect(&mut out, (next_id, 0), &Object::Stream(stream))?;
+        next_id += 1;
+    }
+
+    let xref_id = next_id;
+    let xref_offset = out.len() as u64;
+    entries.insert(xref_id, (1, xref_offset, 0));
+    // the middle field holds offsets and object stream numbers; use as few bytes as fit
+    let largest = entries.values().map(|e| e.1).max().unwrap_or(0);
+    let width = (8 - largest.leading_zeros() as usize / 8).max(1);
+    let mut rows = Vec::new();
+    for id in 0..=xref_id {
+        // object 0 heads the list of free objects, and the gaps are free objects too
+        let (kind, field, generation) = entries.get(&id).copied().unwrap_or((0, 0, if id == 0 { 65535 } else { 0 }));
+        rows.push(kind);
+        rows.extend(&field.to_be_bytes()[8 - width..]);
+        rows.extend(generation.to_be_bytes());
+    }
+    let mut dict = Dictionary::new();
+    dict.set("Type", Object::Name(b"XRef".to_vec()));
+    dict.set("Size", Object::Integer(xref_id as i64 + 1));
+    dict.set("W", Object::Array(vec![Object::Integer(1), Object::Integer(width as i64), Object::Integer(2)]));
+    for key in TRAILER_KEYS {
+        if let Ok(value) = doc.trailer.get(key) {
+            dict.set(key.to_vec(), value.clone());
+        }
+    }
+    write_indirect(&mut out, (xref_id, 0), &Object::Stream(compressed(dict, rows)?))?;
+    writeln!(out, "startxref\n{}\n%%EOF", xref_offset)?;
+    Ok(out)
+}
+
+fn compressed(mut dict: Dictionary, content: Vec<u8>) -> io::Result<Stream> {
+    dict.set("Filter", Object::Name(b"FlateDecode".to_vec()));
+    Ok(Stream::new(dict, deflate(&content)?))
+}
+
+fn write_indirect(out: &mut Vec<u8>, id: ObjectId, object: &Object) -> io::Result<()> {
+    writeln!(out, "{} {} obj", id.0, id.1)?;
+    write_object(out, object)?;
+    out.extend(b"\nendobj\n");
+    Ok(())
+}
+
+/// Write `object` in its shortest usual form. A stream's `/Length` is always written
+/// as the length of its content.
+pub(super) fn write_object(out: &mut impl Write, object: &Object) -> io::Result<()> {
+    match object {
+        Object::Null => out.write_all(b"null"),
+        Object::Boolean(value) => out.write_all(if *value { b"true" } else { b"false" }),
+        Object::Integer(value) => write!(out, "{}", value),
+        // `Display` never uses an exponent, which PDF does not allow
+        Object::Real(value) if value.is_finite() => write!(out, "{}", value),
+        Object::Real(_) => out.write_all(b"0"),
+        Object::Name(name) => write_name(out, name),
+        Object::String(text, StringFormat::Literal) => {
+            out.write_all(b"(")?;
+            for &byte in text {
+                match byte {
+                    b'\\' | b'(' | b')' => out.write_all(&[b'\\', byte])?,
+                    // readers turn a bare carriage return into a line feed
+                    b'\r' => out.write_all(b"\\r")?,
+                    _ => out.write_all(&[byte])?,
+                }
+            }
+            out.write_all(b")")
+        }
+        Object::String(text, StringFormat::Hexadecimal) => {
+            out.write_all(b"<")?;
+            for byte in text {
+                write!(out, "{:02x}", byte)?;
+            }
+            out.write_all(b">")
+        }
+        Object::Array(items) => {
+            out.write_all(b"[")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.write_all(b" ")?;
+                }
+                write_object(out, item)?;
+            }
+            out.write_all(b"]")
+        }
+        Object::Dictionary(dict) => write_dictionary(out, dict, None),
+        Object::Stream(stream) => {
+            write_dictionary(out, &stream.dict, Some(stream.content.len()))?;
+            out.write_all(b"stream\n")?;
+            out.write_all(&stream.content)?;
+            out.write_all(b"\nendstream")
+        }
+        Object::Reference(id) => write!(out, "{} {} R", id.0, id.1),
+    }
+}
+
+fn write_dictionary(out: &mut impl Write, dict: &Dictionary, length: Option<usize>) -> io::Result<()> {
+    out.write_all(b"<<")?;
+    for (key, value) in dict.iter() {
+        if length.is_some() && key == b"Length" {
+            continue;
+        }
+        write_name(out, key)?;
+        // a name, string, array or dictionary starts with its own delimiter
+        if !matches!(value, Object::Name(_) | Object::String(..) | Object::Array(_) | Object::Dictionary(_)) {
+            out.write_all(b" ")?;
+        }
+        write_object(out, value)?;
+    }
+    if let Some(length) = length {
+        write!(out, "/Length {}", length)?;
+    }
+    out.write_all(b">>")
+}
+
+fn write_name(out: &mut impl Write, name: &[u8]) -> io::Result<()> {
+    out.write_all(b"/")?;
+    for &byte in name {
+        // delimiters, whitespace, `#` and anything outside printable ASCII are escaped
+        if !(0x21..=0x7E).contains(&byte) || b"()<>[]{}/%#".contains(&byte) {
+            write!(out, "#{:02X}", byte)?;
+        } else {
+            out.write_all(&[byte])?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,254 @@
+use std::collections::HashSet;
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+/// An outline item and its children.
+#[derive(Debug, Clone)]
+pub(crate) struct OutlineNode {
+    pub(crate) id: ObjectId,
+    pub(crate) children: Vec<OutlineNode>,
+    /// The item stays as a heading for its children, but its destination is gone.
+    pub(crate) drop_target: bool,
+}
+
+impl OutlineNode {
+    pub(crate) fn new(id: ObjectId, children: Vec<OutlineNode>) -> Self {
+        OutlineNode { id, children, drop_target: false }
+    }
+}
+
+/// Read the outline tree of `doc`, resolving named destinations of its items first
+/// since the document's name trees do not survive a merge or split.
+pub(crate) fn read_outline(doc: &mut Document) -> Vec<OutlineNode> {
+    let mut seen = HashSet::new();
+    let nodes = match doc
+        .catalog()
+        .ok()
+        .and_then(|c| c.get(b"Outlines").ok())
+        .and_then(|o| doc.dereference(o).ok())
+        .and_then(|(_, o)| o.as_dict().ok())
+    {
+        Some(root) => read_items(doc, root, &mut seen),
+        None => return Vec::new(),
+    };
+    for id in seen {
+        let resolved = {
+            let Ok(item) = doc.get_dictionary(id) else { continue };
+            let dest = item.get(b"Dest").ok().and_then(|d| named_destination(doc, d));
+            let action_dest = goto_action(doc, item).and_then(|a| a.get(b"D").ok()).and_then(|d| named_destination(doc, d));
+            (dest, action_dest)
+        };
+        if let Ok(Object::Dictionary(item)) = doc.get_object_mut(id) {
+            match resolved {
+                (Some(dest), _) => item.set("Dest", dest),
+                // replace the action rather than editing it, it may be shared
+                (None, Some(dest)) => {
+                    item.remove(b"A");
+                    item.set("Dest", dest);
+                }
+                (None, None) => {}
+            }
+        }
+    }
+    nodes
+}
+
+fn read_items(doc: &Document, parent: &Dictionary, seen: &mut HashSet<ObjectId>) -> Vec<OutlineNode> {
+    let mut nodes = Vec::new();
+    let mut next = parent.get(b"First").and_then(Object::as_reference).ok();
+    while let Some(id) = next {
+        // malformed outlines can contain cycles
+        if !seen.insert(id) {
+            break;
+        }
+        let Ok(item) = doc.get_dictionary(id) else { break };
+        nodes.push(OutlineNode::new(id, read_items(doc, item, seen)));
+        next = item.get(b"Next").and_then(Object::as_reference).ok();
+    }
+    nodes
+}
+
+/// The `/A` dictionary of an outline item if it is a `GoTo` action.
+fn goto_action<'a>(doc: &'a Document, item: &'a Dictionary) -> Option<&'a Dictionary> {
+    let (_, action) = doc.dereference(item.get(b"A").ok()?).ok()?;
+    let action = action.as_dict().ok()?;
+    (action.get(b"S").and_then(Object::as_name).ok() == Some(b"GoTo".as_slice())).then_some(action)
+}
+
+/// Explicit destination array of an outline item, from `/Dest` or a `GoTo` action.
+pub(crate) fn outline_destination(doc: &Document, id: ObjectId) -> Option<&Vec<Object>> {
+    let item = doc.get_dictionary(id).ok()?;
+    let dest = match item.get(b"Dest") {
+        Ok(dest) => dest,
+        Err(_) => goto_action(doc, item)?.get(b"D").ok()?,
+    };
+    let (_, dest) = doc.dereference(dest).ok()?;
+    dest.as_array().ok()
+}
+
+/// Page an outline item jumps to, if it has an explicit destination.
+pub(crate) fn outline_target(doc: &Document, id: ObjectId) -> Option<ObjectId> {
+    outline_destination(doc, id)?.first()?.as_reference().ok()
+}
+
+/// Decoded `/Title` of an outline item.
+pub(crate) fn outline_title(doc: &Document, id: ObjectId) -> String {
+    let title = doc
+        .get_dictionary(id)
+        .ok()
+        .and_then(|item| item.get(b"Title").ok())
+        .and_then(|t| doc.dereference(t).ok())
+        .and_then(|(_, t)| t.as_str().ok());
+    title.map(decode_text_string).unwrap_or_default()
+}
+
+/// Drop the items that jump to a page outside `pages`, unless some of their children stay.
+pub(crate) fn retain_outline(doc: &Document, nodes: &[OutlineNode], pages: &HashSet<ObjectId>) -> Vec<OutlineNode> {
+    let mut kept = Vec::new();
+    for node in nodes {
+        let children = retain_outline(doc, &node.children, pages);
+        match outline_target(doc, node.id) {
+            Some(page) if pages.contains(&page) => kept.push(OutlineNode { children, ..node.clone() }),
+            Some(_) if !children.is_empty() => kept.push(OutlineNode { id: node.id, children, drop_target: true }),
+            Some(_) => {}
+            // headings and non-GoTo actions (links, scripts) stay unless they lost all children
+            None if !node.children.is_empty() && children.is_empty() => {}
+            None => kept.push(OutlineNode { children, ..node.clone() }),
+        }
+    }
+    kept
+}
+
+/// Explicit destination array for a named destination, looked up in the catalog's
+/// `/Dests` dictionary or `/Names /Dests` name tree. `None` if `dest` is not a name.
+fn named_destination(doc: &Document, dest: &Object) -> Option<Object> {
+    let catalog = doc.catalog().ok()?;
+    let found = match dest {
+        Object::Name(name) => {
+            let dests = doc.dereference(catalog.get(b"Dests").ok()?).ok()?.1.as_dict().ok()?;
+            dests.get(name).ok()?.clone()
+        }
+        Object::String(key, _) => {
+            let names = doc.dereference(catalog.get(b"Names").ok()?).ok()?.1.as_dict().ok()?;
+            let tree = doc.dereference(names.get(b"Dests").ok()?).ok()?.1.as_dict().ok()?;
+            lookup_name_tree(doc, tree, key, 0)?
+        }
+        _ => return None,
+    };
+    // a destination may be given as a dictionary whose /D holds the array
+    let (_, found) = doc.dereference(&found).ok()?;
+    match found {
+        Object::Dictionary(d) => d.get(b"D").ok().cloned(),
+        other => Some(other.clone()),
+    }
+}
+
+fn lookup_name_tree(doc: &Document, node: &Dictionary, key: &[u8], depth: usize) -> Option<Object> {
+    if depth > 32 {
+        return None;
+    }
+    if let Ok(names) = node.get(b"Names").and_then(Object::as_array) {
+        for pair in names.chunks(2) {
+            if let [Object::String(k, _), value] = pair {
+                if k.as_slice() == key {
+                    return Some(value.clone());
+                }
+            }
+        }
+    }
+    let kids = node.get(b"Kids").and_then(Object::as_array).ok()?;
+    kids.iter()
+        .filter_map(|kid| doc.dereference(kid).ok()?.1.as_dict().ok())
+        .find_map(|kid| lookup_name_tree(doc, kid, key, depth + 1))
+}
+
+/// Add an `/Outlines` dictionary holding `top` as its items and return its id,
+/// or `None` when there are no items.
+pub(crate) fn build_outline_root(document: &mut Document, top: &[OutlineNode]) -> Option<ObjectId> {
+    let root_id = document.new_object_id();
+    let (first, last, count) = link_outline(document, root_id, top)?;
+
+    let mut root = Dictionary::new();
+    root.set("Type", Object::Name(b"Outlines".to_vec()));
+    root.set("First", first);
+    root.set("Last", last);
+    root.set("Count", count);
+    document.objects.insert(root_id, Object::Dictionary(root));
+    Some(root_id)
+}
+
+/// Attach `nodes` to `parent` as a chain of siblings, rewriting the links of every item below.
+///
+/// Returns the first and last item and the number of visible descendants for the parent's `/Count`.
+fn link_outline(document: &mut Document, parent: ObjectId, nodes: &[OutlineNode]) -> Option<(ObjectId, ObjectId, i64)> {
+    let first = nodes.first()?.id;
+    let last = nodes.last()?.id;
+    let mut visible = nodes.len() as i64;
+    for (i, node) in nodes.iter().enumerate() {
+        let children = link_outline(document, node.id, &node.children);
+        let Ok(Object::Dictionary(item)) = document.get_object_mut(node.id) else { continue };
+        item.set("Parent", parent);
+        if node.drop_target {
+            item.remove(b"Dest");
+            item.remove(b"A");
+        }
+        match i.checked_sub(1).map(|p| nodes[p].id) {
+            Some(prev) => item.set("Prev", prev),
+            None => {
+                item.remove(b"Prev");
+            }
+        }
+        match nodes.get(i + 1) {
+            Some(next) => item.set("Next", next.id),
+            None => {
+                item.remove(b"Next");
+            }
+        }
+        match children {
+            Some((child_first, child_last, count)) => {
+                // keep a source item's open/closed state; new items start open
+                let open = item.get(b"Count").and_then(Object::as_i64).map_or(true, |c| c > 0);
+                item.set("First", child_first);
+                item.set("Last", child_last);
+                if open {
+                    item.set("Count", count);
+                    visible += count;
+                } else {
+                    item.set("Count", -(node.children.len() as i64));
+                }
+            }
+            None => {
+                item.remove(b"First");
+                item.remove(b"Last");
+                item.remove(b"Count");
+            }
+        }
+    }
+    Some((first, last, visible))
+}
+
+/// PDF text string: PDFDocEncoding for ASCII, UTF-16BE with a byte order mark otherwise.
+pub(crate) fn text_string(s: &str) -> Object {
+    if s.is_ascii() {
+        Object::string_literal(s)
+    } else {
+        let mut bytes = vec![0xFE, 0xFF];
+        bytes.extend(s.encode_utf16().flat_map(|u| u.to_be_bytes()));
+        Object::String(bytes, lopdf::StringFormat::Hexadecimal)
+    }
+}
+
+/// Decode a PDF text string: UTF-16BE or UTF-8 after a byte order mark, otherwise
+/// PDFDocEncoding, read as Latin-1 which it matches for printable characters.
+/// Some producers write bare UTF-8, which is taken as such when it is valid.
+pub(crate) fn decode_text_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = utf16.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+        return String::from_utf16_lossy(&units);
+    }
+    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s.to_string(),
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
@@ -0,0 +1,66 @@
+use std::ops::RangeInclusive;
+
+use crate::error::{Error, Result};
+
+/// Parse a page selection such as `1-3,5` into a list of 1-based page numbers.
+pub fn parse_pages(spec: &str) -> Result<Vec<u32>> {
+    parse(spec, false)
+}
+
+/// Parse a page sequence in the same syntax as [`parse_pages`], but keeping the order
+/// as written: ranges may run backwards (`12-3`) and pages may repeat.
+pub fn parse_page_sequence(spec: &str) -> Result<Vec<u32>> {
+    parse(spec, true)
+}
+
+/// Parse a range list such as `1-4,9-12` into one range per comma-separated entry.
+pub fn parse_page_ranges(spec: &str) -> Result<Vec<RangeInclusive<u32>>> {
+    let ranges: Vec<RangeInclusive<u32>> = spec
+        .split(',')
+        .filter(|part| !part.trim().is_empty())
+        .map(|part| {
+            let pages = parse_pages(part)?;
+            Ok(pages[0]..=pages[pages.len() - 1])
+        })
+        .collect::<Result<_>>()?;
+    if ranges.is_empty() {
+        return Err(Error::InvalidArgument("No pages selected".into()));
+    }
+    Ok(ranges)
+}
+
+fn parse(spec: &str, allow_reversed: bool) -> Result<Vec<u32>> {
+    let mut vec: Vec<u32> = Vec::new();
+    for part in spec.split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        if let Some((a, b)) = part.split_once('-') {
+            let sa = a.trim().parse::<u32>().map_err(|_| invalid_range(part))?;
+            let sb = b.trim().parse::<u32>().map_err(|_| invalid_range(part))?;
+            if sa == 0 || sb == 0 || (sb < sa && !allow_reversed) {
+                return Err(invalid_range(part));
+            }
+            if sb < sa {
+                vec.extend((sb..=sa).rev());
+            } else {
+                vec.extend(sa..=sb);
+            }
+        } else {
+            let n = part.parse::<u32>().map_err(|_| Error::InvalidArgument(format!("Invalid page number: {}", part)))?;
+            if n == 0 {
+                return Err(Error::InvalidArgument(format!("Invalid page number: {}", part)));
+            }
+            vec.push(n);
+        }
+    }
+    if vec.is_empty() {
+        return Err(Error::InvalidArgument("No pages selected".into()));
+    }
+    Ok(vec)
+}
+
+fn invalid_range(part: &str) -> Error {
+    Error::InvalidArgument(format!("Invalid page range: {}", part))
+}
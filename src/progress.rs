@@ -0,0 +1,107 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+
+/// Progress and cancellation handle shared between a running operation and its caller.
+///
+/// Clones share the same counters, so the caller can keep one clone to poll
+/// [`Progress::snapshot`] or call [`Progress::cancel`] while a worker thread
+/// runs the operation with another.
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    inner: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    cancelled: AtomicBool,
+    total: AtomicU64,
+    completed: AtomicU64,
+    pages: AtomicU64,
+    objects: AtomicU64,
+    bytes_written: AtomicU64,
+}
+
+/// Point-in-time copy of the counters of a [`Progress`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProgressSnapshot {
+    /// Number of steps the operation expects to take; 0 while unknown.
+    pub total: u64,
+    /// Number of steps finished so far.
+    pub completed: u64,
+    /// Pages processed.
+    pub pages: u64,
+    /// PDF objects (images, streams, ...) rewritten.
+    pub objects: u64,
+    /// Bytes written to output files.
+    pub bytes_written: u64,
+}
+
+impl ProgressSnapshot {
+    /// Completed fraction in `0.0..=1.0`, or `None` while the total is unknown.
+    pub fn fraction(&self) -> Option<f64> {
+        if self.total == 0 {
+            None
+        } else {
+            Some((self.completed as f64 / self.total as f64).min(1.0))
+        }
+    }
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the operation to stop; it returns [`Error::Cancelled`] at its next check.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        let c = &self.inner;
+        ProgressSnapshot {
+            total: c.total.load(Ordering::Relaxed),
+            completed: c.completed.load(Ordering::Relaxed),
+            pages: c.pages.load(Ordering::Relaxed),
+            objects: c.objects.load(Ordering::Relaxed),
+            bytes_written: c.bytes_written.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Return [`Error::Cancelled`] if cancellation was requested.
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(Error::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(crate) fn add_total(&self, steps: u64) {
+        self.inner.total.fetch_add(steps, Ordering::Relaxed);
+    }
+
+    pub(crate) fn step(&self) {
+        self.inner.completed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a processed page as one step.
+    pub(crate) fn page_done(&self) {
+        self.inner.pages.fetch_add(1, Ordering::Relaxed);
+        self.step();
+    }
+
+    pub(crate) fn object_rewritten(&self) {
+        self.inner.objects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_bytes(&self, n: u64) {
+        self.inner.bytes_written.fetch_add(n, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use lopdf::Object;
+
+use crate::document::{load_document, save_document, write_atomically};
+use crate::error::{Error, Result};
+use crate::progress::Progress;
+
+/// Settings for [`rotate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RotateOptions {
+    /// Clockwise rotation; must be a multiple of 90 (negative values rotate counter-clockwise).
+    pub degrees: i32,
+    /// 1-based page numbers to rotate; `None` rotates every page.
+    pub pages: Option<Vec<u32>>,
+}
+
+/// Add `options.degrees` to the `/Rotate` entry of the selected pages of `input`.
+pub fn rotate(input: &Path, output: &Path, options: &RotateOptions, progress: &Progress) -> Result<()> {
+    let mut doc = load_document(input)?;
+
+    let deg = ((options.degrees % 360) + 360) % 360;
+    if deg % 90 != 0 {
+        return Err(Error::InvalidArgument("Rotation must be a multiple of 90 degrees".into()));
+    }
+
+    if deg == 0 {
+        // just copy
+        let mut src = std::fs::File::open(input).map_err(|source| Error::Load { path: input.to_path_buf(), source })?;
+        return write_atomically(output, progress, |w| std::io::copy(&mut src, w).map(|_| ()));
+    }
+
+    let pages_set: Option<std::collections::HashSet<u32>> = options.pages.as_ref().map(|v| v.iter().copied().collect());
+
+    let pages = doc.get_pages();
+    progress.add_total(pages.len() as u64);
+    for (page_number, page_id) in pages.into_iter() {
+        progress.check()?;
+        progress.page_done();
+        if let Some(ref set) = pages_set {
+            if !set.contains(&page_number) {
+                continue;
+            }
+        }
+        if let Ok(Object::Dictionary(dict)) = doc.get_object_mut(page_id) {
+            let current = dict.get(b"Rotate").and_then(|o| o.as_i64()).unwrap_or(0);
+            let new = ((current + deg as i64) % 360 + 360) % 360;
+            dict.set("Rotate", lopdf::Object::Integer(new));
+        }
+    }
+
+    save_document(&mut doc, output, progress)
+}
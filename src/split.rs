@@ -0,0 +1,757 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+
+use crate::document::{inherit_page_attributes, load_document, output_folder, prune_unreachable, save_document, CreatedFiles};
+use crate::error::{Error, Result};
+use crate::images::render_gray_pages;
+use crate::outline::{
+    build_outline_root, outline_destination, outline_target, outline_title, read_outline, retain_outline, OutlineNode,
+};
+use crate::progress::Progress;
+use crate::template::expand;
+
+/// How [`split`] divides a document into parts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SplitMode {
+    /// One part per page.
+    #[default]
+    EveryPage,
+    /// Parts of `n` consecutive pages; the last one may be shorter.
+    Chunks(u32),
+    /// One part per range, e.g. `1-4` and `9-12`.
+    Ranges(Vec<RangeInclusive<u32>>),
+    /// A single part with the odd pages.
+    OddPages,
+    /// A single part with the even pages.
+    EvenPages,
+    /// One part per bookmark down to the given outline level (1 = top level),
+    /// running up to the page of the next bookmark.
+    Bookmarks(u32),
+    /// Parts of as many consecutive pages as fit in the given number of bytes.
+    /// A page that is larger on its own becomes a part by itself.
+    MaxSize(u64),
+    /// Parts separated by blank pages, such as the sheets a scanning station puts
+    /// between documents. A page is blank when its content is empty or whitespace;
+    /// with `render`, also when it renders to a near-uniform image (a blank scan),
+    /// which needs `pdftoppm`. Separators start the next part unless `drop` is set.
+    BlankPages { render: bool, drop: bool },
+}
+
+impl SplitMode {
+    /// File name template used when [`SplitOptions::name_template`] is not set.
+    pub fn default_name_template(&self) -> &'static str {
+        match self {
+            SplitMode::EveryPage => "page-{start}.pdf",
+            SplitMode::Chunks(_) | SplitMode::Ranges(_) | SplitMode::MaxSize(_) | SplitMode::BlankPages { .. } => {
+                "{stem}_{start}-{end}.pdf"
+            }
+            SplitMode::OddPages => "{stem}-odd.pdf",
+            SplitMode::EvenPages => "{stem}-even.pdf",
+            SplitMode::Bookmarks(_) => "{title}.pdf",
+        }
+    }
+}
+
+impl std::str::FromStr for SplitMode {
+    type Err = Error;
+
+    /// Parse `pages`, `every:N`, `ranges:1-4,9-12`, `odd`, `even`, `bookmarks[:DEPTH]`
+    /// `size:10MB` or `blank[:render,drop]`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, arg) = match s.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (s, None),
+        };
+        match (name.to_lowercase().as_str(), arg) {
+            ("pages", None) => Ok(SplitMode::EveryPage),
+            ("every", Some(n)) => n
+                .trim()
+                .parse::<u32>()
+                .map(SplitMode::Chunks)
+                .map_err(|_| Error::InvalidArgument(format!("Invalid chunk size: {}", n))),
+            ("ranges", Some(spec)) => crate::parse_page_ranges(spec).map(SplitMode::Ranges),
+            ("odd", None) => Ok(SplitMode::OddPages),
+            ("even", None) => Ok(SplitMode::EvenPages),
+            ("bookmarks", None) => Ok(SplitMode::Bookmarks(1)),
+            ("bookmarks", Some(n)) => n
+                .trim()
+                .parse::<u32>()
+                .map(SplitMode::Bookmarks)
+                .map_err(|_| Error::InvalidArgument(format!("Invalid bookmark depth: {}", n))),
+            ("size", Some(size)) => parse_size(size).map(SplitMode::MaxSize),
+            ("blank", None) => Ok(SplitMode::BlankPages { render: false, drop: false }),
+            ("blank", Some(flags)) => {
+                let (mut render, mut drop) = (false, false);
+                for flag in flags.split(',').map(str::trim) {
+                    match flag.to_lowercase().as_str() {
+                        "render" => render = true,
+                        "drop" => drop = true,
+                        _ => return Err(Error::InvalidArgument(format!("Unknown blank page option: {}", flag))),
+                    }
+                }
+                Ok(SplitMode::BlankPages { render, drop })
+            }
+            _ => Err(Error::InvalidArgument(format!("Unknown split mode: {}", s))),
+        }
+    }
+}
+
+/// Parse a size such as `10MB`, `500 KB`, `2MiB` or a plain number of bytes.
+/// `KB`, `MB` and `GB` are decimal units, `KiB`, `MiB` and `GiB` binary ones.
+pub fn parse_size(s: &str) -> Result<u64> {
+    let invalid = || Error::InvalidArgument(format!("Invalid size: {}", s));
+    let s = s.trim();
+    let split = s.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+    let unit: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1000,
+        "m" | "mb" => 1000 * 1000,
+        "g" | "gb" => 1000 * 1000 * 1000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        _ => return Err(invalid()),
+    };
+    Ok((number * unit as f64) as u64)
+}
+
+/// Settings for [`split`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SplitOptions {
+    pub mode: SplitMode,
+    /// File name of each part, with `{stem}`, `{start}`, `{end}` (first and last page),
+    /// `{index}` (1-based part number) and `{title}` (the bookmark a part starts at,
+    /// otherwise the stem) placeholders; `.pdf` is added if missing.
+    /// `None` uses [`SplitMode::default_name_template`].
+    pub name_template: Option<String>,
+}
+
+/// A file written by [`split`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitPart {
+    pub path: PathBuf,
+    /// 1-based page numbers of the input, in order.
+    pub pages: Vec<u32>,
+    /// File size in bytes.
+    pub size: u64,
+}
+
+/// A part of the split document: its 1-based page numbers in order, and for
+/// [`SplitMode::Bookmarks`] the bookmark it starts at and the outline it keeps.
+#[derive(Default)]
+struct Part {
+    pages: Vec<u32>,
+    title: Option<String>,
+    outline: Vec<OutlineNode>,
+}
+
+impl Part {
+    fn new(pages: Vec<u32>) -> Self {
+        Part { pages, ..Default::default() }
+    }
+}
+
+impl SplitMode {
+    /// Check the settings without looking at a document.
+    pub(crate) fn validate(&self) -> Result<()> {
+        match self {
+            SplitMode::Chunks(0) => Err(Error::InvalidArgument("Chunk size must be at least 1".into())),
+            SplitMode::Ranges(r) if r.is_empty() => Err(Error::InvalidArgument("No pages selected".into())),
+            SplitMode::Bookmarks(0) => Err(Error::InvalidArgument("Bookmark depth must be at least 1".into())),
+            SplitMode::MaxSize(0) => Err(Error::InvalidArgument("Part size must be at least 1 byte".into())),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The parts to write for `mode`, which has been validated.
+fn plan_parts(mode: &SplitMode, doc: &mut Document, input: &Path) -> Result<Vec<Part>> {
+    let page_count = doc.get_pages().len() as u32;
+    let all = 1..=page_count;
+    let parts = match mode {
+        SplitMode::EveryPage => all.map(|n| Part::new(vec![n])).collect(),
+        SplitMode::Chunks(n) => {
+            let pages: Vec<u32> = all.collect();
+            pages.chunks(*n as usize).map(|c| Part::new(c.to_vec())).collect()
+        }
+        SplitMode::Ranges(ranges) => {
+            if let Some(r) = ranges.iter().find(|r| *r.end() > page_count) {
+                return Err(Error::InvalidArgument(format!(
+                    "Range {}-{} is past the last page ({})",
+                    r.start(),
+                    r.end(),
+                    page_count
+                )));
+            }
+            ranges.iter().map(|r| Part::new(r.clone().collect())).collect()
+        }
+        SplitMode::OddPages => vec![Part::new(all.filter(|n| n % 2 == 1).collect())],
+        SplitMode::EvenPages => vec![Part::new(all.filter(|n| n % 2 == 0).collect())],
+        SplitMode::Bookmarks(depth) => bookmark_parts(doc, *depth)?,
+        SplitMode::MaxSize(limit) => {
+            // measure streams as they will be written
+            doc.compress();
+            size_parts(doc, *limit)?
+        }
+        SplitMode::BlankPages { render, drop } => {
+            let pages = doc.get_pages();
+            let mut blank: Vec<bool> = pages.values().map(|id| has_empty_content(doc, *id)).collect();
+            if *render {
+                let images = render_gray_pages(input, BLANK_RENDER_DPI)?;
+                if images.len() != blank.len() {
+                    return Err(Error::ExternalTool {
+                        tool: "pdftoppm",
+                        message: format!("rendered {} pages of {}", images.len(), blank.len()),
+                        source: None,
+                    });
+                }
+                for (b, image) in blank.iter_mut().zip(&images) {
+                    *b = *b || is_uniform(image);
+                }
+            }
+            blank_page_parts(&blank, *drop)
+        }
+    };
+    Ok(parts)
+}
+
+/// Bytes of a part besides its objects: header, catalog, page tree, trailer.
+const PART_OVERHEAD: u64 = 512;
+/// Bytes per object besides its content: `N 0 obj`, `endobj` and the xref entry.
+const OBJECT_OVERHEAD: u64 = 40;
+
+/// Parts of consecutive pages that stay within `limit` bytes each.
+///
+/// Pages are added while the estimated size of the objects the part needs stays
+/// under the limit; fonts and images shared between pages are counted once per part
+/// since each part gets its own copy. The estimate is then checked against the
+/// serialized part; when it is too large, the longest prefix that fits is found by
+/// bisection and the other pages go back to the next part.
+fn size_parts(doc: &Document, limit: u64) -> Result<Vec<Part>> {
+    let pages: Vec<(u32, ObjectId)> = doc.get_pages().into_iter().collect();
+    let mut sizes: HashMap<ObjectId, u64> = HashMap::new();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    while start < pages.len() {
+        let mut end = start;
+        let mut included: HashSet<ObjectId> = HashSet::new();
+        let mut estimate = PART_OVERHEAD;
+        while end < pages.len() {
+            let objects = page_objects(doc, pages[end].1);
+            let added: u64 = objects
+                .difference(&included)
+                .map(|id| *sizes.entry(*id).or_insert_with(|| doc.objects.get(id).map_or(0, estimated_size) + OBJECT_OVERHEAD))
+                .sum();
+            if end > start && estimate + added > limit {
+                break;
+            }
+            estimate += added;
+            included.extend(objects);
+            end += 1;
+        }
+        let ids: Vec<ObjectId> = pages[start..end].iter().map(|(_, id)| *id).collect();
+        if ids.len() > 1 && serialized_size(doc, &ids)? > limit {
+            // a single page is a part even when it is too large
+            let (mut fits, mut too_many) = (1, ids.len());
+            while too_many - fits > 1 {
+                let middle = (fits + too_many) / 2;
+                if serialized_size(doc, &ids[..middle])? > limit {
+                    too_many = middle;
+                } else {
+                    fits = middle;
+                }
+            }
+            end = start + fits;
+        }
+        parts.push(Part::new(pages[start..end].iter().map(|(n, _)| *n).collect()));
+        start = end;
+    }
+    Ok(parts)
+}
+
+/// Size of `page_ids` written as a document of their own.
+fn serialized_size(doc: &Document, page_ids: &[ObjectId]) -> Result<u64> {
+    let mut part = extract_pages(doc, page_ids, &[]);
+    let mut buffer = Vec::new();
+    part.save_to(&mut buffer).map_err(|e| Error::Malformed(format!("Could not serialize part: {}", e)))?;
+    Ok(buffer.len() as u64)
+}
+
+/// Approximate number of bytes `object` takes when written.
+fn estimated_size(object: &Object) -> u64 {
+    match object {
+        Object::Null => 4,
+        Object::Boolean(_) => 5,
+        Object::Integer(i) => i.to_string().len() as u64,
+        Object::Real(_) => 8,
+        Object::Name(n) => n.len() as u64 + 1,
+        Object::String(s, lopdf::StringFormat::Literal) => s.len() as u64 + 2,
+        Object::String(s, lopdf::StringFormat::Hexadecimal) => 2 * s.len() as u64 + 2,
+        Object::Array(items) => 2 + items.iter().map(|o| estimated_size(o) + 1).sum::<u64>(),
+        Object::Dictionary(d) => 4 + d.iter().map(|(k, v)| k.len() as u64 + 2 + estimated_size(v)).sum::<u64>(),
+        Object::Stream(s) => estimated_size(&Object::Dictionary(s.dict.clone())) + s.content.len() as u64 + 20,
+        Object::Reference(_) => 8,
+    }
+}
+
+/// Resolution for telling blank scans apart; specks vanish, text lines do not.
+const BLANK_RENDER_DPI: u32 = 20;
+/// Gray levels a pixel may differ from the page's background and still count as blank.
+const BLANK_TOLERANCE: usize = 24;
+/// Share of pixels that may differ more, for scanner noise and dust.
+const BLANK_MAX_INK: f64 = 0.005;
+
+/// A page whose content streams are missing, empty or only whitespace.
+fn has_empty_content(doc: &Document, page: ObjectId) -> bool {
+    doc.get_page_content(page).is_ok_and(|content| content.iter().all(u8::is_ascii_whitespace))
+}
+
+/// Whether almost all pixels are close to the most common gray level.
+fn is_uniform(image: &image::GrayImage) -> bool {
+    let mut histogram = [0u64; 256];
+    for pixel in image.pixels() {
+        histogram[pixel.0[0] as usize] += 1;
+    }
+    let total: u64 = histogram.iter().sum();
+    let background = (0..256).max_by_key(|&level| histogram[level]).unwrap_or(255);
+    let near: u64 = histogram[background.saturating_sub(BLANK_TOLERANCE)..=(background + BLANK_TOLERANCE).min(255)].iter().sum();
+    (total - near) as f64 <= total as f64 * BLANK_MAX_INK
+}
+
+/// Parts between runs of blank pages, given whether each page is blank. Kept separators
+/// start the part after them; trailing ones stay with the last part.
+fn blank_page_parts(blank: &[bool], drop: bool) -> Vec<Part> {
+    let mut parts: Vec<Part> = Vec::new();
+    let mut current: Vec<u32> = Vec::new();
+    let mut has_content = false;
+    for (i, &is_blank) in blank.iter().enumerate() {
+        if is_blank && has_content {
+            parts.push(Part::new(std::mem::take(&mut current)));
+            has_content = false;
+        }
+        if !is_blank || !drop {
+            current.push(i as u32 + 1);
+        }
+        has_content |= !is_blank;
+    }
+    match parts.last_mut() {
+        Some(last) if !has_content => last.pages.extend(current),
+        _ => parts.push(Part::new(current)),
+    }
+    parts
+}
+
+/// A bookmark that starts a part.
+struct BookmarkStart {
+    page: u32,
+    level: u32,
+    title: String,
+}
+
+/// Parts running from each bookmark down to level `depth` to the next one. Pages before
+/// the first bookmark make a part of their own; each part keeps the outline items that
+/// point into it.
+fn bookmark_parts(doc: &mut Document, depth: u32) -> Result<Vec<Part>> {
+    let outline = read_outline(doc);
+    let pages = doc.get_pages();
+    let numbers: HashMap<ObjectId, u32> = pages.iter().map(|(n, id)| (*id, *n)).collect();
+    let mut starts = Vec::new();
+    bookmark_starts(doc, &outline, 1, depth, &numbers, &mut starts);
+    if starts.is_empty() {
+        return Err(Error::InvalidArgument("The document has no bookmarks to split at".into()));
+    }
+    // bookmarks on the same page start a single part, named after the deepest of them
+    starts.sort_by_key(|s| s.page);
+    starts.dedup_by(|next, kept| {
+        if next.page != kept.page {
+            return false;
+        }
+        if next.level > kept.level {
+            std::mem::swap(next, kept);
+        }
+        true
+    });
+
+    let mut parts = Vec::new();
+    if starts[0].page > 1 {
+        parts.push(Part::new((1..starts[0].page).collect()));
+    }
+    for (i, start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map_or(pages.len() as u32, |next| next.page - 1);
+        parts.push(Part { pages: (start.page..=end).collect(), title: Some(start.title.clone()), outline: Vec::new() });
+    }
+    for part in &mut parts {
+        let ids: HashSet<ObjectId> = part.pages.iter().map(|n| pages[n]).collect();
+        part.outline = retain_outline(doc, &outline, &ids);
+    }
+    Ok(parts)
+}
+
+fn bookmark_starts(
+    doc: &Document,
+    nodes: &[OutlineNode],
+    level: u32,
+    depth: u32,
+    numbers: &HashMap<ObjectId, u32>,
+    out: &mut Vec<BookmarkStart>,
+) {
+    for node in nodes {
+        if let Some(page) = first_page(doc, node, numbers) {
+            out.push(BookmarkStart { page, level, title: outline_title(doc, node.id) });
+        }
+        if level < depth {
+            bookmark_starts(doc, &node.children, level + 1, depth, numbers, out);
+        }
+    }
+}
+
+/// Page number an outline item points at, or for a heading without a destination
+/// the page of its first child that has one.
+fn first_page(doc: &Document, node: &OutlineNode, numbers: &HashMap<ObjectId, u32>) -> Option<u32> {
+    outline_target(doc, node.id)
+        .and_then(|id| numbers.get(&id).copied())
+        .or_else(|| node.children.iter().find_map(|child| first_page(doc, child, numbers)))
+}
+
+/// File name for a bookmark title: characters that common file systems reject
+/// become `_`, whitespace is collapsed and the length is capped.
+fn file_name_from_title(title: &str) -> String {
+    let cleaned: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    let cleaned: String = cleaned.split_whitespace().collect::<Vec<_>>().join(" ").chars().take(100).collect();
+    let cleaned = cleaned.trim_start_matches('.').trim_end_matches(['.', ' ']);
+    if cleaned.is_empty() { "Untitled".to_string() } else { cleaned.to_string() }
+}
+
+/// Make part names distinct by appending ` (2)`, ` (3)`, … before the extension,
+/// comparing case-insensitively.
+fn unique_names(names: Vec<String>) -> Vec<String> {
+    let mut used = HashSet::new();
+    names
+        .into_iter()
+        .map(|name| {
+            let (base, ext) = name.split_at(name.len() - ".pdf".len());
+            let mut candidate = name.clone();
+            let mut n = 2;
+            while !used.insert(candidate.to_lowercase()) {
+                candidate = format!("{} ({}){}", base, n, ext);
+                n += 1;
+            }
+            candidate
+        })
+        .collect()
+}
+
+/// Split `input` into several PDFs as chosen by [`SplitOptions::mode`].
+///
+/// Parts are written into a `{stem}-pages` folder under `output_dir`
+/// (or into `output_dir` itself when it already has that name). Returns the
+/// written parts in order.
+pub fn split(input: &Path, output_dir: &Path, options: &SplitOptions, progress: &Progress) -> Result<Vec<SplitPart>> {
+    options.mode.validate()?;
+    let mut src = load_document(input)?;
+    // parts get a page tree of their own, so pages take along what they inherited
+    for id in src.get_pages().into_values() {
+        inherit_page_attributes(&mut src, id);
+    }
+    let parts: Vec<Part> = plan_parts(&options.mode, &mut src, input)?
+        .into_iter()
+        .filter(|p| !p.pages.is_empty())
+        .collect();
+    if parts.is_empty() {
+        return Err(Error::InvalidArgument("No pages to split".into()));
+    }
+
+    let stem = input.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+    let template = options.name_template.as_deref().unwrap_or(options.mode.default_name_template());
+    let names: Vec<String> = parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| {
+            let name = expand(
+                template,
+                &[
+                    ("stem", stem.to_string()),
+                    ("start", part.pages[0].to_string()),
+                    ("end", part.pages[part.pages.len() - 1].to_string()),
+                    ("index", (i + 1).to_string()),
+                    ("title", part.title.as_deref().map_or_else(|| stem.to_string(), file_name_from_title)),
+                ],
+            );
+            if name.to_lowercase().ends_with(".pdf") { name } else { format!("{}.pdf", name) }
+        })
+        .collect();
+    // bookmark titles repeat easily, e.g. "Introduction" in every chapter
+    let names = if matches!(options.mode, SplitMode::Bookmarks(_)) { unique_names(names) } else { names };
+    if let Some(name) = names.iter().find(|n| n.contains(['/', '\\'])) {
+        return Err(Error::InvalidArgument(format!("Part name {} must not contain a path separator", name)));
+    }
+    if names.iter().collect::<HashSet<_>>().len() != names.len() {
+        return Err(Error::InvalidArgument(format!("File name template {} gives several parts the same name", template)));
+    }
+
+    let final_out_dir = output_folder(input, output_dir, "pages");
+    let mut created = CreatedFiles::default();
+    created.create_dir(&final_out_dir)?;
+    let pages = src.get_pages();
+    let mut written = Vec::new();
+    progress.add_total(parts.iter().map(|p| p.pages.len() as u64).sum());
+
+    for (part, name) in parts.iter().zip(names) {
+        progress.check()?;
+        let page_ids: Vec<ObjectId> = part.pages.iter().map(|n| pages[n]).collect();
+        let mut out_doc = extract_pages(&src, &page_ids, &part.outline);
+        let out_path = final_out_dir.join(name);
+        save_document(&mut out_doc, &out_path, progress)?;
+        let size = std::fs::metadata(&out_path).map_err(|e| Error::io(&out_path, e))?.len();
+        written.push(SplitPart { path: out_path.clone(), pages: part.pages.clone(), size });
+        created.push(out_path);
+        for _ in &part.pages {
+            progress.page_done();
+        }
+    }
+
+    created.keep();
+    Ok(written)
+}
+
+/// A page and every object it references.
+///
+/// `/Parent` links are not followed: from the page they lead to the whole page tree,
+/// from a widget to a form field whose other widgets sit on other pages. Other pages
+/// are not entered either, e.g. through a link's destination or an annotation's `/P`.
+/// What a page inherits from the page tree must be copied onto it beforehand.
+fn page_objects(doc: &Document, page: ObjectId) -> HashSet<ObjectId> {
+    fn collect_refs(obj: &Object, doc: &Document, set: &mut HashSet<ObjectId>) {
+        match obj {
+            Object::Reference(id) if !set.contains(id) => {
+                let Some(o) = doc.objects.get(id) else { return };
+                if !is_page_node(o) {
+                    set.insert(*id);
+                    collect_refs(o, doc, set);
+                }
+            }
+            Object::Array(arr) => {
+                for item in arr {
+                    collect_refs(item, doc, set);
+                }
+            }
+            Object::Dictionary(d) => collect_dict_refs(d, doc, set),
+            Object::Stream(s) => collect_dict_refs(&s.dict, doc, set),
+            _ => {}
+        }
+    }
+
+    fn collect_dict_refs(d: &Dictionary, doc: &Document, set: &mut HashSet<ObjectId>) {
+        for (k, v) in d.iter() {
+            if k != b"Parent" {
+                collect_refs(v, doc, set);
+            }
+        }
+    }
+
+    let mut set = HashSet::from([page]);
+    if let Ok(page_obj) = doc.get_object(page) {
+        collect_refs(page_obj, doc, &mut set);
+    }
+    set
+}
+
+fn is_page_node(object: &Object) -> bool {
+    let Ok(dict) = object.as_dict() else { return false };
+    matches!(dict.get(b"Type").and_then(Object::as_name), Ok(b"Page" | b"Pages"))
+}
+
+/// Copy `page_ids` with everything they reference into a new document, with
+/// `outline` as its bookmarks. See [`page_objects`] for what is copied.
+fn extract_pages(src: &Document, page_ids: &[ObjectId], outline: &[OutlineNode]) -> Document {
+    fn remap_object(obj: &Object, mapping: &HashMap<ObjectId, ObjectId>) -> Object {
+        match obj {
+            // references left out of the part, such as links to other pages, are cut
+            Object::Reference(r) => mapping.get(r).map_or(Object::Null, |n| Object::Reference(*n)),
+            Object::Array(arr) => Object::Array(arr.iter().map(|o| remap_object(o, mapping)).collect()),
+            Object::Dictionary(d) => {
+                let mut nd = Dictionary::new();
+                for (k, v) in d.iter() {
+                    nd.set(k.clone(), remap_object(v, mapping));
+                }
+                Object::Dictionary(nd)
+            }
+            Object::Stream(s) => {
+                let mut nd = Dictionary::new();
+                for (k, v) in s.dict.iter() {
+                    nd.set(k.clone(), remap_object(v, mapping));
+                }
+                Object::Stream(Stream::new(nd, s.content.clone()))
+            }
+            other => other.clone(),
+        }
+    }
+
+    let mut refs: HashSet<ObjectId> = HashSet::new();
+    for page_id in page_ids {
+        refs.extend(page_objects(src, *page_id));
+    }
+
+    let mut mapping: HashMap<ObjectId, ObjectId> = HashMap::new();
+    let mut out_doc = Document::with_version(&src.version);
+    let pages_id = out_doc.new_object_id();
+
+    for old_id in refs.iter() {
+        let new_id = out_doc.new_object_id();
+        mapping.insert(*old_id, new_id);
+    }
+
+    for (old_id, new_id) in mapping.iter() {
+        if let Some(old_obj) = src.objects.get(old_id) {
+            let mut new_obj = remap_object(old_obj, &mapping);
+            if page_ids.contains(old_id) {
+                if let Object::Dictionary(ref mut dict) = new_obj {
+                    dict.set("Parent", pages_id);
+                }
+            }
+            out_doc.objects.insert(*new_id, new_obj);
+        }
+    }
+
+    let mut pages_dict = Dictionary::new();
+    pages_dict.set("Type", Object::Name(b"Pages".to_vec()));
+    pages_dict.set("Kids", Object::Array(page_ids.iter().map(|id| Object::Reference(mapping[id])).collect()));
+    pages_dict.set("Count", Object::Integer(page_ids.len() as i64));
+    out_doc.objects.insert(pages_id, Object::Dictionary(pages_dict));
+
+    let catalog_id = out_doc.new_object_id();
+    let mut catalog_dict = Dictionary::new();
+    catalog_dict.set("Type", Object::Name(b"Catalog".to_vec()));
+    catalog_dict.set("Pages", Object::Reference(pages_id));
+    let items = copy_outline(src, &mut out_doc, outline, &mapping);
+    if let Some(root) = build_outline_root(&mut out_doc, &items) {
+        catalog_dict.set("Outlines", root);
+    }
+    out_doc.objects.insert(catalog_id, Object::Dictionary(catalog_dict));
+    out_doc.trailer.set("Root", catalog_id);
+
+    // a safety net: whatever the part's catalog does not reach is not written
+    prune_unreachable(&mut out_doc);
+    out_doc.renumber_objects();
+    out_doc.compress();
+    out_doc
+}
+
+/// Copy outline items into `out`, pointing their destinations at the copied pages.
+/// Only the title, style and open state are taken over, plus `URI` actions.
+fn copy_outline(src: &Document, out: &mut Document, nodes: &[OutlineNode], mapping: &HashMap<ObjectId, ObjectId>) -> Vec<OutlineNode> {
+    let mut copied = Vec::new();
+    for node in nodes {
+        let mut item = Dictionary::new();
+        if let Ok(source) = src.get_dictionary(node.id) {
+            for key in [b"Title".as_slice(), b"C", b"F", b"Count"] {
+                if let Some((_, value)) = source.get(key).ok().and_then(|v| src.dereference(v).ok()) {
+                    item.set(key, value.clone());
+                }
+            }
+            let dest = outline_destination(src, node.id).filter(|_| !node.drop_target);
+            match dest.and_then(|d| Some((d, mapping.get(&d.first()?.as_reference().ok()?)?))) {
+                Some((dest, page)) => {
+                    let mut dest = dest.clone();
+                    dest[0] = Object::Reference(*page);
+                    item.set("Dest", dest);
+                }
+                None => {
+                    let action = source.get(b"A").ok().and_then(|a| src.dereference(a).ok()).and_then(|(_, a)| a.as_dict().ok());
+                    if let Some(action) = action.filter(|a| a.get(b"S").and_then(Object::as_name).ok() == Some(b"URI".as_slice())) {
+                        item.set("A", action.clone());
+                    }
+                }
+            }
+        }
+        let id = out.add_object(item);
+        copied.push(OutlineNode::new(id, copy_outline(src, out, &node.children, mapping)));
+    }
+    copied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages(parts: &[Part]) -> Vec<Vec<u32>> {
+        parts.iter().map(|p| p.pages.clone()).collect()
+    }
+
+    /// A document of `count` pages, each holding numbers that are written with more
+    /// than twice the bytes [`estimated_size`] counts for them.
+    fn long_number_pages(count: usize) -> Document {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let kids: Vec<Object> = (0..count)
+            .map(|_| {
+                let numbers = vec![Object::Real(0.1 + 0.2); 200];
+                doc.add_object(lopdf::dictionary! { "Type" => "Page", "Parent" => pages_id, "Numbers" => numbers }).into()
+            })
+            .collect();
+        let pages = lopdf::dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => count as i64 };
+        doc.objects.insert(pages_id, Object::Dictionary(pages));
+        let catalog = doc.add_object(lopdf::dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog);
+        doc
+    }
+
+    #[test]
+    fn size_parts_give_back_pages_the_estimate_let_in() {
+        let doc = long_number_pages(8);
+        let ids = doc.get_pages();
+        let size = |part: &Part| serialized_size(&doc, &part.pages.iter().map(|n| ids[n]).collect::<Vec<_>>()).unwrap();
+        let limit = 3 * size(&Part::new(vec![1]));
+
+        let parts = size_parts(&doc, limit).unwrap();
+
+        assert_eq!(parts.iter().flat_map(|p| p.pages.clone()).collect::<Vec<_>>(), (1..=8).collect::<Vec<_>>());
+        for (part, next) in parts.iter().zip(&parts[1..]) {
+            assert!(size(part) <= limit);
+            // each part is as long as it can be
+            let mut longer = part.pages.clone();
+            longer.push(next.pages[0]);
+            assert!(size(&Part::new(longer)) > limit);
+        }
+    }
+
+    #[test]
+    fn blank_runs_split_parts() {
+        let blank = [true, false, true, true, false, false, true];
+        assert_eq!(pages(&blank_page_parts(&blank, false)), [vec![1, 2], vec![3, 4, 5, 6, 7]]);
+        assert_eq!(pages(&blank_page_parts(&blank, true)), [vec![2], vec![5, 6]]);
+        // no content at all still gives the pages back
+        assert_eq!(pages(&blank_page_parts(&[true, true], false)), [vec![1, 2]]);
+    }
+
+    #[test]
+    fn uniform_pages_allow_some_noise() {
+        let mut page = image::GrayImage::from_pixel(100, 100, image::Luma([240]));
+        assert!(is_uniform(&page));
+        // scanner noise within the tolerance, and a few specks of dust
+        for x in 0..100 {
+            page.put_pixel(x, 0, image::Luma([225]));
+        }
+        for x in 0..40 {
+            page.put_pixel(x, 50, image::Luma([0]));
+        }
+        assert!(is_uniform(&page));
+        // a line of text
+        for x in 0..100 {
+            page.put_pixel(x, 60, image::Luma([0]));
+        }
+        assert!(!is_uniform(&page));
+    }
+}
@@ -0,0 +1,50 @@
+use std::path::Path;
+
+/// Expand an output file name template for the `index`-th (1-based) input of a batch.
+///
+/// Supported placeholders are `{stem}` (input file name without extension) and `{index}`.
+pub fn output_name(template: &str, input: &Path, index: usize) -> String {
+    let stem = input.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+    expand(template, &[("stem", stem.to_string()), ("index", index.to_string())])
+}
+
+/// Whether `template` yields a distinct name for every input of a batch.
+pub fn is_per_file_template(template: &str) -> bool {
+    template.contains("{stem}") || template.contains("{index}")
+}
+
+/// Replace each `{key}` in `template` by its value; unknown placeholders are kept.
+/// Values are copied as they are, even if they contain placeholders themselves.
+pub(crate) fn expand(template: &str, vars: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let value = after.find('}').and_then(|close| vars.iter().find(|(key, _)| *key == &after[..close]).map(|(_, value)| (close, value)));
+        match value {
+            Some((close, value)) => {
+                out.push_str(value);
+                rest = &after[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_not_expanded_again() {
+        let vars = [("title", "Q{end}".to_string()), ("end", "12".to_string())];
+        assert_eq!(expand("{title} to {end}.pdf", &vars), "Q{end} to 12.pdf");
+        assert_eq!(expand("{{title}} {other} {end", &vars), "{Q{end}} {other} {end");
+    }
+}
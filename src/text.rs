@@ -0,0 +1,195 @@
+use std::io::Write;
+use std::path::Path;
+
+use lopdf::{Document, ObjectId};
+
+use crate::document::{load_document, output_folder, write_atomically, CreatedFiles};
+use crate::error::{Error, Result};
+use crate::progress::Progress;
+
+mod cmap;
+mod encoding;
+mod font;
+mod interpret;
+mod layout;
+mod table;
+
+/// How [`extract_text`] arranges the text of a page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextMode {
+    /// Lines in reading order, the columns of multi-column pages one after the other.
+    #[default]
+    Plain,
+    /// Lines and columns placed on a character grid approximating the page.
+    Layout,
+}
+
+impl std::str::FromStr for TextMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "plain" => Ok(TextMode::Plain),
+            "layout" => Ok(TextMode::Layout),
+            other => Err(Error::InvalidArgument(format!("Unknown text mode: {}", other))),
+        }
+    }
+}
+
+/// File format written by [`extract_text`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextFormat {
+    /// Plain text arranged as chosen by [`TextMode`], pages separated by a form feed.
+    #[default]
+    Text,
+    /// A JSON object with a `pages` array. Each page has its `number`, displayed `width`
+    /// and `height` in points, `rotation`, and `runs` of text drawn with one font, size
+    /// and color, each with its `text`, `bbox` as `[x0, top, x1, bottom]` measured from
+    /// the top left corner of the displayed page, `font`, `size` and `color` (`#rrggbb`).
+    Json,
+    /// One CSV file per table, `page-N-table-M.csv`, in a `{stem}-tables` folder under
+    /// the output folder. Tables are found from grids of ruled lines and from text
+    /// lined up in columns.
+    Csv,
+}
+
+impl std::str::FromStr for TextFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" | "txt" => Ok(TextFormat::Text),
+            "json" => Ok(TextFormat::Json),
+            "csv" => Ok(TextFormat::Csv),
+            other => Err(Error::InvalidArgument(format!("Unknown text format: {}", other))),
+        }
+    }
+}
+
+/// Settings for [`extract_text`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextOptions {
+    /// Ignored for [`TextFormat::Json`] and [`TextFormat::Csv`], which go by positions.
+    pub mode: TextMode,
+    pub format: TextFormat,
+    /// 1-based page numbers to extract; `None` extracts every page.
+    pub pages: Option<Vec<u32>>,
+    /// Write one file per page, `page-N.txt` (or `.json`), into a `{stem}-text` folder
+    /// under the output folder instead of a single output file. [`TextFormat::Csv`]
+    /// always writes a folder.
+    pub per_page: bool,
+}
+
+/// Extract the text of the selected pages of `input` into `output`, a plain text or JSON
+/// file as chosen by [`TextOptions::format`], or a folder with one file per page when
+/// [`TextOptions::per_page`] is set (named like [`crate::split`]'s folder), or one
+/// file per table for [`TextFormat::Csv`].
+///
+/// Text is read from the page content streams, placing each glyph with the text
+/// and transformation matrices, then regrouped into lines and columns.
+pub fn extract_text(input: &Path, output: &Path, options: &TextOptions, progress: &Progress) -> Result<()> {
+    let doc = load_document(input)?;
+    let pages = doc.get_pages();
+    let selected: Vec<(u32, ObjectId)> = match &options.pages {
+        Some(numbers) => {
+            if let Some(n) = numbers.iter().find(|n| !pages.contains_key(n)) {
+                return Err(Error::InvalidArgument(format!("Page {} is past the last page ({})", n, pages.len())));
+            }
+            pages.into_iter().filter(|(n, _)| numbers.contains(n)).collect()
+        }
+        None => pages.into_iter().collect(),
+    };
+    if selected.is_empty() {
+        return Err(Error::InvalidArgument("No pages selected".into()));
+    }
+    progress.add_total(selected.len() as u64);
+
+    let mut created = CreatedFiles::default();
+    let folder = match (options.format, options.per_page) {
+        (TextFormat::Csv, _) => Some(output_folder(input, output, "tables")),
+        (_, true) => Some(output_folder(input, output, "text")),
+        _ => None,
+    };
+    if let Some(folder) = &folder {
+        created.create_dir(folder)?;
+    }
+    let page_text = |glyphs: &[interpret::Glyph]| match options.mode {
+        TextMode::Plain => layout::plain_text(glyphs),
+        TextMode::Layout => layout::layout_text(glyphs),
+    };
+    let mut text = String::new();
+    let mut json_pages = Vec::new();
+    for (i, (number, page)) in selected.into_iter().enumerate() {
+        progress.check()?;
+        let (glyphs, rules) = interpret::page_contents(&doc, page).map_err(|source| Error::Parse { path: input.to_path_buf(), source })?;
+        match (&folder, options.format) {
+            (folder, TextFormat::Csv) => {
+                let folder = folder.as_deref().unwrap_or(output);
+                for (i, found) in table::tables(&glyphs, &rules).iter().enumerate() {
+                    let path = folder.join(format!("page-{}-table-{}.csv", number, i + 1));
+                    write_atomically(&path, progress, |w| w.write_all(table::csv(found).as_bytes()))?;
+                    created.push(path);
+                }
+            }
+            (Some(folder), TextFormat::Text) => {
+                let path = folder.join(format!("page-{}.txt", number));
+                write_atomically(&path, progress, |w| w.write_all(page_text(&glyphs).as_bytes()))?;
+                created.push(path);
+            }
+            (Some(folder), TextFormat::Json) => {
+                let path = folder.join(format!("page-{}.json", number));
+                let json = page_json(&doc, number, page, &glyphs);
+                write_atomically(&path, progress, |w| serde_json::to_writer(w, &json).map_err(std::io::Error::from))?;
+                created.push(path);
+            }
+            (None, TextFormat::Text) => {
+                if i > 0 {
+                    text.push('\x0C');
+                }
+                text.push_str(&page_text(&glyphs));
+            }
+            (None, TextFormat::Json) => json_pages.push(page_json(&doc, number, page, &glyphs)),
+        }
+        progress.page_done();
+    }
+    match (folder, options.format) {
+        (_, TextFormat::Csv) if created.is_empty() => Err(Error::Unsupported("No tables found on the selected pages".into())),
+        (Some(_), _) | (None, TextFormat::Csv) => {
+            created.keep();
+            Ok(())
+        }
+        (None, TextFormat::Text) => write_atomically(output, progress, |w| w.write_all(text.as_bytes())),
+        (None, TextFormat::Json) => {
+            let json = serde_json::json!({ "pages": json_pages });
+            write_atomically(output, progress, |w| serde_json::to_writer(w, &json).map_err(std::io::Error::from))
+        }
+    }
+}
+
+/// The entry of a page in the [`TextFormat::Json`] output.
+fn page_json(doc: &Document, number: u32, page: ObjectId, glyphs: &[interpret::Glyph]) -> serde_json::Value {
+    let (width, height) = interpret::page_size(doc, page);
+    // hundredths of a point are plenty and keep the file readable
+    let round = |v: f64| (v * 100.0).round() / 100.0;
+    let runs: Vec<serde_json::Value> = layout::runs(glyphs)
+        .into_iter()
+        .map(|run| {
+            let [x0, y0, x1, y1] = run.bbox;
+            let [r, g, b] = run.color;
+            serde_json::json!({
+                "text": run.text,
+                "bbox": [round(x0), round(height - y1), round(x1), round(height - y0)],
+                "font": run.font.name,
+                "size": round(run.size),
+                "color": format!("#{:02x}{:02x}{:02x}", r, g, b),
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "number": number,
+        "width": round(width),
+        "height": round(height),
+        "rotation": interpret::page_rotation(doc, page),
+        "runs": runs,
+    })
+}
@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+
+use encoding::all::{BIG5_2003, EUC_JP, GB18030, WINDOWS_31J, WINDOWS_949};
+use encoding::{DecoderTrap, Encoding};
+
+use super::encoding::glyph_name_text;
+
+/// Ranges mapping more codes than this are cut short, so a corrupt CMap cannot fill memory.
+const MAX_RANGE: u32 = 0xFFFF;
+
+/// A CMap: how a string splits into character codes and what the codes map to, either
+/// CIDs (the `/Encoding` of a composite font) or text (a `/ToUnicode` CMap).
+#[derive(Debug, Default)]
+pub(super) struct CMap {
+    /// Valid codes as low and high bytes of equal length.
+    codespace: Vec<(Vec<u8>, Vec<u8>)>,
+    text: HashMap<u32, String>,
+    /// `bfrange` entries with a single destination: the last UTF-16 unit counts up.
+    text_ranges: Vec<(u32, u32, Vec<u16>)>,
+    cids: HashMap<u32, u32>,
+    /// `cidrange` entries: first code, last code, CID of the first code.
+    cid_ranges: Vec<(u32, u32, u32)>,
+    /// Each code is its own CID, as in `Identity-H`.
+    identity: bool,
+    /// Codes are Unicode text in this form, as in `UniGB-UCS2-H`.
+    unicode: Option<UnicodeForm>,
+    /// Codes are text in a legacy CJK encoding, as in `90ms-RKSJ-H`.
+    legacy: Option<Legacy>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnicodeForm {
+    Ucs2,
+    Utf16,
+    Utf32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Legacy {
+    ShiftJis,
+    EucJp,
+    /// JIS X 0208 as two 7-bit bytes, ISO-2022-JP without its escapes.
+    Jis,
+    /// GB 2312, GBK and GB 18030, each a superset of the one before.
+    Gb,
+    Big5,
+    /// EUC-KR and its superset UHC.
+    Korean,
+}
+
+impl Legacy {
+    /// The legacy encoding of a predefined CMap's codes, `None` for CNS 11643.
+    fn of(name: &str) -> Option<Legacy> {
+        if name.contains("RKSJ") {
+            Some(Legacy::ShiftJis)
+        } else if name.starts_with("EUC-") {
+            Some(Legacy::EucJp)
+        } else if name == "H" || name == "V" {
+            Some(Legacy::Jis)
+        } else if name.starts_with("GB") {
+            Some(Legacy::Gb)
+        } else if name.contains("B5") {
+            Some(Legacy::Big5)
+        } else if name.starts_with("KSC") {
+            Some(Legacy::Korean)
+        } else {
+            None
+        }
+    }
+
+    /// Text of a code; codes below 0x100 are one byte, the others two.
+    fn text(self, code: u32) -> Option<String> {
+        let bytes = match code {
+            0..=0xFF => vec![code as u8],
+            _ => vec![(code >> 8) as u8, code as u8],
+        };
+        let (encoding, bytes): (&dyn Encoding, Vec<u8>) = match self {
+            Legacy::ShiftJis => (WINDOWS_31J, bytes),
+            Legacy::EucJp => (EUC_JP, bytes),
+            // the same characters as EUC-JP with the high bits clear
+            Legacy::Jis => (EUC_JP, bytes.iter().map(|b| b | 0x80).collect()),
+            Legacy::Gb => (GB18030, bytes),
+            Legacy::Big5 => (BIG5_2003, bytes),
+            Legacy::Korean => (WINDOWS_949, bytes),
+        };
+        encoding.decode(&bytes, DecoderTrap::Strict).ok().filter(|text| !text.is_empty())
+    }
+}
+
+impl CMap {
+    /// One of the predefined CMaps a composite font can name as its `/Encoding`.
+    ///
+    /// The code space is known for the Identity, Unicode and legacy CJK encodings; CIDs
+    /// for Identity, text for the others. The CIDs of the Unicode and legacy encodings
+    /// need Adobe's CMap files, which are not bundled.
+    pub(super) fn predefined(name: &str) -> CMap {
+        let two_byte = |ranges: &[(u16, u16)]| -> Vec<(Vec<u8>, Vec<u8>)> {
+            ranges.iter().map(|(lo, hi)| (lo.to_be_bytes().to_vec(), hi.to_be_bytes().to_vec())).collect()
+        };
+        let one_byte = |lo: u8, hi: u8| (vec![lo], vec![hi]);
+        let mut cmap = CMap::default();
+        if name.starts_with("Identity-") {
+            cmap.codespace = two_byte(&[(0x0000, 0xFFFF)]);
+            cmap.identity = true;
+        } else if name.contains("UCS2") {
+            cmap.codespace = two_byte(&[(0x0000, 0xFFFF)]);
+            cmap.unicode = Some(UnicodeForm::Ucs2);
+        } else if name.contains("UTF16") {
+            cmap.codespace = two_byte(&[(0x0000, 0xD7FF), (0xE000, 0xFFFF)]);
+            cmap.codespace.push((vec![0xD8, 0x00, 0xDC, 0x00], vec![0xDB, 0xFF, 0xDF, 0xFF]));
+            cmap.unicode = Some(UnicodeForm::Utf16);
+        } else if name.contains("UTF32") {
+            cmap.codespace.push((vec![0, 0, 0, 0], vec![0, 0x10, 0xFF, 0xFF]));
+            cmap.unicode = Some(UnicodeForm::Utf32);
+        } else if name.contains("RKSJ") {
+            // Shift-JIS
+            cmap.codespace = vec![one_byte(0x00, 0x80), one_byte(0xA0, 0xDF), one_byte(0xFD, 0xFF)];
+            cmap.codespace.extend(two_byte(&[(0x8140, 0x9FFC), (0xE040, 0xFCFC)]));
+        } else if name.contains("EUC") && !name.starts_with("GBK") {
+            cmap.codespace = vec![one_byte(0x00, 0x80)];
+            cmap.codespace.extend(two_byte(&[(0x8EA0, 0x8EFE), (0xA1A1, 0xFEFE)]));
+        } else if name.starts_with("GBK") || name.contains("UHC") || name.contains("B5") {
+            // GBK, Korean UHC and the Big Five variants: a lead byte from 0x81
+            cmap.codespace = vec![one_byte(0x00, 0x80)];
+            cmap.codespace.extend(two_byte(&[(0x8140, 0xFEFE)]));
+        } else if name == "H" || name == "V" {
+            // ISO-2022-JP
+            cmap.codespace = two_byte(&[(0x2121, 0x7E7E)]);
+        } else {
+            cmap.codespace = two_byte(&[(0x0000, 0xFFFF)]);
+        }
+        cmap.legacy = Legacy::of(name);
+        cmap
+    }
+
+    /// Parse an embedded CMap stream. A `usecmap` of a predefined CMap is taken as the base.
+    pub(super) fn parse(data: &[u8]) -> CMap {
+        let mut cmap = CMap::default();
+        let mut operands: Vec<Token> = Vec::new();
+        for token in tokenize(data) {
+            let Token::Keyword(keyword) = token else {
+                operands.push(token);
+                continue;
+            };
+            match keyword.as_str() {
+                "usecmap" => {
+                    if let Some(Token::Name(name)) = operands.last() {
+                        let base = CMap::predefined(name);
+                        cmap.identity = base.identity;
+                        cmap.unicode = base.unicode;
+                        cmap.legacy = base.legacy;
+                        cmap.codespace.extend(base.codespace);
+                    }
+                }
+                "endcodespacerange" => {
+                    for pair in operands.chunks_exact(2) {
+                        if let [Token::Hex(lo), Token::Hex(hi)] = pair {
+                            if lo.len() == hi.len() && !lo.is_empty() {
+                                cmap.codespace.push((lo.clone(), hi.clone()));
+                            }
+                        }
+                    }
+                }
+                "endbfchar" => {
+                    for pair in operands.chunks_exact(2) {
+                        let text = match &pair[1] {
+                            Token::Hex(dst) => utf16_text(&units(dst)),
+                            Token::Name(name) => glyph_name_text(name).unwrap_or_default(),
+                            _ => continue,
+                        };
+                        if let Token::Hex(src) = &pair[0] {
+                            cmap.text.insert(code_value(src), text);
+                        }
+                    }
+                }
+                "endbfrange" => {
+                    for triple in operands.chunks_exact(3) {
+                        let [Token::Hex(lo), Token::Hex(hi), dst] = triple else { continue };
+                        let (lo, hi) = (code_value(lo), code_value(hi));
+                        if hi < lo {
+                            continue;
+                        }
+                        let hi = hi.min(lo.saturating_add(MAX_RANGE));
+                        match dst {
+                            Token::Hex(dst) => cmap.text_ranges.push((lo, hi, units(dst))),
+                            Token::Array(items) => {
+                                for (code, item) in (lo..=hi).zip(items) {
+                                    if let Token::Hex(dst) = item {
+                                        cmap.text.insert(code, utf16_text(&units(dst)));
+                                    }
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                "endcidchar" => {
+                    for pair in operands.chunks_exact(2) {
+                        if let [Token::Hex(src), Token::Int(cid)] = pair {
+                            cmap.cids.insert(code_value(src), *cid);
+                        }
+                    }
+                }
+                "endcidrange" => {
+                    for triple in operands.chunks_exact(3) {
+                        if let [Token::Hex(lo), Token::Hex(hi), Token::Int(cid)] = triple {
+                            cmap.cid_ranges.push((code_value(lo), code_value(hi), *cid));
+                        }
+                    }
+                }
+                _ => {}
+            }
+            operands.clear();
+        }
+        cmap
+    }
+
+    /// Split a string into codes of the code space, with their length in bytes; bytes
+    /// outside it are taken one at a time.
+    pub(super) fn codes(&self, bytes: &[u8]) -> Vec<(u32, usize)> {
+        let mut codes = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            let len = (1..=4)
+                .find(|n| {
+                    let Some(code) = bytes.get(i..i + n) else { return false };
+                    self.codespace.iter().any(|(lo, hi)| {
+                        lo.len() == *n && code.iter().zip(lo).zip(hi).all(|((b, lo), hi)| lo <= b && b <= hi)
+                    })
+                })
+                .unwrap_or(1);
+            codes.push((code_value(&bytes[i..i + len]), len));
+            i += len;
+        }
+        codes
+    }
+
+    /// CID of a code, for a CMap used as the encoding of a composite font.
+    pub(super) fn cid(&self, code: u32) -> Option<u32> {
+        if let Some(cid) = self.cids.get(&code) {
+            return Some(*cid);
+        }
+        if let Some((lo, _, cid)) = self.cid_ranges.iter().find(|(lo, hi, _)| (*lo..=*hi).contains(&code)) {
+            return cid.checked_add(code - lo);
+        }
+        self.identity.then_some(code)
+    }
+
+    /// Whether a code of a legacy CJK encoding is ASCII or half-width kana, which most
+    /// fonts draw at half the width of the other characters.
+    pub(super) fn is_half_width(&self, code: u32) -> bool {
+        match self.legacy {
+            // EUC-JP gives half-width kana a 0x8E prefix
+            Some(Legacy::EucJp) => code <= 0xFF || code >> 8 == 0x8E,
+            Some(_) => code <= 0xFF,
+            None => false,
+        }
+    }
+
+    /// Text a code maps to.
+    pub(super) fn text(&self, code: u32) -> Option<String> {
+        if let Some(text) = self.text.get(&code) {
+            return Some(text.clone());
+        }
+        if let Some((lo, _, dst)) = self.text_ranges.iter().find(|(lo, hi, _)| (*lo..=*hi).contains(&code)) {
+            let mut dst = dst.clone();
+            if let Some(last) = dst.last_mut() {
+                *last = last.wrapping_add((code - lo) as u16);
+            }
+            return Some(utf16_text(&dst));
+        }
+        if let Some(legacy) = self.legacy {
+            return legacy.text(code);
+        }
+        match self.unicode? {
+            UnicodeForm::Ucs2 => char::from_u32(code).map(String::from),
+            UnicodeForm::Utf16 if code > 0xFFFF => Some(utf16_text(&[(code >> 16) as u16, code as u16])),
+            UnicodeForm::Utf16 | UnicodeForm::Utf32 => char::from_u32(code).map(String::from),
+        }
+    }
+}
+
+fn code_value(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |code, b| code << 8 | *b as u32)
+}
+
+/// UTF-16BE code units of a destination string; a single byte stands for itself.
+fn units(bytes: &[u8]) -> Vec<u16> {
+    match bytes {
+        [b] => vec![*b as u16],
+        _ => bytes.chunks(2).map(|c| c.iter().fold(0, |unit, b| unit << 8 | *b as u16)).collect(),
+    }
+}
+
+fn utf16_text(units: &[u16]) -> String {
+    String::from_utf16_lossy(units)
+}
+
+#[derive(Debug)]
+enum Token {
+    Hex(Vec<u8>),
+    Int(u32),
+    Name(String),
+    Array(Vec<Token>),
+    Keyword(String),
+    /// Literal strings and dictionary brackets, which the mappings never use.
+    Other,
+}
+
+/// Split CMap source into the tokens the mapping sections are made of; arrays are
+/// returned as one token.
+fn tokenize(data: &[u8]) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut arrays: Vec<Vec<Token>> = Vec::new();
+    let mut i = 0;
+    let is_delimiter = |b: u8| b.is_ascii_whitespace() || b"()<>[]{}/%".contains(&b);
+    while i < data.len() {
+        let b = data[i];
+        let token = match b {
+            b'%' => {
+                while i < data.len() && data[i] != b'\n' && data[i] != b'\r' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'<' if data.get(i + 1) == Some(&b'<') => {
+                i += 2;
+                Token::Other
+            }
+            b'>' if data.get(i + 1) == Some(&b'>') => {
+                i += 2;
+                Token::Other
+            }
+            b'<' => {
+                let end = data[i..].iter().position(|b| *b == b'>').map_or(data.len(), |p| i + p);
+                let digits: Vec<u8> = data[i + 1..end].iter().filter_map(|d| (*d as char).to_digit(16)).map(|d| d as u8).collect();
+                i = end + 1;
+                // an odd final digit is followed by an implied 0
+                Token::Hex(digits.chunks(2).map(|d| d[0] << 4 | d.get(1).copied().unwrap_or(0)).collect())
+            }
+            b'(' => {
+                let mut depth = 0;
+                while i < data.len() {
+                    match data[i] {
+                        b'\\' => i += 1,
+                        b'(' => depth += 1,
+                        b')' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                i += 1;
+                Token::Other
+            }
+            b'[' => {
+                arrays.push(Vec::new());
+                i += 1;
+                continue;
+            }
+            b']' => {
+                i += 1;
+                match arrays.pop() {
+                    Some(items) => Token::Array(items),
+                    None => continue,
+                }
+            }
+            b if b.is_ascii_whitespace() || b == b'{' || b == b'}' || b == b')' || b == b'>' => {
+                i += 1;
+                continue;
+            }
+            _ => {
+                let start = i;
+                i += 1;
+                while i < data.len() && !is_delimiter(data[i]) {
+                    i += 1;
+                }
+                let word = String::from_utf8_lossy(&data[start..i]).into_owned();
+                match word.strip_prefix('/') {
+                    Some(name) => Token::Name(name.to_string()),
+                    None => match word.parse::<u32>() {
+                        Ok(n) => Token::Int(n),
+                        Err(_) => Token::Keyword(word),
+                    },
+                }
+            }
+        };
+        match arrays.last_mut() {
+            Some(array) => array.push(token),
+            None => tokens.push(token),
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bfrange_maps_counting_ranges_and_arrays() {
+        let cmap = CMap::parse(
+            b"1 begincodespacerange <00> <FF> endcodespacerange
+              2 beginbfrange <20> <22> <0041> <30> <31> [<0061> <00660069>] endbfrange
+              1 beginbfchar <40> /germandbls endbfchar",
+        );
+        assert_eq!(cmap.text(0x22).as_deref(), Some("C"));
+        assert_eq!(cmap.text(0x30).as_deref(), Some("a"));
+        assert_eq!(cmap.text(0x31).as_deref(), Some("fi"));
+        assert_eq!(cmap.text(0x40).as_deref(), Some("ß"));
+        assert_eq!(cmap.text(0x23), None);
+    }
+
+    #[test]
+    fn odd_hex_strings_end_with_an_implied_zero() {
+        let cmap = CMap::parse(b"1 beginbfchar <7> <004> endbfchar");
+        assert_eq!(cmap.text(0x70).as_deref(), Some("@"));
+    }
+
+    #[test]
+    fn usecmap_takes_the_predefined_base() {
+        let cmap = CMap::parse(b"/Identity-H usecmap 1 begincidchar <0003> 99 endcidchar");
+        assert_eq!(cmap.codes(b"\x00\x03\x01\x00"), [(3, 2), (0x100, 2)]);
+        assert_eq!(cmap.cid(3), Some(99));
+        assert_eq!(cmap.cid(0x100), Some(0x100));
+    }
+
+    #[test]
+    fn mixed_width_code_spaces_split_by_lead_byte() {
+        let cmap = CMap::parse(b"2 begincodespacerange <00> <80> <8140> <9FFC> endcodespacerange");
+        assert_eq!(cmap.codes(b"A\x81\x40B\xFF"), [(0x41, 1), (0x8140, 2), (0x42, 1), (0xFF, 1)]);
+    }
+
+    #[test]
+    fn legacy_encodings_give_text_and_half_widths() {
+        let decode = |name: &str, bytes: &[u8]| -> String {
+            let cmap = CMap::predefined(name);
+            cmap.codes(bytes).iter().filter_map(|(code, _)| cmap.text(*code)).collect()
+        };
+        assert_eq!(decode("90ms-RKSJ-H", b"\x93\xfa\x96\x7b1"), "日本1");
+        assert_eq!(decode("EUC-H", b"\xc6\xfc\x8e\xb1"), "日ｱ");
+        assert_eq!(decode("H", b"\x46\x7c"), "日");
+        assert_eq!(decode("GBK-EUC-H", b"\xd6\xd0\xce\xc4"), "中文");
+        assert_eq!(decode("ETen-B5-H", b"\xa4\xa4\xa4\xe5"), "中文");
+        assert_eq!(decode("KSCms-UHC-H", b"\xc7\xd1\xb1\xdb"), "한글");
+
+        let cmap = CMap::predefined("EUC-H");
+        assert!(cmap.is_half_width(0x41) && cmap.is_half_width(0x8EB1) && !cmap.is_half_width(0xC6FC));
+        assert!(!CMap::predefined("Identity-H").is_half_width(0x41));
+    }
+
+    #[test]
+    fn ranges_at_the_top_of_the_code_space_do_not_overflow() {
+        let cmap = CMap::parse(
+            b"1 beginbfrange <FFFFFFFE> <FFFFFFFF> <0041> endbfrange
+              1 begincidrange <FFFFFFF0> <FFFFFFFF> 4294967295 endcidrange",
+        );
+        assert_eq!(cmap.text(0xFFFF_FFFF).as_deref(), Some("B"));
+        assert_eq!(cmap.cid(0xFFFF_FFF0), Some(u32::MAX));
+        assert_eq!(cmap.cid(0xFFFF_FFF1), None);
+    }
+}
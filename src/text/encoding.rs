@@ -0,0 +1,519 @@
+/// A simple font's built-in encoding that `/Encoding` (or its `/BaseEncoding`) can name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum BaseEncoding {
+    Standard,
+    WinAnsi,
+    MacRoman,
+}
+
+impl BaseEncoding {
+    pub(super) fn from_name(name: &[u8]) -> Option<BaseEncoding> {
+        match name {
+            b"StandardEncoding" => Some(BaseEncoding::Standard),
+            b"WinAnsiEncoding" => Some(BaseEncoding::WinAnsi),
+            b"MacRomanEncoding" => Some(BaseEncoding::MacRoman),
+            _ => None,
+        }
+    }
+
+    /// Character a code stands for, `None` where the encoding leaves it undefined.
+    pub(super) fn char(self, code: u8) -> Option<char> {
+        let c = match (self, code) {
+            (_, 0..=0x1F) | (_, 0x7F) => '\0',
+            (BaseEncoding::Standard, 0x27) => '\u{2019}',
+            (BaseEncoding::Standard, 0x60) => '\u{2018}',
+            (_, 0x20..=0x7E) => code as char,
+            (BaseEncoding::Standard, 0x80..=0x9F) => '\0',
+            (BaseEncoding::Standard, _) => high_char(&STANDARD_HIGH, code - 0xA0),
+            (BaseEncoding::WinAnsi, 0x80..=0x9F) => high_char(&WIN_ANSI_HIGH, code - 0x80),
+            (BaseEncoding::WinAnsi, _) => code as char,
+            (BaseEncoding::MacRoman, _) => high_char(&MAC_ROMAN_HIGH, code - 0x80),
+        };
+        (c != '\0').then_some(c)
+    }
+}
+
+/// Character `index` of a table written as rows of 16, `\0` marking undefined codes.
+fn high_char(rows: &[&str], index: u8) -> char {
+    rows.get(index as usize / 16).and_then(|row| row.chars().nth(index as usize % 16)).unwrap_or('\0')
+}
+
+/// StandardEncoding from 0xA0.
+const STANDARD_HIGH: [&str; 6] = [
+    "\0¡¢£⁄¥ƒ§¤'“«‹›ﬁﬂ",
+    "\0–†‡·\0¶•‚„”»…‰\0¿",
+    "\0`´ˆ˜¯˘˙¨\0˚¸\0˝˛ˇ",
+    "—\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+    "\0Æ\0ª\0\0\0\0ŁØŒº\0\0\0\0",
+    "\0æ\0\0\0ı\0\0łøœß\0\0\0\0",
+];
+
+/// WinAnsiEncoding from 0x80 to 0x9F; the rest is Latin-1.
+const WIN_ANSI_HIGH: [&str; 2] = ["€\0‚ƒ„…†‡ˆ‰Š‹Œ\0Ž\0", "\0‘’“”•–—˜™š›œ\0žŸ"];
+
+/// MacRomanEncoding from 0x80.
+const MAC_ROMAN_HIGH: [&str; 8] = [
+    "ÄÅÇÉÑÖÜáàâäãåçéè",
+    "êëíìîïñóòôöõúùûü",
+    "†°¢£§•¶ß®©™´¨≠ÆØ",
+    "∞±≤≥¥µ∂∑∏π∫ªºΩæø",
+    "¿¡¬√ƒ≈∆«»…\u{A0}ÀÃÕŒœ",
+    "–—“”‘’÷◊ÿŸ⁄¤‹›ﬁﬂ",
+    "‡·‚„‰ÂÊÁËÈÍÎÏÌÓÔ",
+    "\0ÒÚÛÙıˆ˜¯˘˙˚¸˝˛ˇ",
+];
+
+/// Text of a glyph name as the Adobe Glyph List specification reads it: the part
+/// before a `.` suffix, split at `_` into components that are each a name from the
+/// list, `uniXXXX` (one or more UTF-16 code units) or `uXXXX` to `uXXXXXX`.
+pub(super) fn glyph_name_text(name: &str) -> Option<String> {
+    let base = name.split('.').next().unwrap_or_default();
+    let mut text = String::new();
+    for component in base.split('_') {
+        text.push_str(&glyph_component(component)?);
+    }
+    (!text.is_empty()).then_some(text)
+}
+
+fn glyph_component(name: &str) -> Option<String> {
+    if let Some(c) = glyph_char(name) {
+        return Some(c.to_string());
+    }
+    let is_hex = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit() || (b'A'..=b'F').contains(&b));
+    if let Some(hex) = name.strip_prefix("uni").filter(|h| h.len() % 4 == 0 && is_hex(h)) {
+        let units: Vec<u16> = (0..hex.len()).step_by(4).filter_map(|i| u16::from_str_radix(&hex[i..i + 4], 16).ok()).collect();
+        return String::from_utf16(&units).ok();
+    }
+    if let Some(hex) = name.strip_prefix('u').filter(|h| (4..=6).contains(&h.len()) && is_hex(h)) {
+        return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32).map(String::from);
+    }
+    None
+}
+
+/// Characters of the glyph names of the Latin, Greek and symbol character sets that
+/// fonts commonly use, from the Adobe Glyph List.
+fn glyph_char(name: &str) -> Option<char> {
+    if name.len() == 1 && name.as_bytes()[0].is_ascii_alphabetic() {
+        return name.chars().next();
+    }
+    let c = match name {
+        "space" | "spacehackarabic" => ' ',
+        "exclam" => '!',
+        "quotedbl" => '"',
+        "numbersign" => '#',
+        "dollar" => '$',
+        "percent" => '%',
+        "ampersand" => '&',
+        "quotesingle" => '\'',
+        "parenleft" => '(',
+        "parenright" => ')',
+        "asterisk" => '*',
+        "plus" => '+',
+        "comma" => ',',
+        "hyphen" => '-',
+        "period" => '.',
+        "slash" => '/',
+        "zero" => '0',
+        "one" => '1',
+        "two" => '2',
+        "three" => '3',
+        "four" => '4',
+        "five" => '5',
+        "six" => '6',
+        "seven" => '7',
+        "eight" => '8',
+        "nine" => '9',
+        "colon" => ':',
+        "semicolon" => ';',
+        "less" => '<',
+        "equal" => '=',
+        "greater" => '>',
+        "question" => '?',
+        "at" => '@',
+        "bracketleft" => '[',
+        "backslash" => '\\',
+        "bracketright" => ']',
+        "asciicircum" => '^',
+        "underscore" => '_',
+        "grave" => '`',
+        "braceleft" => '{',
+        "bar" => '|',
+        "braceright" => '}',
+        "asciitilde" => '~',
+        "nbspace" | "nonbreakingspace" => '\u{A0}',
+        "exclamdown" => '¡',
+        "cent" => '¢',
+        "sterling" => '£',
+        "currency" => '¤',
+        "yen" => '¥',
+        "brokenbar" => '¦',
+        "section" => '§',
+        "dieresis" => '¨',
+        "copyright" => '©',
+        "ordfeminine" => 'ª',
+        "guillemotleft" => '«',
+        "logicalnot" => '¬',
+        "sfthyphen" | "softhyphen" => '\u{AD}',
+        "registered" => '®',
+        "macron" | "overscore" => '¯',
+        "degree" => '°',
+        "plusminus" => '±',
+        "twosuperior" => '²',
+        "threesuperior" => '³',
+        "acute" => '´',
+        "mu" | "mu1" => 'µ',
+        "paragraph" => '¶',
+        "periodcentered" | "middot" => '·',
+        "cedilla" => '¸',
+        "onesuperior" => '¹',
+        "ordmasculine" => 'º',
+        "guillemotright" => '»',
+        "onequarter" => '¼',
+        "onehalf" => '½',
+        "threequarters" => '¾',
+        "questiondown" => '¿',
+        "Agrave" => 'À',
+        "Aacute" => 'Á',
+        "Acircumflex" => 'Â',
+        "Atilde" => 'Ã',
+        "Adieresis" => 'Ä',
+        "Aring" => 'Å',
+        "AE" => 'Æ',
+        "Ccedilla" => 'Ç',
+        "Egrave" => 'È',
+        "Eacute" => 'É',
+        "Ecircumflex" => 'Ê',
+        "Edieresis" => 'Ë',
+        "Igrave" => 'Ì',
+        "Iacute" => 'Í',
+        "Icircumflex" => 'Î',
+        "Idieresis" => 'Ï',
+        "Eth" => 'Ð',
+        "Ntilde" => 'Ñ',
+        "Ograve" => 'Ò',
+        "Oacute" => 'Ó',
+        "Ocircumflex" => 'Ô',
+        "Otilde" => 'Õ',
+        "Odieresis" => 'Ö',
+        "multiply" => '×',
+        "Oslash" => 'Ø',
+        "Ugrave" => 'Ù',
+        "Uacute" => 'Ú',
+        "Ucircumflex" => 'Û',
+        "Udieresis" => 'Ü',
+        "Yacute" => 'Ý',
+        "Thorn" => 'Þ',
+        "germandbls" => 'ß',
+        "agrave" => 'à',
+        "aacute" => 'á',
+        "acircumflex" => 'â',
+        "atilde" => 'ã',
+        "adieresis" => 'ä',
+        "aring" => 'å',
+        "ae" => 'æ',
+        "ccedilla" => 'ç',
+        "egrave" => 'è',
+        "eacute" => 'é',
+        "ecircumflex" => 'ê',
+        "edieresis" => 'ë',
+        "igrave" => 'ì',
+        "iacute" => 'í',
+        "icircumflex" => 'î',
+        "idieresis" => 'ï',
+        "eth" => 'ð',
+        "ntilde" => 'ñ',
+        "ograve" => 'ò',
+        "oacute" => 'ó',
+        "ocircumflex" => 'ô',
+        "otilde" => 'õ',
+        "odieresis" => 'ö',
+        "divide" => '÷',
+        "oslash" => 'ø',
+        "ugrave" => 'ù',
+        "uacute" => 'ú',
+        "ucircumflex" => 'û',
+        "udieresis" => 'ü',
+        "yacute" => 'ý',
+        "thorn" => 'þ',
+        "ydieresis" => 'ÿ',
+        "Amacron" => 'Ā',
+        "amacron" => 'ā',
+        "Abreve" => 'Ă',
+        "abreve" => 'ă',
+        "Aogonek" => 'Ą',
+        "aogonek" => 'ą',
+        "Cacute" => 'Ć',
+        "cacute" => 'ć',
+        "Cdotaccent" => 'Ċ',
+        "cdotaccent" => 'ċ',
+        "Ccaron" => 'Č',
+        "ccaron" => 'č',
+        "Dcaron" => 'Ď',
+        "dcaron" => 'ď',
+        "Dcroat" | "Dslash" => 'Đ',
+        "dcroat" | "dmacron" => 'đ',
+        "Emacron" => 'Ē',
+        "emacron" => 'ē',
+        "Ebreve" => 'Ĕ',
+        "ebreve" => 'ĕ',
+        "Edotaccent" => 'Ė',
+        "edotaccent" => 'ė',
+        "Eogonek" => 'Ę',
+        "eogonek" => 'ę',
+        "Ecaron" => 'Ě',
+        "ecaron" => 'ě',
+        "Gbreve" => 'Ğ',
+        "gbreve" => 'ğ',
+        "Gdotaccent" => 'Ġ',
+        "gdotaccent" => 'ġ',
+        "Gcommaaccent" | "Gcedilla" => 'Ģ',
+        "gcommaaccent" | "gcedilla" => 'ģ',
+        "Hbar" => 'Ħ',
+        "hbar" => 'ħ',
+        "Itilde" => 'Ĩ',
+        "itilde" => 'ĩ',
+        "Imacron" => 'Ī',
+        "imacron" => 'ī',
+        "Iogonek" => 'Į',
+        "iogonek" => 'į',
+        "Idotaccent" => 'İ',
+        "dotlessi" => 'ı',
+        "IJ" => 'Ĳ',
+        "ij" => 'ĳ',
+        "Kcommaaccent" | "Kcedilla" => 'Ķ',
+        "kcommaaccent" | "kcedilla" => 'ķ',
+        "Lacute" => 'Ĺ',
+        "lacute" => 'ĺ',
+        "Lcommaaccent" | "Lcedilla" => 'Ļ',
+        "lcommaaccent" | "lcedilla" => 'ļ',
+        "Lcaron" => 'Ľ',
+        "lcaron" => 'ľ',
+        "Ldot" => 'Ŀ',
+        "ldot" => 'ŀ',
+        "Lslash" => 'Ł',
+        "lslash" => 'ł',
+        "Nacute" => 'Ń',
+        "nacute" => 'ń',
+        "Ncommaaccent" | "Ncedilla" => 'Ņ',
+        "ncommaaccent" | "ncedilla" => 'ņ',
+        "Ncaron" => 'Ň',
+        "ncaron" => 'ň',
+        "Eng" => 'Ŋ',
+        "eng" => 'ŋ',
+        "Omacron" => 'Ō',
+        "omacron" => 'ō',
+        "Ohungarumlaut" | "Odblacute" => 'Ő',
+        "ohungarumlaut" | "odblacute" => 'ő',
+        "OE" => 'Œ',
+        "oe" => 'œ',
+        "Racute" => 'Ŕ',
+        "racute" => 'ŕ',
+        "Rcommaaccent" | "Rcedilla" => 'Ŗ',
+        "rcommaaccent" | "rcedilla" => 'ŗ',
+        "Rcaron" => 'Ř',
+        "rcaron" => 'ř',
+        "Sacute" => 'Ś',
+        "sacute" => 'ś',
+        "Scircumflex" => 'Ŝ',
+        "scircumflex" => 'ŝ',
+        "Scedilla" => 'Ş',
+        "scedilla" => 'ş',
+        "Scaron" => 'Š',
+        "scaron" => 'š',
+        "Tcommaaccent" | "Tcedilla" => 'Ţ',
+        "tcommaaccent" | "tcedilla" => 'ţ',
+        "Tcaron" => 'Ť',
+        "tcaron" => 'ť',
+        "Utilde" => 'Ũ',
+        "utilde" => 'ũ',
+        "Umacron" => 'Ū',
+        "umacron" => 'ū',
+        "Ubreve" => 'Ŭ',
+        "ubreve" => 'ŭ',
+        "Uring" => 'Ů',
+        "uring" => 'ů',
+        "Uhungarumlaut" | "Udblacute" => 'Ű',
+        "uhungarumlaut" | "udblacute" => 'ű',
+        "Uogonek" => 'Ų',
+        "uogonek" => 'ų',
+        "Wcircumflex" => 'Ŵ',
+        "wcircumflex" => 'ŵ',
+        "Ycircumflex" => 'Ŷ',
+        "ycircumflex" => 'ŷ',
+        "Ydieresis" => 'Ÿ',
+        "Zacute" => 'Ź',
+        "zacute" => 'ź',
+        "Zdotaccent" => 'Ż',
+        "zdotaccent" => 'ż',
+        "Zcaron" => 'Ž',
+        "zcaron" => 'ž',
+        "longs" => 'ſ',
+        "florin" => 'ƒ',
+        "Scommaaccent" => 'Ș',
+        "scommaaccent" => 'ș',
+        "dotlessj" => 'ȷ',
+        "circumflex" => 'ˆ',
+        "caron" => 'ˇ',
+        "breve" => '˘',
+        "dotaccent" => '˙',
+        "ring" => '˚',
+        "ogonek" => '˛',
+        "tilde" => '˜',
+        "hungarumlaut" => '˝',
+        "Alpha" => 'Α',
+        "Beta" => 'Β',
+        "Gamma" => 'Γ',
+        "Delta" => 'Δ',
+        "Epsilon" => 'Ε',
+        "Zeta" => 'Ζ',
+        "Eta" => 'Η',
+        "Theta" => 'Θ',
+        "Iota" => 'Ι',
+        "Kappa" => 'Κ',
+        "Lambda" => 'Λ',
+        "Mu" => 'Μ',
+        "Nu" => 'Ν',
+        "Xi" => 'Ξ',
+        "Omicron" => 'Ο',
+        "Pi" => 'Π',
+        "Rho" => 'Ρ',
+        "Sigma" => 'Σ',
+        "Tau" => 'Τ',
+        "Upsilon" => 'Υ',
+        "Phi" => 'Φ',
+        "Chi" => 'Χ',
+        "Psi" => 'Ψ',
+        "Omega" => 'Ω',
+        "alpha" => 'α',
+        "beta" => 'β',
+        "gamma" => 'γ',
+        "delta" => 'δ',
+        "epsilon" => 'ε',
+        "zeta" => 'ζ',
+        "eta" => 'η',
+        "theta" => 'θ',
+        "iota" => 'ι',
+        "kappa" => 'κ',
+        "lambda" => 'λ',
+        "nu" => 'ν',
+        "xi" => 'ξ',
+        "omicron" => 'ο',
+        "pi" => 'π',
+        "rho" => 'ρ',
+        "sigma1" => 'ς',
+        "sigma" => 'σ',
+        "tau" => 'τ',
+        "upsilon" => 'υ',
+        "phi" => 'φ',
+        "chi" => 'χ',
+        "psi" => 'ψ',
+        "omega" => 'ω',
+        "theta1" => 'ϑ',
+        "phi1" => 'ϕ',
+        "omega1" => 'ϖ',
+        "figuredash" => '‒',
+        "endash" => '–',
+        "emdash" => '—',
+        "quoteleft" => '‘',
+        "quoteright" => '’',
+        "quotesinglbase" => '‚',
+        "quotereversed" => '‛',
+        "quotedblleft" => '“',
+        "quotedblright" => '”',
+        "quotedblbase" => '„',
+        "dagger" => '†',
+        "daggerdbl" => '‡',
+        "bullet" => '•',
+        "onedotenleader" => '․',
+        "twodotenleader" => '‥',
+        "ellipsis" => '…',
+        "perthousand" => '‰',
+        "minute" => '′',
+        "second" => '″',
+        "guilsinglleft" => '‹',
+        "guilsinglright" => '›',
+        "exclamdbl" => '‼',
+        "fraction" => '⁄',
+        "Euro" => '€',
+        "trademark" => '™',
+        "Ohm" => 'Ω',
+        "estimated" => '℮',
+        "onethird" => '⅓',
+        "twothirds" => '⅔',
+        "oneeighth" => '⅛',
+        "threeeighths" => '⅜',
+        "fiveeighths" => '⅝',
+        "seveneighths" => '⅞',
+        "arrowleft" => '←',
+        "arrowup" => '↑',
+        "arrowright" => '→',
+        "arrowdown" => '↓',
+        "arrowboth" => '↔',
+        "arrowupdn" => '↕',
+        "arrowdblleft" => '⇐',
+        "arrowdblup" => '⇑',
+        "arrowdblright" => '⇒',
+        "arrowdbldown" => '⇓',
+        "arrowdblboth" => '⇔',
+        "universal" => '∀',
+        "partialdiff" => '∂',
+        "existential" => '∃',
+        "emptyset" => '∅',
+        "gradient" => '∇',
+        "element" => '∈',
+        "notelement" => '∉',
+        "suchthat" => '∋',
+        "product" => '∏',
+        "summation" => '∑',
+        "minus" => '−',
+        "asteriskmath" => '∗',
+        "radical" => '√',
+        "proportional" => '∝',
+        "infinity" => '∞',
+        "angle" => '∠',
+        "logicaland" => '∧',
+        "logicalor" => '∨',
+        "intersection" => '∩',
+        "union" => '∪',
+        "integral" => '∫',
+        "therefore" => '∴',
+        "similar" => '∼',
+        "congruent" => '≅',
+        "approxequal" => '≈',
+        "notequal" => '≠',
+        "equivalence" => '≡',
+        "lessequal" => '≤',
+        "greaterequal" => '≥',
+        "propersubset" => '⊂',
+        "propersuperset" => '⊃',
+        "reflexsubset" => '⊆',
+        "reflexsuperset" => '⊇',
+        "circleplus" => '⊕',
+        "circlemultiply" => '⊗',
+        "perpendicular" => '⊥',
+        "dotmath" => '⋅',
+        "angleleft" => '〈',
+        "angleright" => '〉',
+        "lozenge" => '◊',
+        "filledbox" => '■',
+        "H22073" => '□',
+        "triagup" => '▲',
+        "triagrt" => '►',
+        "triagdn" => '▼',
+        "triaglf" => '◄',
+        "circle" => '○',
+        "H18533" => '●',
+        "openbullet" => '◦',
+        "spade" => '♠',
+        "club" => '♣',
+        "heart" => '♥',
+        "diamond" => '♦',
+        "ff" => 'ﬀ',
+        "fi" => 'ﬁ',
+        "fl" => 'ﬂ',
+        "ffi" => 'ﬃ',
+        "ffl" => 'ﬄ',
+        _ => return None,
+    };
+    Some(c)
+}
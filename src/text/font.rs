@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+
+use lopdf::{Dictionary, Document, Object};
+
+use super::cmap::CMap;
+use super::encoding::{glyph_name_text, BaseEncoding};
+use crate::graphics::{content, deref, number};
+
+/// What the text extractor needs to know about a font: how a string splits into
+/// character codes, what each code reads as and how far it advances.
+#[derive(Debug)]
+pub(super) struct Font {
+    /// `/BaseFont` without a subset prefix such as `ABCDEF+`.
+    pub(super) name: String,
+    /// Top and bottom of the glyphs above the baseline, for a font size of 1.
+    pub(super) ascent: f64,
+    pub(super) descent: f64,
+    /// `/Encoding` of a composite font; a simple font has one byte per code.
+    cmap: Option<CMap>,
+    to_unicode: Option<CMap>,
+    /// Text of each code of a simple font by its encoding and `/Differences`.
+    simple_text: Vec<Option<String>>,
+    first_char: u32,
+    widths: Vec<f64>,
+    cid_widths: HashMap<u32, f64>,
+    default_width: f64,
+    /// Glyph space to text space: 1/1000, or the font matrix of a Type 3 font.
+    scale: f64,
+}
+
+impl Font {
+    pub(super) fn load(doc: &Document, dict: &Dictionary) -> Font {
+        let base_font = dict.get(b"BaseFont").and_then(Object::as_name_str).unwrap_or_default();
+        let name = match base_font.split_once('+') {
+            Some((tag, name)) if tag.len() == 6 && tag.bytes().all(|b| b.is_ascii_uppercase()) => name,
+            _ => base_font,
+        };
+        let subtype = dict.get(b"Subtype").and_then(Object::as_name).unwrap_or_default();
+        let mut font = Font {
+            name: name.to_string(),
+            ascent: 0.8,
+            descent: -0.2,
+            cmap: None,
+            to_unicode: deref(doc, dict.get(b"ToUnicode").ok()).and_then(|t| t.as_stream().ok()).map(|t| CMap::parse(&content(t))),
+            simple_text: Vec::new(),
+            first_char: 0,
+            widths: Vec::new(),
+            cid_widths: HashMap::new(),
+            default_width: if name.starts_with("Courier") { 600.0 } else { 500.0 },
+            scale: 0.001,
+        };
+
+        if subtype == b"Type0" {
+            font.cmap = Some(match deref(doc, dict.get(b"Encoding").ok()) {
+                Some(Object::Name(name)) => CMap::predefined(&String::from_utf8_lossy(name)),
+                Some(Object::Stream(stream)) => CMap::parse(&content(stream)),
+                _ => CMap::predefined("Identity-H"),
+            });
+            let descendant = deref(doc, dict.get(b"DescendantFonts").ok())
+                .and_then(|d| d.as_array().ok())
+                .and_then(|a| deref(doc, a.first()))
+                .and_then(|d| d.as_dict().ok());
+            if let Some(descendant) = descendant {
+                font.read_descriptor(doc, descendant);
+                font.default_width = deref(doc, descendant.get(b"DW").ok()).and_then(number).unwrap_or(1000.0);
+                if let Some(w) = deref(doc, descendant.get(b"W").ok()).and_then(|w| w.as_array().ok()) {
+                    font.cid_widths = cid_widths(doc, w);
+                }
+            }
+            return font;
+        }
+
+        font.simple_text = simple_text(doc, dict, subtype);
+        font.first_char = dict.get(b"FirstChar").ok().and_then(number).unwrap_or(0.0) as u32;
+        if let Some(widths) = deref(doc, dict.get(b"Widths").ok()).and_then(|w| w.as_array().ok()) {
+            font.widths = widths.iter().map(|w| deref(doc, Some(w)).and_then(number).unwrap_or(0.0)).collect();
+        }
+        font.read_descriptor(doc, dict);
+        if subtype == b"Type3" {
+            let matrix = deref(doc, dict.get(b"FontMatrix").ok()).and_then(|m| m.as_array().ok());
+            if let Some(a) = matrix.and_then(|m| m.first()).and_then(number) {
+                font.scale = a;
+            }
+        }
+        font
+    }
+
+    /// Ascent, descent and missing width from the `/FontDescriptor` of `dict`.
+    fn read_descriptor(&mut self, doc: &Document, dict: &Dictionary) {
+        let Some(descriptor) = deref(doc, dict.get(b"FontDescriptor").ok()).and_then(|d| d.as_dict().ok()) else { return };
+        let value = |key: &[u8]| deref(doc, descriptor.get(key).ok()).and_then(number);
+        // some producers write zeros; keep the defaults then
+        if let (Some(ascent), Some(descent)) = (value(b"Ascent"), value(b"Descent")) {
+            if ascent > descent && ascent > 0.0 {
+                self.ascent = ascent / 1000.0;
+                self.descent = descent.min(0.0) / 1000.0;
+            }
+        }
+        if let Some(missing) = value(b"MissingWidth").filter(|w| *w > 0.0) {
+            self.default_width = missing;
+        }
+    }
+
+    /// Character codes of a shown string, with their length in bytes.
+    pub(super) fn codes(&self, bytes: &[u8]) -> Vec<(u32, usize)> {
+        match &self.cmap {
+            Some(cmap) => cmap.codes(bytes),
+            None => bytes.iter().map(|b| (*b as u32, 1)).collect(),
+        }
+    }
+
+    /// Whether word spacing applies to a code, which it does for a single-byte space only.
+    pub(super) fn is_word_space(&self, code: u32, len: usize) -> bool {
+        code == 32 && len == 1
+    }
+
+    /// Text a character code stands for: from `/ToUnicode` if it maps the code, otherwise
+    /// from the encoding. Empty if neither knows the code.
+    pub(super) fn text(&self, code: u32) -> String {
+        let mapped = self.to_unicode.as_ref().and_then(|t| t.text(code)).filter(|t| !t.is_empty() && t != "\0");
+        let text = mapped.or_else(|| match &self.cmap {
+            Some(cmap) => cmap.text(code),
+            None => self.simple_text.get(code as usize).cloned().flatten(),
+        });
+        text.map(expand_ligatures).unwrap_or_default()
+    }
+
+    /// Advance of `code` in text space, for a font size of 1.
+    pub(super) fn width(&self, code: u32) -> f64 {
+        let width = if let Some(cmap) = &self.cmap {
+            match cmap.cid(code) {
+                Some(cid) => self.cid_widths.get(&cid).copied(),
+                None => cmap.is_half_width(code).then_some(self.default_width / 2.0),
+            }
+        } else {
+            code.checked_sub(self.first_char).and_then(|i| self.widths.get(i as usize).copied())
+        };
+        width.unwrap_or(self.default_width) * self.scale
+    }
+}
+
+/// Text of the 256 codes of a simple font: its base encoding (Standard unless named,
+/// WinAnsi for TrueType fonts) with `/Differences` applied, read through the glyph names.
+/// Names the glyph list does not know keep the base encoding's character.
+fn simple_text(doc: &Document, dict: &Dictionary, subtype: &[u8]) -> Vec<Option<String>> {
+    let default = if subtype == b"TrueType" { BaseEncoding::WinAnsi } else { BaseEncoding::Standard };
+    let (base, differences) = match deref(doc, dict.get(b"Encoding").ok()) {
+        Some(Object::Name(name)) => (BaseEncoding::from_name(name), None),
+        Some(Object::Dictionary(encoding)) => (
+            encoding.get(b"BaseEncoding").and_then(Object::as_name).ok().and_then(BaseEncoding::from_name),
+            deref(doc, encoding.get(b"Differences").ok()).and_then(|d| d.as_array().ok()),
+        ),
+        _ => (None, None),
+    };
+    let base = base.unwrap_or(default);
+    let mut text: Vec<Option<String>> = (0..=255u8).map(|code| base.char(code).map(String::from)).collect();
+    let mut code = 0usize;
+    for item in differences.into_iter().flatten() {
+        match item {
+            Object::Integer(n) => code = *n as usize,
+            Object::Name(name) => {
+                if let (Some(slot), Some(glyph)) = (text.get_mut(code), glyph_name_text(&String::from_utf8_lossy(name))) {
+                    *slot = Some(glyph);
+                }
+                code += 1;
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Spell out the Latin ligatures (`ﬁ` as `fi`) so the text can be searched.
+fn expand_ligatures(text: String) -> String {
+    if !text.chars().any(|c| ('\u{FB00}'..='\u{FB06}').contains(&c)) {
+        return text;
+    }
+    text.chars()
+        .map(|c| match c {
+            '\u{FB00}' => "ff".to_string(),
+            '\u{FB01}' => "fi".to_string(),
+            '\u{FB02}' => "fl".to_string(),
+            '\u{FB03}' => "ffi".to_string(),
+            '\u{FB04}' => "ffl".to_string(),
+            '\u{FB05}' | '\u{FB06}' => "st".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// Widths of a CID font's `/W` array: `c [w1 w2 ...]` gives consecutive codes from `c`,
+/// `c1 c2 w` gives the same width to a range.
+fn cid_widths(doc: &Document, w: &[Object]) -> HashMap<u32, f64> {
+    let mut widths = HashMap::new();
+    let mut i = 0;
+    while i + 1 < w.len() {
+        let Some(first) = number(&w[i]) else { break };
+        match deref(doc, Some(&w[i + 1])) {
+            Some(Object::Array(list)) => {
+                for (code, width) in (first as u32..=u32::MAX).zip(list) {
+                    if let Some(width) = number(width) {
+                        widths.insert(code, width);
+                    }
+                }
+                i += 2;
+            }
+            Some(last) => {
+                let (Some(last), Some(width)) = (number(last), w.get(i + 2).and_then(number)) else { break };
+                // keep a corrupt range from filling memory
+                for code in first as u32..=(last as u32).min((first as u32).saturating_add(0xFFFF)) {
+                    widths.insert(code, width);
+                }
+                i += 3;
+            }
+            None => break,
+        }
+    }
+    widths
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::{dictionary, Document, Object};
+
+    use super::*;
+
+    #[test]
+    fn differences_override_the_base_encoding() {
+        let names = ["A", "uni20AC", "u1F600", "f_f_i", "a.sc", "nosuchglyph"];
+        let mut differences = vec![Object::Integer(0x41)];
+        differences.extend(names.iter().map(|n| Object::Name(n.as_bytes().to_vec())));
+        let font = Font::load(
+            &Document::new(),
+            &dictionary! {
+                "Subtype" => "Type1",
+                "Encoding" => dictionary! { "BaseEncoding" => "WinAnsiEncoding", "Differences" => differences },
+            },
+        );
+        let texts: Vec<String> = (0x41..0x48).map(|code| font.text(code)).collect();
+        assert_eq!(texts, ["A", "€", "😀", "ffi", "a", "F", "G"]);
+        assert_eq!(font.text(0x80), "€");
+    }
+
+    #[test]
+    fn legacy_cjk_encodings_decode_without_to_unicode() {
+        let font = Font::load(
+            &Document::new(),
+            &dictionary! { "Subtype" => "Type0", "Encoding" => "90ms-RKSJ-H", "DescendantFonts" => vec![dictionary! {}.into()] },
+        );
+        let codes = font.codes(b"A\x82\xa0\xb1");
+        let texts: Vec<String> = codes.iter().map(|(code, _)| font.text(*code)).collect();
+        assert_eq!(texts, ["A", "あ", "ｱ"]);
+        let widths: Vec<f64> = codes.iter().map(|(code, _)| font.width(*code)).collect();
+        assert_eq!(widths, [0.5, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn cid_widths_read_lists_and_ranges() {
+        let w = [
+            Object::Integer(1),
+            Object::Array(vec![Object::Integer(500), Object::Real(250.5)]),
+            Object::Integer(10),
+            Object::Integer(12),
+            Object::Integer(1000),
+        ];
+        let widths = cid_widths(&Document::new(), &w);
+        assert_eq!(widths.len(), 5);
+        assert_eq!(widths[&2], 250.5);
+        assert_eq!(widths[&12], 1000.0);
+    }
+
+    #[test]
+    fn cid_widths_stop_at_the_largest_code() {
+        let max = Object::Integer(u32::MAX as i64);
+        let list = Object::Array(vec![Object::Integer(1); 3]);
+        let widths = cid_widths(&Document::new(), &[max.clone(), list, max.clone(), max, Object::Integer(2)]);
+        assert_eq!(widths.len(), 1);
+        assert_eq!(widths[&u32::MAX], 2.0);
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use lopdf::content::Operation;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+use super::font::Font;
+use crate::document::page_attribute;
+use crate::graphics::{self, deref, number, Matrix, Visitor};
+
+/// A character drawn on the page, in the page's displayed orientation with the
+/// origin at its lower left corner.
+#[derive(Debug, Clone)]
+pub(super) struct Glyph {
+    pub(super) text: String,
+    /// Start of the glyph on the baseline.
+    pub(super) x: f64,
+    pub(super) y: f64,
+    /// Extent along the baseline, never negative.
+    pub(super) width: f64,
+    /// Font size after scaling by the text and transformation matrices.
+    pub(super) size: f64,
+    /// Direction of the baseline in quarter turns counterclockwise, 0 for text read
+    /// left to right.
+    pub(super) rotation: u8,
+    pub(super) font: Rc<Font>,
+    /// Fill color as RGB.
+    pub(super) color: [u8; 3],
+}
+
+/// A horizontal or vertical line painted on the page, such as a table border, in the
+/// coordinates of [`Glyph`].
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Rule {
+    pub(super) horizontal: bool,
+    /// `y` of a horizontal rule, `x` of a vertical one.
+    pub(super) position: f64,
+    /// Extent along the rule, `start <= end`.
+    pub(super) start: f64,
+    pub(super) end: f64,
+}
+
+/// Filled rectangles thinner than this many points are drawn lines rather than areas.
+const RULE_THICKNESS: f64 = 3.0;
+/// Segments tilted by less than this many points over their length count as straight.
+const RULE_SLANT: f64 = 0.5;
+
+/// Visible area of a page: its crop box, or media box, as `[x0 y0 x1 y1]`.
+fn page_box(doc: &Document, page: ObjectId) -> [f64; 4] {
+    let rect = |key: &[u8]| -> Option<[f64; 4]> {
+        let values: Vec<f64> = page_attribute(doc, page, key)?.as_array().ok()?.iter().map(number).collect::<Option<_>>()?;
+        let [x0, y0, x1, y1] = values[..] else { return None };
+        Some([x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)])
+    };
+    rect(b"CropBox").or_else(|| rect(b"MediaBox")).unwrap_or([0.0, 0.0, 612.0, 792.0])
+}
+
+/// `/Rotate` of a page as 0, 90, 180 or 270.
+pub(super) fn page_rotation(doc: &Document, page: ObjectId) -> i64 {
+    let rotate = page_attribute(doc, page, b"Rotate").and_then(|r| r.as_i64().ok()).unwrap_or(0);
+    // other values are invalid; viewers ignore them
+    Some(rotate.rem_euclid(360)).filter(|r| r % 90 == 0).unwrap_or(0)
+}
+
+/// Width and height of a page as displayed, i.e. after `/Rotate`.
+pub(super) fn page_size(doc: &Document, page: ObjectId) -> (f64, f64) {
+    let [x0, y0, x1, y1] = page_box(doc, page);
+    match page_rotation(doc, page) {
+        90 | 270 => (y1 - y0, x1 - x0),
+        _ => (x1 - x0, y1 - y0),
+    }
+}
+
+/// Maps user space to the page as displayed: `/Rotate` applied and the lower left
+/// corner of the crop box at the origin.
+fn page_matrix(doc: &Document, page: ObjectId) -> Matrix {
+    let [x0, y0, x1, y1] = page_box(doc, page);
+    match page_rotation(doc, page) {
+        90 => Matrix { a: 0.0, b: -1.0, c: 1.0, d: 0.0, e: -y0, f: x1 },
+        180 => Matrix { a: -1.0, b: 0.0, c: 0.0, d: -1.0, e: x1, f: y1 },
+        270 => Matrix { a: 0.0, b: 1.0, c: -1.0, d: 0.0, e: y1, f: -x0 },
+        _ => Matrix::translate(-x0, -y0),
+    }
+}
+
+/// The characters drawn by the content streams of `page`, including those of the form
+/// XObjects it paints, in drawing order, and the horizontal and vertical lines painted
+/// by stroking paths or filling thin rectangles.
+pub(super) fn page_contents(doc: &Document, page: ObjectId) -> lopdf::Result<(Vec<Glyph>, Vec<Rule>)> {
+    let mut interpreter = Interpreter {
+        doc,
+        fonts: HashMap::new(),
+        glyphs: Vec::new(),
+        rules: Vec::new(),
+        tm: Matrix::IDENTITY,
+        tlm: Matrix::IDENTITY,
+        path: Vec::new(),
+    };
+    let state = GraphicsState {
+        font: None,
+        font_size: 0.0,
+        char_spacing: 0.0,
+        word_spacing: 0.0,
+        horizontal_scale: 1.0,
+        leading: 0.0,
+        rise: 0.0,
+        fill: [0, 0, 0],
+        stroke: [0, 0, 0],
+    };
+    graphics::walk_page(doc, page, page_matrix(doc, page), state, &mut interpreter)?;
+    Ok((interpreter.glyphs, interpreter.rules))
+}
+
+/// Graphics state parameters besides the CTM that matter for text and rules.
+#[derive(Clone)]
+struct GraphicsState {
+    font: Option<Rc<Font>>,
+    font_size: f64,
+    char_spacing: f64,
+    word_spacing: f64,
+    /// `Tz` divided by 100.
+    horizontal_scale: f64,
+    leading: f64,
+    rise: f64,
+    fill: [u8; 3],
+    stroke: [u8; 3],
+}
+
+/// RGB for color components of a gray, RGB or CMYK color space, told apart by their count.
+fn rgb(components: &[f64]) -> Option<[u8; 3]> {
+    let byte = |v: f64| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    match *components {
+        [g] => Some([byte(g); 3]),
+        [r, g, b] => Some([byte(r), byte(g), byte(b)]),
+        [c, m, y, k] => Some([byte((1.0 - c) * (1.0 - k)), byte((1.0 - m) * (1.0 - k)), byte((1.0 - y) * (1.0 - k))]),
+        _ => None,
+    }
+}
+
+struct Interpreter<'a> {
+    doc: &'a Document,
+    fonts: HashMap<ObjectId, Rc<Font>>,
+    glyphs: Vec<Glyph>,
+    rules: Vec<Rule>,
+    /// Text matrix and text line matrix.
+    tm: Matrix,
+    tlm: Matrix,
+    /// Subpaths of the path being built, in page coordinates, and whether each is closed.
+    path: Vec<(Vec<(f64, f64)>, bool)>,
+}
+
+impl<'a> Visitor<'a> for Interpreter<'a> {
+    type State = GraphicsState;
+
+    fn operation(&mut self, op: &Operation, state: &mut GraphicsState, ctm: &Matrix, resources: Option<&'a Dictionary>) {
+        let operands = &op.operands;
+        let num = |i: usize| operands.get(i).and_then(number).unwrap_or(0.0);
+        match op.operator.as_str() {
+            // a new color space starts out black, or close enough to it
+            "cs" => state.fill = [0, 0, 0],
+            "CS" => state.stroke = [0, 0, 0],
+            "g" | "rg" | "k" | "sc" | "scn" => {
+                let components: Vec<f64> = operands.iter().filter_map(number).collect();
+                if let Some(fill) = rgb(&components) {
+                    state.fill = fill;
+                }
+            }
+            "G" | "RG" | "K" | "SC" | "SCN" => {
+                let components: Vec<f64> = operands.iter().filter_map(number).collect();
+                if let Some(stroke) = rgb(&components) {
+                    state.stroke = stroke;
+                }
+            }
+            "m" => self.path.push((vec![ctm.apply(num(0), num(1))], false)),
+            "l" => {
+                if let Some((points, _)) = self.path.last_mut() {
+                    points.push(ctm.apply(num(0), num(1)));
+                }
+            }
+            // curves are not rules; keep the end point so the subpath goes on from there
+            "c" | "v" | "y" => {
+                let n = operands.len();
+                if let Some((points, _)) = self.path.last_mut().filter(|_| n >= 4) {
+                    points.push((f64::NAN, f64::NAN));
+                    points.push(ctm.apply(num(n - 2), num(n - 1)));
+                }
+            }
+            "h" => {
+                if let Some((_, closed)) = self.path.last_mut() {
+                    *closed = true;
+                }
+            }
+            "re" => {
+                let (x, y, w, h) = (num(0), num(1), num(2), num(3));
+                let corners = [(x, y), (x + w, y), (x + w, y + h), (x, y + h)];
+                self.path.push((corners.iter().map(|&(x, y)| ctm.apply(x, y)).collect(), true));
+            }
+            "S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" | "n" => {
+                let op = op.operator.as_str();
+                if matches!(op, "s" | "b" | "b*") {
+                    for (_, closed) in &mut self.path {
+                        *closed = true;
+                    }
+                }
+                // white on the usual white page is invisible, and often a page background
+                let stroked = matches!(op, "S" | "s" | "B" | "B*" | "b" | "b*") && state.stroke != [255; 3];
+                let filled = matches!(op, "f" | "F" | "f*" | "B" | "B*" | "b" | "b*") && state.fill != [255; 3];
+                for (points, closed) in std::mem::take(&mut self.path) {
+                    if stroked {
+                        self.stroke_rules(&points, closed);
+                    } else if filled {
+                        self.fill_rules(&points);
+                    }
+                }
+            }
+            "BT" => {
+                self.tm = Matrix::IDENTITY;
+                self.tlm = Matrix::IDENTITY;
+            }
+            "Tf" => {
+                state.font = operands.first().and_then(|n| n.as_name().ok()).and_then(|n| self.font(resources, n));
+                state.font_size = num(1);
+            }
+            "Tc" => state.char_spacing = num(0),
+            "Tw" => state.word_spacing = num(0),
+            "Tz" => state.horizontal_scale = num(0) / 100.0,
+            "TL" => state.leading = num(0),
+            "Ts" => state.rise = num(0),
+            "Td" | "TD" => {
+                if op.operator == "TD" {
+                    state.leading = -num(1);
+                }
+                self.tlm = Matrix::translate(num(0), num(1)).then(&self.tlm);
+                self.tm = self.tlm;
+            }
+            "Tm" => {
+                if let Some(m) = Matrix::from_operands(operands) {
+                    self.tlm = m;
+                    self.tm = self.tlm;
+                }
+            }
+            "T*" | "'" | "\"" => {
+                if op.operator == "\"" {
+                    state.word_spacing = num(0);
+                    state.char_spacing = num(1);
+                }
+                self.tlm = Matrix::translate(0.0, -state.leading).then(&self.tlm);
+                self.tm = self.tlm;
+                if let Some(Object::String(bytes, _)) = operands.last().filter(|_| op.operator != "T*") {
+                    self.show(state, ctm, bytes);
+                }
+            }
+            "Tj" => {
+                if let Some(Object::String(bytes, _)) = operands.first() {
+                    self.show(state, ctm, bytes);
+                }
+            }
+            "TJ" => {
+                for item in operands.first().and_then(|a| a.as_array().ok()).into_iter().flatten() {
+                    match item {
+                        Object::String(bytes, _) => self.show(state, ctm, bytes),
+                        other => {
+                            let adjust = number(other).unwrap_or(0.0);
+                            self.tm = Matrix::translate(-adjust / 1000.0 * state.font_size * state.horizontal_scale, 0.0).then(&self.tm);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Interpreter<'_> {
+    /// Record the straight horizontal and vertical segments of a stroked subpath.
+    fn stroke_rules(&mut self, points: &[(f64, f64)], closed: bool) {
+        let closing = points.first().filter(|_| closed && points.len() > 2);
+        let ends: Vec<(f64, f64)> = points.iter().chain(closing).copied().collect();
+        for pair in ends.windows(2) {
+            let [(x0, y0), (x1, y1)] = [pair[0], pair[1]];
+            if (y1 - y0).abs() <= RULE_SLANT && (x1 - x0).abs() > RULE_SLANT {
+                self.rules.push(Rule { horizontal: true, position: (y0 + y1) / 2.0, start: x0.min(x1), end: x0.max(x1) });
+            } else if (x1 - x0).abs() <= RULE_SLANT && (y1 - y0).abs() > RULE_SLANT {
+                self.rules.push(Rule { horizontal: false, position: (x0 + x1) / 2.0, start: y0.min(y1), end: y0.max(y1) });
+            }
+        }
+    }
+
+    /// Record a filled subpath that is an upright rectangle: a thin one as the line it
+    /// draws, a larger one, such as a shaded cell, as its four edges.
+    fn fill_rules(&mut self, points: &[(f64, f64)]) {
+        let mut corners = points.to_vec();
+        if corners.len() == 5 && corners[0] == corners[4] {
+            corners.pop();
+        }
+        let [(x0, y0), (x1, y1), (x2, y2), (x3, y3)] = corners[..] else { return };
+        let upright = |(ax, ay): (f64, f64), (bx, by): (f64, f64)| (ax - bx).abs() <= RULE_SLANT || (ay - by).abs() <= RULE_SLANT;
+        if ![(x0, y0), (x1, y1), (x2, y2), (x3, y3), (x0, y0)].windows(2).all(|p| upright(p[0], p[1])) {
+            return;
+        }
+        let (left, right) = (x0.min(x1).min(x2).min(x3), x0.max(x1).max(x2).max(x3));
+        let (bottom, top) = (y0.min(y1).min(y2).min(y3), y0.max(y1).max(y2).max(y3));
+        if top - bottom <= RULE_THICKNESS && right - left > top - bottom {
+            self.rules.push(Rule { horizontal: true, position: (top + bottom) / 2.0, start: left, end: right });
+        } else if right - left <= RULE_THICKNESS && top - bottom > right - left {
+            self.rules.push(Rule { horizontal: false, position: (left + right) / 2.0, start: bottom, end: top });
+        } else {
+            self.stroke_rules(&[(left, bottom), (right, bottom), (right, top), (left, top)], true);
+        }
+    }
+
+    /// Record the glyphs of a shown string and advance the text matrix past them.
+    fn show(&mut self, state: &GraphicsState, ctm: &Matrix, bytes: &[u8]) {
+        let Some(font) = &state.font else { return };
+        for (code, len) in font.codes(bytes) {
+            let mut advance = font.width(code) * state.font_size + state.char_spacing;
+            if font.is_word_space(code, len) {
+                advance += state.word_spacing;
+            }
+            advance *= state.horizontal_scale;
+
+            let device = self.tm.then(ctm);
+            let origin = Matrix::translate(0.0, state.rise).then(&device);
+            let text = font.text(code);
+            if !text.is_empty() {
+                // a negative advance, from negative spacing or scaling, moves leftwards, so
+                // the glyph then starts where its advance ends
+                let width = advance * device.a.hypot(device.b);
+                let start = if width < 0.0 { Matrix::translate(advance, state.rise).then(&device) } else { origin };
+                self.glyphs.push(Glyph {
+                    text,
+                    x: start.e,
+                    y: start.f,
+                    width: width.abs(),
+                    size: state.font_size * device.c.hypot(device.d),
+                    rotation: (device.b.atan2(device.a).to_degrees() / 90.0).round().rem_euclid(4.0) as u8,
+                    font: font.clone(),
+                    color: state.fill,
+                });
+            }
+            self.tm = Matrix::translate(advance, 0.0).then(&self.tm);
+        }
+    }
+
+    fn font(&mut self, resources: Option<&Dictionary>, name: &[u8]) -> Option<Rc<Font>> {
+        let fonts = deref(self.doc, resources?.get(b"Font").ok())?.as_dict().ok()?;
+        let entry = fonts.get(name).ok()?;
+        match entry.as_reference() {
+            Ok(id) => {
+                if let Some(font) = self.fonts.get(&id) {
+                    return Some(font.clone());
+                }
+                let font = Rc::new(Font::load(self.doc, self.doc.get_dictionary(id).ok()?));
+                self.fonts.insert(id, font.clone());
+                Some(font)
+            }
+            Err(_) => Some(Rc::new(Font::load(self.doc, entry.as_dict().ok()?))),
+        }
+    }
+}
@@ -0,0 +1,440 @@
+use std::rc::Rc;
+
+use super::font::Font;
+use super::interpret::Glyph;
+
+/// Glyphs whose baselines are closer than this many font sizes share a line.
+const LINE_TOLERANCE: f64 = 0.5;
+/// A gap wider than this many font sizes separates two words...
+const WORD_GAP: f64 = 0.15;
+/// ...and one wider than this separates two runs of words that may sit in different columns.
+const SEGMENT_GAP: f64 = 1.0;
+/// A vertical gap of more than this many font sizes ends a paragraph.
+pub(super) const PARAGRAPH_GAP: f64 = 2.0;
+/// Share of the lines that may cross a column gutter, such as headings over all columns.
+const GUTTER_CROSSINGS: usize = 4;
+/// Narrowest column, as a share of the width of the page's text.
+const MIN_COLUMN_WIDTH: f64 = 0.2;
+
+pub(super) struct Word {
+    pub(super) x0: f64,
+    pub(super) x1: f64,
+    pub(super) text: String,
+}
+
+pub(super) struct Line {
+    /// Baseline.
+    pub(super) y: f64,
+    pub(super) size: f64,
+    /// Left to right.
+    pub(super) words: Vec<Word>,
+}
+
+impl Line {
+    /// Extents of the runs of words separated by at most [`SEGMENT_GAP`].
+    pub(super) fn segments(&self) -> Vec<(f64, f64)> {
+        let mut segments: Vec<(f64, f64)> = Vec::new();
+        for word in &self.words {
+            match segments.last_mut() {
+                Some((_, x1)) if word.x0 - *x1 <= SEGMENT_GAP * self.size => *x1 = x1.max(word.x1),
+                _ => segments.push((word.x0, word.x1)),
+            }
+        }
+        segments
+    }
+
+    pub(super) fn text(&self) -> String {
+        self.words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Text of a page in reading order: lines top to bottom, and the lines of side by side
+/// columns one column after the other.
+pub(super) fn plain_text(glyphs: &[Glyph]) -> String {
+    let lines = lines(glyphs);
+    let gutters = gutters(&lines);
+    let mut text = String::new();
+    let mut previous: Option<&Line> = None;
+    let ordered = reading_order(lines, &gutters);
+    for line in &ordered {
+        if let Some(previous) = previous {
+            // a new paragraph, or the top of the next column
+            if previous.y - line.y > PARAGRAPH_GAP * line.size.max(previous.size) || line.y > previous.y {
+                text.push('\n');
+            }
+        }
+        text.push_str(&line.text());
+        text.push('\n');
+        previous = Some(line);
+    }
+    text
+}
+
+/// Text of a page laid out on a character grid, so that columns, indentation and vertical
+/// spacing come out roughly where they are on the page.
+pub(super) fn layout_text(glyphs: &[Glyph]) -> String {
+    let lines = lines(glyphs);
+    let Some(x_min) = lines.iter().flat_map(|l| &l.words).map(|w| w.x0).reduce(f64::min) else {
+        return String::new();
+    };
+    let cell = median(lines.iter().flat_map(|l| &l.words).map(|w| (w.x1 - w.x0) / w.text.chars().count() as f64).filter(|w| *w > 0.0));
+    let size = median(lines.iter().map(|l| l.size)).unwrap_or(10.0);
+    let cell = cell.unwrap_or(size / 2.0);
+    let line_height = median(lines.windows(2).map(|w| w[0].y - w[1].y).filter(|gap| *gap > 0.0)).unwrap_or(size).max(size / 2.0);
+
+    let mut text = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            let rows = ((lines[i - 1].y - line.y) / line_height).round() as usize;
+            text.extend(std::iter::repeat_n('\n', rows.saturating_sub(1)));
+        }
+        let mut row = String::new();
+        let mut column = 0;
+        for word in &line.words {
+            let mut target = ((word.x0 - x_min) / cell).round().max(0.0) as usize;
+            if column > 0 {
+                target = target.max(column + 1);
+            }
+            row.extend(std::iter::repeat_n(' ', target - column));
+            row.push_str(&word.text);
+            column = target + word.text.chars().count();
+        }
+        text.push_str(row.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// Group glyphs into lines, top to bottom, and the glyphs of each line into words.
+pub(super) fn lines(glyphs: &[Glyph]) -> Vec<Line> {
+    let (_, lines) = glyph_lines(glyphs);
+    lines
+        .into_iter()
+        .filter_map(|line| {
+            let y = median(line.iter().map(|g| g.y))?;
+            let size = median(line.iter().map(|g| g.size))?;
+            let words = words(&line);
+            (!words.is_empty()).then_some(Line { y, size, words })
+        })
+        .collect()
+}
+
+/// Glyphs grouped into lines, top to bottom, each sorted left to right without the
+/// duplicates painted for fake bold.
+///
+/// The page is read in the direction most of its text runs in, so text drawn sideways
+/// comes out in lines as well: coordinates are turned by the returned number of quarter
+/// turns (see [`turn`]).
+fn glyph_lines(glyphs: &[Glyph]) -> (u8, Vec<Vec<Glyph>>) {
+    let mut counts = [0usize; 4];
+    for glyph in glyphs {
+        counts[glyph.rotation as usize % 4] += 1;
+    }
+    let rotation = (0..4u8).max_by_key(|r| (counts[*r as usize], std::cmp::Reverse(*r))).unwrap_or(0);
+    let mut sorted: Vec<Glyph> = glyphs
+        .iter()
+        .filter(|g| g.size > 0.0 && g.x.is_finite() && g.y.is_finite())
+        .map(|g| {
+            let (x, y) = turn(g.x, g.y, rotation);
+            Glyph { x, y, ..g.clone() }
+        })
+        .collect();
+    sorted.sort_by(|a, b| b.y.total_cmp(&a.y));
+
+    let mut lines: Vec<Vec<Glyph>> = Vec::new();
+    for glyph in sorted {
+        match lines.last_mut() {
+            Some(line) if line[0].y - glyph.y <= LINE_TOLERANCE * glyph.size.max(line[0].size) => line.push(glyph),
+            _ => lines.push(vec![glyph]),
+        }
+    }
+    for line in &mut lines {
+        line.sort_by(|a, b| a.x.total_cmp(&b.x));
+        // fake bold: the same glyph painted again slightly offset
+        line.dedup_by(|g, previous| previous.text == g.text && g.x - previous.x < (previous.width / 2.0).max(0.01 * g.size));
+    }
+    (rotation, lines)
+}
+
+/// Turn a point by `quarters` quarter turns clockwise; four turns get back to the start.
+fn turn(x: f64, y: f64, quarters: u8) -> (f64, f64) {
+    match quarters % 4 {
+        1 => (y, -x),
+        2 => (-x, -y),
+        3 => (-y, x),
+        _ => (x, y),
+    }
+}
+
+/// Split the glyphs of a line, left to right, at spaces and at gaps wider than [`WORD_GAP`].
+fn words(glyphs: &[Glyph]) -> Vec<Word> {
+    let mut words: Vec<Word> = Vec::new();
+    let mut previous: Option<&Glyph> = None;
+    let mut after_space = true;
+    for glyph in glyphs {
+        if glyph.text.trim().is_empty() {
+            after_space = true;
+            previous = Some(glyph);
+            continue;
+        }
+        let gap = previous.map_or(0.0, |p| glyph.x - (p.x + p.width));
+        match words.last_mut() {
+            Some(word) if !after_space && gap <= WORD_GAP * glyph.size.max(previous.map_or(0.0, |p| p.size)) => {
+                word.text.push_str(&glyph.text);
+                word.x1 = word.x1.max(glyph.x + glyph.width);
+            }
+            _ => words.push(Word { x0: glyph.x, x1: glyph.x + glyph.width, text: glyph.text.clone() }),
+        }
+        after_space = false;
+        previous = Some(glyph);
+    }
+    words
+}
+
+/// Text drawn with one font, size and color, left to right on one line.
+pub(super) struct Run {
+    pub(super) text: String,
+    /// `[x0 y0 x1 y1]` in the coordinates of [`Glyph`], from the font's descent to its ascent.
+    pub(super) bbox: [f64; 4],
+    pub(super) font: Rc<Font>,
+    pub(super) size: f64,
+    pub(super) color: [u8; 3],
+}
+
+/// The text of a page as runs, line by line from the top. A run ends where the font,
+/// size or color changes or at a gap wider than [`SEGMENT_GAP`].
+pub(super) fn runs(glyphs: &[Glyph]) -> Vec<Run> {
+    let (rotation, lines) = glyph_lines(glyphs);
+    let mut runs = Vec::new();
+    for line in lines {
+        // the run being built, with its extent in turned coordinates
+        let mut current: Option<(Run, [f64; 4])> = None;
+        let mut previous: Option<&Glyph> = None;
+        let mut after_space = false;
+        for glyph in &line {
+            if glyph.text.trim().is_empty() {
+                after_space = true;
+                previous = Some(glyph);
+                continue;
+            }
+            let gap = previous.map_or(0.0, |p| glyph.x - (p.x + p.width));
+            let extent = [
+                glyph.x,
+                glyph.y + glyph.font.descent * glyph.size,
+                glyph.x + glyph.width,
+                glyph.y + glyph.font.ascent * glyph.size,
+            ];
+            match &mut current {
+                Some((run, area))
+                    if Rc::ptr_eq(&run.font, &glyph.font)
+                        && (run.size - glyph.size).abs() < 0.01
+                        && run.color == glyph.color
+                        && gap <= SEGMENT_GAP * glyph.size =>
+                {
+                    if after_space || gap > WORD_GAP * glyph.size {
+                        run.text.push(' ');
+                    }
+                    run.text.push_str(&glyph.text);
+                    *area = [area[0].min(extent[0]), area[1].min(extent[1]), area[2].max(extent[2]), area[3].max(extent[3])];
+                }
+                _ => {
+                    runs.extend(current.take().map(|(run, area)| finish_run(run, area, rotation)));
+                    let run = Run { text: glyph.text.clone(), bbox: [0.0; 4], font: glyph.font.clone(), size: glyph.size, color: glyph.color };
+                    current = Some((run, extent));
+                }
+            }
+            after_space = false;
+            previous = Some(glyph);
+        }
+        runs.extend(current.map(|(run, area)| finish_run(run, area, rotation)));
+    }
+    runs
+}
+
+/// Set the bounding box of `run` from its extent in coordinates turned by `rotation`.
+fn finish_run(mut run: Run, [x0, y0, x1, y1]: [f64; 4], rotation: u8) -> Run {
+    let (ax, ay) = turn(x0, y0, 4 - rotation);
+    let (bx, by) = turn(x1, y1, 4 - rotation);
+    run.bbox = [ax.min(bx), ay.min(by), ax.max(bx), ay.max(by)];
+    run
+}
+
+/// Horizontal positions of the gaps between text columns: vertical strips at least an
+/// em wide that almost no line crosses, with text on both sides of them.
+fn gutters(lines: &[Line]) -> Vec<f64> {
+    let segments: Vec<Vec<(f64, f64)>> = lines.iter().map(Line::segments).collect();
+    let x_min = segments.iter().flatten().map(|s| s.0).fold(f64::INFINITY, f64::min);
+    let x_max = segments.iter().flatten().map(|s| s.1).fold(f64::NEG_INFINITY, f64::max);
+    let width = x_max - x_min;
+    // one bin per point; nonsense coordinates do not get a histogram
+    if !(1.0..100_000.0).contains(&width) {
+        return Vec::new();
+    }
+    let bins = width.ceil() as usize;
+    let mut coverage = vec![0usize; bins];
+    let bin = |x: f64| ((x - x_min).floor() as usize).min(bins);
+    for &(x0, x1) in segments.iter().flatten() {
+        for count in coverage.iter_mut().take(bin(x1)).skip(bin(x0)) {
+            *count += 1;
+        }
+    }
+
+    let em = median(lines.iter().map(|l| l.size)).unwrap_or(10.0);
+    let limit = lines.len() / GUTTER_CROSSINGS;
+    let mut candidates = Vec::new();
+    let mut start = None;
+    for (i, count) in coverage.iter().enumerate() {
+        if *count <= limit {
+            start.get_or_insert(i);
+        } else if let Some(s) = start.take() {
+            // strips at the left or right edge are margins, not gutters
+            if s > 0 && (i - s) as f64 >= em {
+                candidates.push(x_min + (s + i) as f64 / 2.0);
+            }
+        }
+    }
+
+    let mut gutters: Vec<f64> = Vec::new();
+    for x in candidates {
+        let left_edge = gutters.last().copied().unwrap_or(x_min);
+        if x - left_edge < MIN_COLUMN_WIDTH * width || x_max - x < MIN_COLUMN_WIDTH * width {
+            continue;
+        }
+        let (mut left, mut right, mut both, mut crossing) = (0, 0, 0, 0);
+        for line in &segments {
+            let l = line.iter().any(|s| s.1 <= x && s.0 >= left_edge);
+            let r = line.iter().any(|s| s.0 >= x);
+            left += l as usize;
+            right += r as usize;
+            both += (l && r) as usize;
+            crossing += line.iter().any(|s| s.0 < x && x < s.1) as usize;
+        }
+        if left >= 3 && right >= 3 && both >= 2 && both > crossing {
+            gutters.push(x);
+        }
+    }
+    gutters
+}
+
+/// Order lines for reading: runs of lines that do not cross a gutter are read column
+/// by column, and a line crossing one (a heading over all columns) ends the run.
+fn reading_order(lines: Vec<Line>, gutters: &[f64]) -> Vec<Line> {
+    if gutters.is_empty() {
+        return lines;
+    }
+    let mut ordered = Vec::new();
+    let mut region: Vec<Line> = Vec::new();
+    for line in lines {
+        let crosses = line.segments().iter().any(|(x0, x1)| gutters.iter().any(|g| x0 < g && g < x1));
+        if crosses {
+            flush_columns(&mut region, gutters, &mut ordered);
+            ordered.push(line);
+        } else {
+            region.push(line);
+        }
+    }
+    flush_columns(&mut region, gutters, &mut ordered);
+    ordered
+}
+
+fn flush_columns(region: &mut Vec<Line>, gutters: &[f64], ordered: &mut Vec<Line>) {
+    let mut columns: Vec<Vec<Line>> = (0..=gutters.len()).map(|_| Vec::new()).collect();
+    for line in region.drain(..) {
+        let mut parts: Vec<Vec<Word>> = (0..=gutters.len()).map(|_| Vec::new()).collect();
+        for word in line.words {
+            parts[gutters.iter().filter(|g| **g < word.x0).count()].push(word);
+        }
+        for (column, words) in columns.iter_mut().zip(parts) {
+            if !words.is_empty() {
+                column.push(Line { y: line.y, size: line.size, words });
+            }
+        }
+    }
+    ordered.extend(columns.into_iter().flatten());
+}
+
+fn median(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let mut values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    Some(values[values.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::{Dictionary, Document};
+
+    use super::*;
+
+    /// Glyphs for `text` from `x` on the baseline `y`, each 6 points wide at size 10.
+    fn glyphs(text: &str, x: f64, y: f64) -> Vec<Glyph> {
+        let font = Rc::new(Font::load(&Document::new(), &Dictionary::new()));
+        text.chars()
+            .enumerate()
+            .map(|(i, c)| Glyph {
+                text: c.to_string(),
+                x: x + 6.0 * i as f64,
+                y,
+                width: 6.0,
+                size: 10.0,
+                rotation: 0,
+                font: font.clone(),
+                color: [0; 3],
+            })
+            .collect()
+    }
+
+    fn line(words: &[(f64, f64)]) -> Line {
+        Line { y: 0.0, size: 10.0, words: words.iter().map(|&(x0, x1)| Word { x0, x1, text: "w".into() }).collect() }
+    }
+
+    #[test]
+    fn words_split_at_spaces_and_gaps() {
+        let mut line = glyphs("ab cd", 0.0, 0.0);
+        line.extend(glyphs("ef", 40.0, 0.0));
+        let words = words(&line);
+        let texts: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(texts, ["ab", "cd", "ef"]);
+        assert_eq!((words[1].x0, words[1].x1), (18.0, 30.0));
+    }
+
+    #[test]
+    fn lines_run_top_to_bottom_and_merge_close_baselines() {
+        let mut page = glyphs("low", 0.0, 100.0);
+        page.extend(glyphs("top", 0.0, 200.0));
+        page.extend(glyphs("er", 18.0, 201.0));
+        let lines = lines(&page);
+        let texts: Vec<String> = lines.iter().map(Line::text).collect();
+        assert_eq!(texts, ["toper", "low"]);
+    }
+
+    #[test]
+    fn gutters_separate_columns_but_not_margins() {
+        let lines: Vec<Line> = (0..6).map(|_| line(&[(0.0, 100.0), (150.0, 250.0)])).collect();
+        let gutters = gutters(&lines);
+        assert_eq!(gutters.len(), 1);
+        assert!(100.0 < gutters[0] && gutters[0] < 150.0);
+
+        let single: Vec<Line> = (0..6).map(|_| line(&[(20.0, 250.0)])).collect();
+        assert!(super::gutters(&single).is_empty());
+    }
+
+    #[test]
+    fn gutters_ignore_inverted_segments() {
+        let mut lines: Vec<Line> = (0..6).map(|_| line(&[(0.0, 100.0), (150.0, 250.0)])).collect();
+        lines.push(line(&[(240.0, 10.0)]));
+        assert_eq!(gutters(&lines).len(), 1);
+    }
+
+    #[test]
+    fn plain_text_reads_columns_one_after_the_other() {
+        let mut page = Vec::new();
+        for (i, y) in [700.0, 688.0, 676.0, 664.0].into_iter().enumerate() {
+            page.extend(glyphs(&format!("left{}", i), 0.0, y));
+            page.extend(glyphs(&format!("right{}", i), 200.0, y));
+        }
+        assert_eq!(plain_text(&page), "left0\nleft1\nleft2\nleft3\n\nright0\nright1\nright2\nright3\n");
+    }
+}
@@ -0,0 +1,328 @@
+use super::interpret::{Glyph, Rule};
+use super::layout::{self, Line, PARAGRAPH_GAP};
+
+/// Rules whose ends come closer than this many points meet, and rule positions this
+/// close are the same grid line.
+const JOIN_TOLERANCE: f64 = 2.0;
+/// Pages with more rules than this are drawings; their rules are not searched for grids.
+const MAX_RULES: usize = 5000;
+/// Fewest rows and columns of a table found from the alignment of its text alone...
+const MIN_ALIGNED_ROWS: usize = 3;
+const MIN_ALIGNED_COLUMNS: usize = 3;
+/// ...and most words per cell on average; longer cells are columns of prose.
+const MAX_CELL_WORDS: f64 = 5.0;
+
+/// A table found on a page: rows top to bottom, each with one cell per column.
+pub(super) struct Table {
+    pub(super) rows: Vec<Vec<String>>,
+}
+
+/// The tables of a page, top to bottom: grids of ruled lines, with the text inside
+/// each cell, and then blocks of lines whose text lines up in columns.
+///
+/// Only text read left to right is considered.
+pub(super) fn tables(glyphs: &[Glyph], rules: &[Rule]) -> Vec<Table> {
+    let upright: Vec<&Glyph> = glyphs.iter().filter(|g| g.rotation == 0 && g.size > 0.0 && g.x.is_finite() && g.y.is_finite()).collect();
+    let mut used = vec![false; upright.len()];
+    let mut found = if rules.len() <= MAX_RULES { ruled_tables(&upright, rules, &mut used) } else { Vec::new() };
+    let rest: Vec<Glyph> = upright.iter().zip(&used).filter(|(_, used)| !**used).map(|(g, _)| (*g).clone()).collect();
+    found.extend(aligned_tables(&layout::lines(&rest)));
+    found.sort_by(|a, b| b.0.total_cmp(&a.0));
+    found.into_iter().map(|(_, table)| table).collect()
+}
+
+fn touches(a: &Rule, b: &Rule) -> bool {
+    let within = |v: f64, start: f64, end: f64| start - JOIN_TOLERANCE <= v && v <= end + JOIN_TOLERANCE;
+    if a.horizontal == b.horizontal {
+        (a.position - b.position).abs() <= JOIN_TOLERANCE && a.start <= b.end + JOIN_TOLERANCE && b.start <= a.end + JOIN_TOLERANCE
+    } else {
+        within(a.position, b.start, b.end) && within(b.position, a.start, a.end)
+    }
+}
+
+/// Distinct values, ascending, merging those within [`JOIN_TOLERANCE`] of the previous one.
+fn grid_lines(mut values: Vec<f64>) -> Vec<f64> {
+    values.sort_by(f64::total_cmp);
+    let mut lines: Vec<f64> = Vec::new();
+    let mut last = f64::NEG_INFINITY;
+    for v in values {
+        if v - last > JOIN_TOLERANCE {
+            lines.push(v);
+        }
+        last = v;
+    }
+    lines
+}
+
+/// Tables drawn as grids of rules, with the top of each. Glyphs placed in a table are
+/// marked in `used`.
+fn ruled_tables(glyphs: &[&Glyph], rules: &[Rule], used: &mut [bool]) -> Vec<(f64, Table)> {
+    // rules that meet, directly or through others, make up one grid
+    let mut parent: Vec<usize> = (0..rules.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for i in 0..rules.len() {
+        for j in i + 1..rules.len() {
+            if touches(&rules[i], &rules[j]) {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[a] = b;
+            }
+        }
+    }
+    let mut groups: Vec<Vec<&Rule>> = Vec::new();
+    let mut group_of = vec![usize::MAX; rules.len()];
+    for (i, rule) in rules.iter().enumerate() {
+        let r = root(&mut parent, i);
+        if group_of[r] == usize::MAX {
+            group_of[r] = groups.len();
+            groups.push(Vec::new());
+        }
+        groups[group_of[r]].push(rule);
+    }
+
+    let mut tables = Vec::new();
+    for group in groups {
+        let xs = grid_lines(group.iter().filter(|r| !r.horizontal).map(|r| r.position).collect());
+        let mut ys = grid_lines(group.iter().filter(|r| r.horizontal).map(|r| r.position).collect());
+        ys.reverse();
+        if xs.len() < 2 || ys.len() < 2 {
+            continue;
+        }
+        let mut cells: Vec<Vec<Vec<usize>>> = vec![vec![Vec::new(); xs.len() - 1]; ys.len() - 1];
+        for (i, glyph) in glyphs.iter().enumerate() {
+            if used[i] {
+                continue;
+            }
+            // the middle of the glyph decides its cell
+            let x = glyph.x + glyph.width / 2.0;
+            let y = glyph.y + glyph.size * 0.3;
+            let row = ys.windows(2).position(|w| y <= w[0] && y > w[1]);
+            let column = xs.windows(2).position(|w| x >= w[0] && x < w[1]);
+            if let (Some(row), Some(column)) = (row, column) {
+                cells[row][column].push(i);
+            }
+        }
+        let rows: Vec<Vec<String>> = cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| {
+                        let cell: Vec<Glyph> = cell.iter().map(|&i| glyphs[i].clone()).collect();
+                        layout::lines(&cell).iter().map(Line::text).collect::<Vec<_>>().join(" ")
+                    })
+                    .collect()
+            })
+            .collect();
+        // cells merged across several grid cells leave rows and columns without text
+        let Some(table) = prune(rows) else { continue };
+        for i in cells.iter().flatten().flatten() {
+            used[*i] = true;
+        }
+        tables.push((ys[0], table));
+    }
+    tables
+}
+
+/// `rows` without the rows and columns that have no text, if at least two of each remain.
+fn prune(rows: Vec<Vec<String>>) -> Option<Table> {
+    let columns = rows.first()?.len();
+    let keep: Vec<bool> = (0..columns).map(|c| rows.iter().any(|row| !row[c].is_empty())).collect();
+    let rows: Vec<Vec<String>> = rows
+        .into_iter()
+        .filter(|row| row.iter().any(|cell| !cell.is_empty()))
+        .map(|row| row.into_iter().zip(&keep).filter(|(_, keep)| **keep).map(|(cell, _)| cell).collect())
+        .collect();
+    (rows.len() >= 2 && keep.iter().filter(|k| **k).count() >= 2).then_some(Table { rows })
+}
+
+/// Tables without rules, with the top of each: runs of lines split into several
+/// segments (see [`Line::segments`]) whose segments line up in columns.
+fn aligned_tables(lines: &[Line]) -> Vec<(f64, Table)> {
+    let mut tables = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].segments().len() < 2 {
+            i += 1;
+            continue;
+        }
+        // a wrapped cell puts a single line between two rows; two in a row end the table
+        let mut end = i + 1;
+        let mut j = i + 1;
+        while j < lines.len() && j - end < 2 && lines[j - 1].y - lines[j].y <= PARAGRAPH_GAP * lines[j].size.max(lines[j - 1].size) {
+            if lines[j].segments().len() >= 2 {
+                end = j + 1;
+            }
+            j += 1;
+        }
+        if let Some(table) = aligned_table(&lines[i..end]) {
+            tables.push((lines[i].y + lines[i].size, table));
+        }
+        i = end;
+    }
+    tables
+}
+
+fn aligned_table(lines: &[Line]) -> Option<Table> {
+    let rows = lines.iter().filter(|l| l.segments().len() >= 2).count();
+    if rows < MIN_ALIGNED_ROWS {
+        return None;
+    }
+    // a column is where the segments of the rows overlap
+    let mut segments: Vec<(f64, f64)> = lines.iter().filter(|l| l.segments().len() >= 2).flat_map(Line::segments).collect();
+    segments.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut columns: Vec<(f64, f64)> = Vec::new();
+    for (x0, x1) in segments {
+        match columns.last_mut() {
+            Some(column) if x0 <= column.1 => column.1 = column.1.max(x1),
+            _ => columns.push((x0, x1)),
+        }
+    }
+    if columns.len() < MIN_ALIGNED_COLUMNS {
+        return None;
+    }
+
+    let mut table: Vec<Vec<String>> = Vec::new();
+    let mut words = 0;
+    for line in lines {
+        let mut row = vec![String::new(); columns.len()];
+        for word in &line.words {
+            let x = (word.x0 + word.x1) / 2.0;
+            let distance = |c: &(f64, f64)| if x < c.0 { c.0 - x } else { (x - c.1).max(0.0) };
+            let column = (0..columns.len()).min_by(|a, b| distance(&columns[*a]).total_cmp(&distance(&columns[*b]))).unwrap_or(0);
+            append(&mut row[column], &word.text);
+            words += 1;
+        }
+        match table.last_mut() {
+            // the continuation of a wrapped cell
+            Some(previous) if line.segments().len() < 2 => {
+                for (cell, text) in previous.iter_mut().zip(row) {
+                    append(cell, &text);
+                }
+            }
+            _ => table.push(row),
+        }
+    }
+    let filled = table.iter().flatten().filter(|cell| !cell.is_empty()).count();
+    (words as f64 <= MAX_CELL_WORDS * filled as f64).then_some(Table { rows: table })
+}
+
+/// Add `text` to `cell`, separated by a space.
+fn append(cell: &mut String, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    if !cell.is_empty() {
+        cell.push(' ');
+    }
+    cell.push_str(text);
+}
+
+/// `table` as CSV (RFC 4180): fields with commas, quotes or line breaks quoted, and
+/// rows ended by CRLF.
+pub(super) fn csv(table: &Table) -> String {
+    let mut csv = String::new();
+    for row in &table.rows {
+        let fields: Vec<String> = row
+            .iter()
+            .map(|cell| {
+                if cell.contains([',', '"', '\n', '\r']) {
+                    format!("\"{}\"", cell.replace('"', "\"\""))
+                } else {
+                    cell.clone()
+                }
+            })
+            .collect();
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use lopdf::{Dictionary, Document};
+
+    use super::super::font::Font;
+    use super::*;
+
+    /// Glyphs for `text` from `x` on the baseline `y`, each 6 points wide at size 10.
+    fn text(page: &mut Vec<Glyph>, text: &str, x: f64, y: f64) {
+        let font = Rc::new(Font::load(&Document::new(), &Dictionary::new()));
+        page.extend(text.chars().enumerate().map(|(i, c)| Glyph {
+            text: c.to_string(),
+            x: x + 6.0 * i as f64,
+            y,
+            width: 6.0,
+            size: 10.0,
+            rotation: 0,
+            font: font.clone(),
+            color: [0; 3],
+        }));
+    }
+
+    fn rule(horizontal: bool, position: f64, start: f64, end: f64) -> Rule {
+        Rule { horizontal, position, start, end }
+    }
+
+    fn rows(tables: &[Table]) -> Vec<Vec<Vec<&str>>> {
+        tables.iter().map(|t| t.rows.iter().map(|r| r.iter().map(String::as_str).collect()).collect()).collect()
+    }
+
+    #[test]
+    fn csv_quotes_fields_as_rfc_4180_asks() {
+        let table = Table { rows: vec![vec!["plain".into(), "a,b".into(), "say \"hi\"".into(), "two\nlines".into(), String::new()]] };
+        assert_eq!(csv(&table), "plain,\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",\r\n");
+    }
+
+    #[test]
+    fn ruled_grids_give_one_cell_per_box() {
+        let mut page = Vec::new();
+        text(&mut page, "Title", 100.0, 720.0);
+        text(&mut page, "Name", 105.0, 685.0);
+        text(&mut page, "Qty", 205.0, 685.0);
+        text(&mut page, "Apple", 105.0, 665.0);
+        text(&mut page, "3", 205.0, 665.0);
+        // the grid's rules overlap a little where they meet, as drawn lines do
+        let mut rules: Vec<Rule> = [700.0, 680.0, 660.0].iter().map(|y| rule(true, *y, 99.0, 301.0)).collect();
+        rules.extend([100.0, 200.0, 300.0].iter().map(|x| rule(false, *x, 659.0, 701.0)));
+
+        assert_eq!(rows(&tables(&page, &rules)), [[["Name", "Qty"], ["Apple", "3"]]]);
+    }
+
+    #[test]
+    fn separate_grids_are_separate_tables() {
+        let mut page = Vec::new();
+        let mut rules = Vec::new();
+        for top in [700.0, 500.0] {
+            text(&mut page, "a", 105.0, top - 15.0);
+            text(&mut page, "b", 205.0, top - 15.0);
+            text(&mut page, "c", 105.0, top - 35.0);
+            text(&mut page, "d", 205.0, top - 35.0);
+            rules.extend([top, top - 20.0, top - 40.0].iter().map(|y| rule(true, *y, 100.0, 300.0)));
+            rules.extend([100.0, 200.0, 300.0].iter().map(|x| rule(false, *x, top - 40.0, top)));
+        }
+        assert_eq!(tables(&page, &rules).len(), 2);
+    }
+
+    #[test]
+    fn aligned_text_is_a_table_and_prose_is_not() {
+        let mut page = Vec::new();
+        for (i, row) in [["Fruit", "Qty", "Price"], ["Apple", "3", "1.20"], ["Pear", "12", "0.80"]].iter().enumerate() {
+            let y = 700.0 - 14.0 * i as f64;
+            for (column, cell) in row.iter().enumerate() {
+                text(&mut page, cell, 100.0 + 120.0 * column as f64, y);
+            }
+        }
+        for i in 0..4 {
+            text(&mut page, "just a sentence of running text", 100.0, 500.0 - 14.0 * i as f64);
+        }
+        assert_eq!(rows(&tables(&page, &[])), [[["Fruit", "Qty", "Price"], ["Apple", "3", "1.20"], ["Pear", "12", "0.80"]]]);
+    }
+}
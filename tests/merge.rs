@@ -0,0 +1,283 @@
+use std::path::PathBuf;
+
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, ObjectId, Stream};
+use pdf_tools::{MergeOptions, Progress};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("pdf-tools-merge-{}-{}", std::process::id(), name))
+}
+
+fn text_content(doc: &mut Document, text: &str) -> ObjectId {
+    let content = Content {
+        operations: vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 12.into()]),
+            Operation::new("Td", vec![20.into(), 20.into()]),
+            Operation::new("Tj", vec![Object::string_literal(text)]),
+            Operation::new("ET", vec![]),
+        ],
+    };
+    doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()))
+}
+
+/// A document whose page objects are numbered in reverse reading order, with the
+/// pages below an intermediate `Pages` node that holds MediaBox, Resources and Rotate.
+fn write_scrambled(name: &str, texts: &[&str]) -> PathBuf {
+    let mut doc = Document::with_version("1.5");
+    let root_id = doc.new_object_id();
+    let node_id = doc.new_object_id();
+    let mut page_ids: Vec<ObjectId> = texts.iter().map(|_| doc.new_object_id()).collect();
+    page_ids.reverse();
+
+    let font_id = doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Courier" });
+    for (text, id) in texts.iter().zip(&page_ids) {
+        let content_id = text_content(&mut doc, text);
+        doc.objects.insert(*id, Object::Dictionary(dictionary! { "Type" => "Page", "Parent" => node_id, "Contents" => content_id }));
+    }
+    let kids: Vec<Object> = page_ids.iter().map(|id| Object::Reference(*id)).collect();
+    doc.objects.insert(
+        node_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Parent" => root_id,
+            "Kids" => kids,
+            "Count" => texts.len() as u32,
+            "MediaBox" => vec![0.into(), 0.into(), 200.into(), 300.into()],
+            "Rotate" => 90,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+        }),
+    );
+    doc.objects.insert(
+        root_id,
+        Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![node_id.into()], "Count" => texts.len() as u32 }),
+    );
+    let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => root_id });
+    doc.trailer.set("Root", catalog_id);
+
+    let path = temp_path(name);
+    doc.save(&path).unwrap();
+    path
+}
+
+fn page_texts(path: &PathBuf) -> Vec<String> {
+    let doc = Document::load(path).unwrap();
+    doc.get_pages().keys().map(|n| doc.extract_text(&[*n]).unwrap().trim().to_string()).collect()
+}
+
+#[test]
+fn merge_follows_page_tree_order() {
+    let a = write_scrambled("order-a.pdf", &["a1", "a2", "a3"]);
+    let b = write_scrambled("order-b.pdf", &["b1", "b2"]);
+    let out = temp_path("order-out.pdf");
+
+    pdf_tools::merge(&[a.clone(), b.clone()], &out, &MergeOptions::default(), &Progress::new()).unwrap();
+
+    assert_eq!(page_texts(&out), ["a1", "a2", "a3", "b1", "b2"]);
+    for p in [a, b, out] {
+        std::fs::remove_file(p).unwrap();
+    }
+}
+
+#[test]
+fn merge_keeps_inherited_page_attributes() {
+    let a = write_scrambled("inherit-a.pdf", &["a1", "a2"]);
+    let out = temp_path("inherit-out.pdf");
+
+    pdf_tools::merge(&[a.clone(), a.clone()], &out, &MergeOptions::default(), &Progress::new()).unwrap();
+
+    let doc = Document::load(&out).unwrap();
+    let pages = doc.get_pages();
+    assert_eq!(pages.len(), 4);
+    for id in pages.values() {
+        let page = doc.get_dictionary(*id).unwrap();
+        let media_box: Vec<i64> = page.get(b"MediaBox").unwrap().as_array().unwrap().iter().map(|o| o.as_i64().unwrap()).collect();
+        assert_eq!(media_box, [0, 0, 200, 300]);
+        assert_eq!(page.get(b"Rotate").unwrap().as_i64().unwrap(), 90);
+        assert!(doc.get_page_fonts(*id).contains_key(b"F1".as_slice()));
+    }
+    for p in [a, out] {
+        std::fs::remove_file(p).unwrap();
+    }
+}
+
+#[test]
+fn merge_takes_page_sequences_per_input() {
+    let a = write_scrambled("segments-a.pdf", &["a1", "a2", "a3", "a4"]);
+    let b = write_scrambled("segments-b.pdf", &["b1", "b2"]);
+    let out = temp_path("segments-out.pdf");
+    let options = MergeOptions {
+        pages: vec![Some(vec![1]), None, Some(pdf_tools::parse_page_sequence("4-2").unwrap())],
+        ..Default::default()
+    };
+
+    pdf_tools::merge(&[a.clone(), b.clone(), a.clone()], &out, &options, &Progress::new()).unwrap();
+
+    assert_eq!(page_texts(&out), ["a1", "b1", "b2", "a4", "a3", "a2"]);
+    for p in [a, b, out] {
+        std::fs::remove_file(p).unwrap();
+    }
+}
+
+/// Give the catalog of `path` entries that point at its second page.
+fn add_catalog_links(path: &PathBuf) {
+    let mut doc = Document::load(path).unwrap();
+    let second = doc.get_pages()[&2];
+    let dests = doc.add_object(dictionary! { "Names" => vec![Object::string_literal("intro"), vec![second.into(), "Fit".into()].into()] });
+    let tree = doc.add_object(dictionary! { "Type" => "StructTreeRoot", "K" => dictionary! { "S" => "P", "Pg" => second } });
+    let root = doc.trailer.get(b"Root").unwrap().as_reference().unwrap();
+    let catalog = doc.get_object_mut(root).unwrap().as_dict_mut().unwrap();
+    catalog.set("OpenAction", dictionary! { "S" => "GoTo", "D" => vec![second.into(), "Fit".into()] });
+    catalog.set("StructTreeRoot", tree);
+    catalog.set("MarkInfo", dictionary! { "Marked" => true });
+    catalog.set("PageLabels", dictionary! { "Nums" => vec![0.into(), dictionary! { "S" => "r" }.into()] });
+    catalog.set("Names", dictionary! { "Dests" => dests, "EmbeddedFiles" => dictionary! { "Names" => vec![] } });
+    doc.save(path).unwrap();
+}
+
+#[test]
+fn merge_drops_catalog_entries_for_pages_left_out() {
+    let a = write_scrambled("catalog-a.pdf", &["a1", "a2"]);
+    add_catalog_links(&a);
+    let out = temp_path("catalog-out.pdf");
+    let options = MergeOptions { pages: vec![Some(vec![1])], ..Default::default() };
+
+    pdf_tools::merge(std::slice::from_ref(&a), &out, &options, &Progress::new()).unwrap();
+
+    let doc = Document::load(&out).unwrap();
+    let catalog = doc.catalog().unwrap();
+    for key in [b"OpenAction".as_slice(), b"StructTreeRoot", b"MarkInfo", b"PageLabels"] {
+        assert!(!catalog.has(key), "{}", String::from_utf8_lossy(key));
+    }
+    let names = catalog.get(b"Names").unwrap().as_dict().unwrap();
+    assert!(!names.has(b"Dests"));
+    assert!(names.has(b"EmbeddedFiles"));
+
+    // an open action going to a merged page stays
+    pdf_tools::merge(std::slice::from_ref(&a), &out, &MergeOptions::default(), &Progress::new()).unwrap();
+    let doc = Document::load(&out).unwrap();
+    let action = doc.catalog().unwrap().get(b"OpenAction").unwrap().as_dict().unwrap();
+    let page = action.get(b"D").unwrap().as_array().unwrap()[0].as_reference().unwrap();
+    assert_eq!(doc.get_pages()[&2], page);
+    for p in [a, out] {
+        std::fs::remove_file(p).unwrap();
+    }
+}
+
+/// Give `path` a form with a text field named `name` on its first page, default
+/// resources holding `fonts` (resource name and base font), and the given flags.
+fn add_form(path: &PathBuf, name: &str, fonts: &[(&str, &str)], need_appearances: bool, sig_flags: i64) {
+    let mut doc = Document::load(path).unwrap();
+    let first = doc.get_pages()[&1];
+    let field = doc.add_object(dictionary! {
+        "FT" => "Tx",
+        "T" => Object::string_literal(name),
+        "Type" => "Annot",
+        "Subtype" => "Widget",
+        "Rect" => vec![10.into(), 10.into(), 100.into(), 30.into()],
+        "P" => first,
+    });
+    doc.get_object_mut(first).unwrap().as_dict_mut().unwrap().set("Annots", vec![field.into()]);
+    let mut font_entries = lopdf::Dictionary::new();
+    for (resource, base_font) in fonts {
+        let font = doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => *base_font });
+        font_entries.set(*resource, font);
+    }
+    let form = dictionary! {
+        "Fields" => vec![field.into()],
+        "DR" => dictionary! { "Font" => font_entries },
+        "NeedAppearances" => need_appearances,
+        "SigFlags" => sig_flags,
+        "DA" => Object::string_literal("/Helv 0 Tf 0 g"),
+    };
+    let root = doc.trailer.get(b"Root").unwrap().as_reference().unwrap();
+    doc.get_object_mut(root).unwrap().as_dict_mut().unwrap().set("AcroForm", form);
+    doc.save(path).unwrap();
+}
+
+#[test]
+fn merge_combines_forms_and_renames_clashing_fields() {
+    let a = write_scrambled("form-a.pdf", &["a1"]);
+    add_form(&a, "name", &[("Helv", "Helvetica")], false, 1);
+    let b = write_scrambled("form-b.pdf", &["b1"]);
+    add_form(&b, "name", &[("Helv", "Courier"), ("ZaDb", "ZapfDingbats")], true, 2);
+    let out = temp_path("form-out.pdf");
+
+    pdf_tools::merge(&[a.clone(), b.clone(), a.clone()], &out, &MergeOptions::default(), &Progress::new()).unwrap();
+
+    let doc = Document::load(&out).unwrap();
+    let form = doc.catalog().unwrap().get(b"AcroForm").unwrap().as_dict().unwrap();
+    let names: Vec<String> = form
+        .get(b"Fields")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| {
+            let field = doc.get_dictionary(f.as_reference().unwrap()).unwrap();
+            String::from_utf8(field.get(b"T").unwrap().as_str().unwrap().to_vec()).unwrap()
+        })
+        .collect();
+    assert_eq!(names, ["name", "name_2", "name_3"]);
+
+    // default resources are combined, the first input winning a clash
+    let fonts = form.get(b"DR").unwrap().as_dict().unwrap().get(b"Font").unwrap().as_dict().unwrap();
+    let base_font = |name: &[u8]| {
+        let font = doc.get_dictionary(fonts.get(name).unwrap().as_reference().unwrap()).unwrap();
+        font.get(b"BaseFont").unwrap().as_name_str().unwrap().to_string()
+    };
+    assert_eq!(base_font(b"Helv"), "Helvetica");
+    assert_eq!(base_font(b"ZaDb"), "ZapfDingbats");
+    assert!(form.get(b"NeedAppearances").unwrap().as_bool().unwrap());
+    assert_eq!(form.get(b"SigFlags").unwrap().as_i64().unwrap(), 3);
+    for p in [a, b, out] {
+        std::fs::remove_file(p).unwrap();
+    }
+}
+
+#[test]
+fn merge_gives_a_page_listed_twice_its_own_annotations() {
+    let path = write_scrambled("twice.pdf", &["one", "two"]);
+    add_form(&path, "name", &[("Helv", "Helvetica")], false, 0);
+    let mut doc = Document::load(&path).unwrap();
+    let first = doc.get_pages()[&1];
+    let link = doc.add_object(dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Link",
+        "Rect" => vec![0.into(), 0.into(), 50.into(), 20.into()],
+        "A" => dictionary! { "S" => "URI", "URI" => Object::string_literal("https://example.com") },
+        "P" => first,
+    });
+    let page = doc.get_object_mut(first).unwrap().as_dict_mut().unwrap();
+    page.get_mut(b"Annots").unwrap().as_array_mut().unwrap().push(link.into());
+    doc.save(&path).unwrap();
+    let out = temp_path("twice-out.pdf");
+
+    let options = MergeOptions { pages: vec![Some(vec![1, 1])], ..Default::default() };
+    pdf_tools::merge(std::slice::from_ref(&path), &out, &options, &Progress::new()).unwrap();
+
+    let doc = Document::load(&out).unwrap();
+    let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    assert_eq!(pages.len(), 2);
+    let annots: Vec<Vec<ObjectId>> = pages
+        .iter()
+        .map(|page| {
+            let annots = doc.get_dictionary(*page).unwrap().get(b"Annots").unwrap().as_array().unwrap();
+            annots.iter().map(|a| a.as_reference().unwrap()).collect()
+        })
+        .collect();
+    // the copy keeps the link but not the form field's widget
+    assert_eq!(annots[0].len(), 2);
+    assert_eq!(annots[1].len(), 1);
+    assert!(!annots[0].contains(&annots[1][0]));
+    for (page, annots) in pages.iter().zip(&annots) {
+        for annot in annots {
+            assert_eq!(doc.get_dictionary(*annot).unwrap().get(b"P").unwrap().as_reference().unwrap(), *page);
+        }
+    }
+    let copy = doc.get_dictionary(annots[1][0]).unwrap();
+    assert_eq!(copy.get(b"Subtype").unwrap().as_name_str().unwrap(), "Link");
+    for p in [path, out] {
+        std::fs::remove_file(p).unwrap();
+    }
+}
@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+
+use lopdf::{dictionary, Document, Object, ObjectId, Stream};
+use pdf_tools::{CompressOutcome, Progress};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("pdf-tools-optimize-{}-{}", std::process::id(), name))
+}
+
+/// A three page document that embeds its font, its image and the content of the first
+/// two pages once per page, uncompressed, and gives every page the same link. The first
+/// two pages are identical but for their number. Besides the link, which has `/Type`,
+/// every page has a square annotation without it and a pop-up that lacks `/Rect` too.
+fn write_duplicated(name: &str) -> PathBuf {
+    let mut doc = Document::with_version("1.4");
+    let pages_id = doc.new_object_id();
+    let mut page_ids: Vec<ObjectId> = Vec::new();
+    for text in ["same", "same", "other"] {
+        let font_id = doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica" });
+        let pixels: Vec<u8> = (0..64 * 64 * 3).map(|i| (i % 251) as u8).collect();
+        let image = dictionary! { "Type" => "XObject", "Subtype" => "Image", "Width" => 64, "Height" => 64, "ColorSpace" => "DeviceRGB", "BitsPerComponent" => 8 };
+        let image_id = doc.add_object(Stream::new(image, pixels));
+        let content = format!("q 50 0 0 50 10 10 cm /Im1 Do Q BT /F1 12 Tf 20 250 Td ({}) Tj ET", text);
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.into_bytes()));
+        let link = doc.add_object(dictionary! { "Type" => "Annot", "Subtype" => "Link", "Rect" => vec![0.into(), 0.into(), 10.into(), 10.into()] });
+        let square = doc.add_object(dictionary! { "Subtype" => "Square", "Rect" => vec![20.into(), 20.into(), 40.into(), 40.into()] });
+        let popup = doc.add_object(dictionary! { "Subtype" => "Popup", "Open" => false });
+        page_ids.push(doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 200.into(), 300.into()],
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id }, "XObject" => dictionary! { "Im1" => image_id } },
+            "Contents" => content_id,
+            "Annots" => vec![link.into(), square.into(), popup.into()],
+        }));
+    }
+    let kids: Vec<Object> = page_ids.iter().map(|id| Object::Reference(*id)).collect();
+    doc.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => 3 }));
+    let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", catalog_id);
+
+    let path = temp_path(name);
+    doc.save(&path).unwrap();
+    path
+}
+
+#[test]
+fn optimize_merges_duplicates_but_keeps_pages_and_annotations_apart() {
+    let input = write_duplicated("dup.pdf");
+    let output = temp_path("dup-out.pdf");
+    let report = pdf_tools::optimize(&input, &output, &Progress::new()).unwrap();
+    assert_eq!(report.outcome, CompressOutcome::Structure);
+    assert!(report.output_size < report.input_size / 2, "{} bytes from {}", report.output_size, report.input_size);
+    assert_eq!(std::fs::metadata(&output).unwrap().len(), report.output_size);
+
+    let doc = Document::load_mem(&std::fs::read(&output).unwrap()).unwrap();
+    let pages = doc.get_pages();
+    assert_eq!(pages.len(), 3);
+    let page_ids: Vec<&ObjectId> = pages.values().collect();
+    assert!(page_ids[0] != page_ids[1] && page_ids[1] != page_ids[2]);
+    // one font, one image and two contents are left, next to the catalog, the page
+    // tree and the three pages and their annotations, which are identical but stay apart
+    let kept: Vec<&Object> = doc
+        .objects
+        .values()
+        .filter(|o| {
+            let kind = o.as_dict().or_else(|_| o.as_stream().map(|s| &s.dict)).ok().and_then(|d| d.get(b"Type").ok()).and_then(|t| t.as_name().ok());
+            !matches!(kind, Some(b"ObjStm" | b"XRef"))
+        })
+        .collect();
+    assert_eq!(kept.len(), 18);
+
+    for i in 0..3 {
+        let annots: Vec<ObjectId> = pages
+            .values()
+            .map(|page| doc.get_dictionary(*page).unwrap().get(b"Annots").unwrap().as_array().unwrap()[i].as_reference().unwrap())
+            .collect();
+        assert!(annots[0] != annots[1] && annots[1] != annots[2] && annots[0] != annots[2]);
+    }
+    let font = |page: &ObjectId| {
+        let resources = doc.get_dictionary(*page).unwrap().get(b"Resources").unwrap().as_dict().unwrap();
+        resources.get(b"Font").unwrap().as_dict().unwrap().get(b"F1").unwrap().as_reference().unwrap()
+    };
+    let fonts: Vec<ObjectId> = pages.values().map(font).collect();
+    assert!(fonts.iter().all(|f| *f == fonts[0]));
+
+    let text = temp_path("dup-out.txt");
+    pdf_tools::extract_text(&output, &text, &Default::default(), &Progress::new()).unwrap();
+    assert_eq!(std::fs::read_to_string(&text).unwrap(), "same\n\x0Csame\n\x0Cother\n");
+    for path in [input, output, text] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,208 @@
+use std::path::PathBuf;
+
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, ObjectId, Stream};
+use pdf_tools::{Progress, SplitMode, SplitOptions, SplitPart};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("pdf-tools-split-{}-{}", std::process::id(), name))
+}
+
+/// Bytes that do not shrink when the output is compressed.
+fn noise(len: usize, seed: u64) -> Vec<u8> {
+    let mut x = seed | 1;
+    (0..len)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x as u8
+        })
+        .collect()
+}
+
+/// A document with one page per entry of `sizes`, each drawing a form XObject of that
+/// many bytes of its own; pages of size 0 draw only text and inherit their resources.
+/// The pages sit below an intermediate `Pages` node holding MediaBox, Rotate and
+/// Resources, and the second page links to the first one and has a widget of a form
+/// field whose other widget is on the third page.
+fn write_document(name: &str, sizes: &[usize]) -> PathBuf {
+    let mut doc = Document::with_version("1.5");
+    let root_id = doc.new_object_id();
+    let node_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Courier" });
+
+    let mut page_ids: Vec<ObjectId> = Vec::new();
+    for (i, size) in sizes.iter().enumerate() {
+        let mut operations = vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 12.into()]),
+            Operation::new("Td", vec![20.into(), 20.into()]),
+            Operation::new("Tj", vec![Object::string_literal(format!("page {}", i + 1))]),
+            Operation::new("ET", vec![]),
+        ];
+        let mut page = dictionary! { "Type" => "Page", "Parent" => node_id };
+        if *size > 0 {
+            let bbox = vec![0.into(), 0.into(), 1.into(), 1.into()];
+            let form = Stream::new(dictionary! { "Type" => "XObject", "Subtype" => "Form", "BBox" => bbox }, noise(*size, i as u64 + 1));
+            let form_id = doc.add_object(form.with_compression(false));
+            page.set("Resources", dictionary! { "Font" => dictionary! { "F1" => font_id }, "XObject" => dictionary! { "X1" => form_id } });
+            operations.push(Operation::new("Do", vec!["X1".into()]));
+        }
+        let content_id = doc.add_object(Stream::new(dictionary! {}, Content { operations }.encode().unwrap()));
+        page.set("Contents", content_id);
+        page_ids.push(doc.add_object(page));
+    }
+
+    let field_id = doc.new_object_id();
+    let rect = || vec![0.into(), 0.into(), 10.into(), 10.into()];
+    let link = doc.add_object(dictionary! { "Type" => "Annot", "Subtype" => "Link", "Rect" => rect(), "P" => page_ids[1], "Dest" => vec![page_ids[0].into(), "Fit".into()] });
+    let widget_a = doc.add_object(dictionary! { "Type" => "Annot", "Subtype" => "Widget", "Rect" => rect(), "P" => page_ids[1], "Parent" => field_id });
+    let widget_b = doc.add_object(dictionary! { "Type" => "Annot", "Subtype" => "Widget", "Rect" => rect(), "P" => page_ids[2], "Parent" => field_id });
+    doc.objects.insert(field_id, Object::Dictionary(dictionary! { "FT" => "Tx", "T" => Object::string_literal("name"), "Kids" => vec![widget_a.into(), widget_b.into()] }));
+    for (page, annots) in [(page_ids[1], vec![link.into(), widget_a.into()]), (page_ids[2], vec![widget_b.into()])] {
+        doc.get_object_mut(page).unwrap().as_dict_mut().unwrap().set("Annots", annots);
+    }
+
+    let kids: Vec<Object> = page_ids.iter().map(|id| Object::Reference(*id)).collect();
+    doc.objects.insert(
+        node_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Parent" => root_id,
+            "Kids" => kids,
+            "Count" => sizes.len() as u32,
+            "MediaBox" => vec![0.into(), 0.into(), 200.into(), 300.into()],
+            "Rotate" => 90,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+        }),
+    );
+    doc.objects.insert(
+        root_id,
+        Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![node_id.into()], "Count" => sizes.len() as u32 }),
+    );
+    let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => root_id, "AcroForm" => dictionary! { "Fields" => vec![field_id.into()] } });
+    doc.trailer.set("Root", catalog_id);
+
+    let path = temp_path(name);
+    doc.save(&path).unwrap();
+    path
+}
+
+fn split_every_page(name: &str, sizes: &[usize]) -> (PathBuf, PathBuf, Vec<SplitPart>) {
+    let input = write_document(&format!("{}.pdf", name), sizes);
+    let out_dir = temp_path(&format!("{}-out", name));
+    let parts = pdf_tools::split(&input, &out_dir, &SplitOptions::default(), &Progress::new()).unwrap();
+    (input, out_dir, parts)
+}
+
+#[test]
+fn split_output_size_follows_page_content() {
+    let sizes = [200_000, 1_000, 50_000, 2_000];
+    let (input, out_dir, parts) = split_every_page("size", &sizes);
+
+    assert_eq!(parts.len(), sizes.len());
+    for (part, size) in parts.iter().zip(sizes) {
+        let written = std::fs::metadata(&part.path).unwrap().len();
+        assert_eq!(written, part.size);
+        // the page's own content plus a little structure, nothing of the other pages
+        assert!(written as usize > size, "{} is {} bytes for {} bytes of content", part.path.display(), written, size);
+        assert!((written as usize) < size + 4_000, "{} is {} bytes for {} bytes of content", part.path.display(), written, size);
+    }
+    std::fs::remove_file(input).unwrap();
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
+
+#[test]
+fn split_pages_keep_inherited_attributes() {
+    let (input, out_dir, parts) = split_every_page("inherit", &[0, 0, 0]);
+
+    for part in &parts {
+        let doc = Document::load(&part.path).unwrap();
+        let pages = doc.get_pages();
+        assert_eq!(pages.len(), 1);
+        let id = pages[&1];
+        let page = doc.get_dictionary(id).unwrap();
+        let media_box: Vec<i64> = page.get(b"MediaBox").unwrap().as_array().unwrap().iter().map(|o| o.as_i64().unwrap()).collect();
+        assert_eq!(media_box, [0, 0, 200, 300]);
+        assert_eq!(page.get(b"Rotate").unwrap().as_i64().unwrap(), 90);
+        assert!(doc.get_page_fonts(id).contains_key(b"F1".as_slice()));
+    }
+    std::fs::remove_file(input).unwrap();
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
+
+#[test]
+fn split_does_not_copy_other_pages_through_annotations() {
+    let (input, out_dir, parts) = split_every_page("annots", &[300_000, 1_000, 300_000]);
+
+    // page 2 links to page 1 and shares a form field with page 3
+    let doc = Document::load(&parts[1].path).unwrap();
+    assert!(parts[1].size < 10_000, "page 2 is {} bytes", parts[1].size);
+    let page_count = doc.objects.values().filter(|o| o.as_dict().and_then(|d| d.get(b"Type")).and_then(Object::as_name).ok() == Some(b"Page".as_slice())).count();
+    assert_eq!(page_count, 1);
+    std::fs::remove_file(input).unwrap();
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
+
+#[test]
+fn split_rejects_invalid_modes_before_reading_the_input() {
+    let missing = temp_path("missing.pdf");
+    for mode in [SplitMode::Chunks(0), SplitMode::Ranges(vec![]), SplitMode::Bookmarks(0), SplitMode::MaxSize(0)] {
+        let options = SplitOptions { mode: mode.clone(), ..Default::default() };
+        let split_error = pdf_tools::split(&missing, &temp_path("missing-out"), &options, &Progress::new()).unwrap_err();
+        let validate_error = pdf_tools::Operation::Split(options).validate().unwrap_err();
+        assert!(matches!(split_error, pdf_tools::Error::InvalidArgument(_)), "{:?}: {}", mode, split_error);
+        assert_eq!(split_error.to_string(), validate_error.to_string());
+    }
+}
+
+#[test]
+fn size_split_keeps_every_part_under_the_limit() {
+    let sizes = [40_000, 30_000, 30_000, 150_000, 10_000, 10_000, 10_000, 60_000];
+    let input = write_document("by-size.pdf", &sizes);
+    let out_dir = temp_path("by-size-out");
+    let limit = 100_000;
+    let options = SplitOptions { mode: SplitMode::MaxSize(limit), ..Default::default() };
+
+    let parts = pdf_tools::split(&input, &out_dir, &options, &Progress::new()).unwrap();
+
+    let pages: Vec<u32> = parts.iter().flat_map(|p| p.pages.clone()).collect();
+    assert_eq!(pages, (1..=sizes.len() as u32).collect::<Vec<_>>());
+    for part in &parts {
+        assert_eq!(std::fs::metadata(&part.path).unwrap().len(), part.size);
+        // only a page too large on its own may go over, alone in its part
+        if part.pages != [4] {
+            assert!(part.size <= limit, "pages {:?} take {} bytes", part.pages, part.size);
+        }
+    }
+    assert!(parts.iter().any(|p| p.pages == [4]));
+    assert!(parts.iter().any(|p| p.pages.len() > 1));
+    std::fs::remove_file(input).unwrap();
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
+
+#[test]
+fn blank_split_starts_parts_at_separator_pages() {
+    let input = write_document("blank.pdf", &[0; 7]);
+    // pages 3, 5 and 6 are separator sheets
+    let mut doc = Document::load(&input).unwrap();
+    let pages = doc.get_pages();
+    for n in [3, 5, 6] {
+        let empty = doc.add_object(Stream::new(dictionary! {}, b" \n".to_vec()));
+        doc.get_object_mut(pages[&n]).unwrap().as_dict_mut().unwrap().set("Contents", empty);
+    }
+    doc.save(&input).unwrap();
+
+    let split_blank = |drop: bool| {
+        let out_dir = temp_path(&format!("blank-out-{}", drop));
+        let options = SplitOptions { mode: SplitMode::BlankPages { render: false, drop }, ..Default::default() };
+        let parts = pdf_tools::split(&input, &out_dir, &options, &Progress::new()).unwrap();
+        let pages: Vec<Vec<u32>> = parts.iter().map(|p| p.pages.clone()).collect();
+        std::fs::remove_dir_all(out_dir).unwrap();
+        pages
+    };
+    assert_eq!(split_blank(false), [vec![1, 2], vec![3, 4], vec![5, 6, 7]]);
+    assert_eq!(split_blank(true), [vec![1, 2], vec![4], vec![7]]);
+    std::fs::remove_file(input).unwrap();
+}
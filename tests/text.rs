@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+use lopdf::{dictionary, Document, Object, ObjectId, Stream};
+use pdf_tools::{Error, Progress, TextFormat, TextOptions};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("pdf-tools-text-{}-{}", std::process::id(), name))
+}
+
+/// A document with one 200 by 300 point page per entry of `contents`, each drawing its
+/// content stream with a subset of Helvetica as `/F1`; the second page has `/Rotate 90`.
+fn write_document(name: &str, contents: &[&str]) -> PathBuf {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "ABCDEF+Helvetica" });
+    let page_ids: Vec<ObjectId> = contents
+        .iter()
+        .enumerate()
+        .map(|(i, content)| {
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content.as_bytes().to_vec()));
+            let mut page = dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "MediaBox" => vec![0.into(), 0.into(), 200.into(), 300.into()],
+                "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+                "Contents" => content_id,
+            };
+            if i == 1 {
+                page.set("Rotate", 90);
+            }
+            doc.add_object(page)
+        })
+        .collect();
+    let kids: Vec<Object> = page_ids.iter().map(|id| Object::Reference(*id)).collect();
+    doc.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => contents.len() as u32 }));
+    let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", catalog_id);
+
+    let path = temp_path(name);
+    doc.save(&path).unwrap();
+    path
+}
+
+#[test]
+fn csv_without_tables_fails_without_leaving_a_folder() {
+    let input = write_document("prose.pdf", &["BT /F1 10 Tf 20 250 Td (Only a sentence here.) Tj ET"]);
+    let output = std::env::temp_dir();
+    let options = TextOptions { format: TextFormat::Csv, ..TextOptions::default() };
+
+    let result = pdf_tools::extract_text(&input, &output, &options, &Progress::new());
+    // not a usage error: the options were fine, the document has nothing to give
+    assert!(matches!(result, Err(Error::Unsupported(_))), "{:?}", result);
+    assert!(!temp_path("prose-tables").exists());
+    std::fs::remove_file(&input).unwrap();
+}
+
+#[test]
+fn json_gives_runs_with_their_box_font_size_and_color() {
+    let input = write_document(
+        "runs.pdf",
+        &[
+            "BT /F1 10 Tf 1 0 0 rg 20 250 Td (Hello) Tj 0 g 0 -20 Td (world) Tj ET",
+            "BT /F1 12 Tf 20 150 Td (Sideways) Tj ET",
+        ],
+    );
+    let output = temp_path("runs.json");
+    let options = TextOptions { format: TextFormat::Json, ..TextOptions::default() };
+    pdf_tools::extract_text(&input, &output, &options, &Progress::new()).unwrap();
+
+    let json: serde_json::Value = serde_json::from_slice(&std::fs::read(&output).unwrap()).unwrap();
+    let pages = json["pages"].as_array().unwrap();
+    assert_eq!(pages.len(), 2);
+    let first = &pages[0];
+    assert_eq!((first["number"].as_u64(), first["width"].as_f64(), first["height"].as_f64()), (Some(1), Some(200.0), Some(300.0)));
+    assert_eq!(first["rotation"], 0);
+    // Helvetica without /Widths is 500 units wide a glyph; the box runs from the
+    // descent to the ascent, measured from the top of the page
+    assert_eq!(
+        first["runs"][0],
+        serde_json::json!({ "text": "Hello", "bbox": [20.0, 42.0, 45.0, 52.0], "font": "Helvetica", "size": 10.0, "color": "#ff0000" })
+    );
+    assert_eq!((first["runs"][1]["text"].as_str(), first["runs"][1]["color"].as_str()), (Some("world"), Some("#000000")));
+
+    let second = &pages[1];
+    assert_eq!((second["width"].as_f64(), second["height"].as_f64(), second["rotation"].as_i64()), (Some(300.0), Some(200.0), Some(90)));
+    assert_eq!(second["runs"][0]["text"], "Sideways");
+    std::fs::remove_file(&input).unwrap();
+    std::fs::remove_file(&output).unwrap();
+}
+
+#[test]
+fn per_page_writes_only_the_selected_pages() {
+    let contents = ["one", "two", "three"].map(|word| format!("BT /F1 10 Tf 20 250 Td (page {}) Tj ET", word));
+    let input = write_document("pages.pdf", &contents.each_ref().map(String::as_str));
+    let options = TextOptions { pages: Some(vec![1, 3]), per_page: true, ..TextOptions::default() };
+    pdf_tools::extract_text(&input, &std::env::temp_dir(), &options, &Progress::new()).unwrap();
+
+    let folder = temp_path("pages-text");
+    let mut names: Vec<String> = std::fs::read_dir(&folder).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+    names.sort();
+    assert_eq!(names, ["page-1.txt", "page-3.txt"]);
+    assert_eq!(std::fs::read_to_string(folder.join("page-3.txt")).unwrap(), "page three\n");
+
+    // a single file separates the selected pages with form feeds
+    let output = temp_path("pages.txt");
+    let options = TextOptions { pages: Some(vec![3, 1]), ..TextOptions::default() };
+    pdf_tools::extract_text(&input, &output, &options, &Progress::new()).unwrap();
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "page one\n\x0Cpage three\n");
+
+    let options = TextOptions { pages: Some(vec![4]), ..TextOptions::default() };
+    let result = pdf_tools::extract_text(&input, &output, &options, &Progress::new());
+    assert!(matches!(result, Err(Error::InvalidArgument(_))), "{:?}", result);
+    std::fs::remove_dir_all(&folder).unwrap();
+    std::fs::remove_file(&input).unwrap();
+    std::fs::remove_file(&output).unwrap();
+}